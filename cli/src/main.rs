@@ -111,6 +111,9 @@ pub enum SerializeOptions {
     Pretty,
     Json,
     NTriples,
+    Turtle,
+    Trig,
+    RdfXml,
 }
 
 impl Into<Format> for SerializeOptions {
@@ -119,6 +122,9 @@ impl Into<Format> for SerializeOptions {
             SerializeOptions::Pretty => Format::Pretty,
             SerializeOptions::Json => Format::Json,
             SerializeOptions::NTriples => Format::NTriples,
+            SerializeOptions::Turtle => Format::Turtle,
+            SerializeOptions::Trig => Format::TriG,
+            SerializeOptions::RdfXml => Format::RdfXml,
         }
     }
 }
@@ -20,7 +20,10 @@ pub fn new(context: &mut Context, class_input: &str) -> AtomicResult<()> {
         .unwrap()
         .try_mapping_or_url(class_input)
         .unwrap();
-    let class = context.store.get_class(&class_url)?;
+    let class = context
+        .store
+        .get_class(&class_url)?
+        .with_inherited(&context.store)?;
     println!("Enter a new {}: {}", class.shortname, class.description);
     let (resource, _bookmark) = prompt_instance(context, &class, None)?;
     println!(
@@ -165,6 +168,35 @@ fn prompt_field(
                 None => return Ok(None),
             }
         }
+        DataType::Decimal
+        | DataType::BigInt
+        | DataType::GeoPoint
+        | DataType::LangString
+        | DataType::Duration
+        | DataType::Time
+        | DataType::DateTime => {
+            let label = match property.data_type {
+                DataType::Decimal => "decimal",
+                DataType::BigInt => "big integer",
+                DataType::LangString => "translations as JSON, e.g. {\"en\": \"Hello\"}",
+                DataType::Duration => "duration, e.g. PT1H30M",
+                DataType::Time => "time, e.g. 09:30:00",
+                DataType::DateTime => "date and time, e.g. 2024-05-01T09:30:00+02:00",
+                _ => "location latitude,longitude",
+            };
+            let msg = format!("{}{}", label, msg_appendix);
+            let number: Option<String> = prompt_opt(msg)?;
+            match number {
+                Some(nr) => {
+                    if Value::new(&nr, &property.data_type).is_ok() {
+                        return Ok(Some(nr));
+                    }
+                    println!("Not a valid {}.", label);
+                    return Ok(None);
+                }
+                None => return Ok(None),
+            }
+        }
         DataType::Date => {
             let msg = format!("date YYYY-MM-DD{}", msg_appendix);
             let date: Option<String> = prompt_opt(msg).unwrap();
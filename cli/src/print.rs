@@ -37,7 +37,10 @@ pub fn print_resource(
         Format::Json => resource.to_json(&context.store)?,
         Format::JsonLd => resource.to_json_ld(&context.store)?,
         Format::JsonAd => resource.to_json_ad()?,
-        Format::NTriples => serialize::atoms_to_ntriples(resource.to_atoms(), &context.store)?,
+        Format::JsonAdCbor => return Err("Binary JSON-AD can not be printed".into()),
+        Format::NTriples | Format::Turtle | Format::TriG | Format::RdfXml => {
+            serialize::atoms_to_rdf(resource.to_atoms(), &format, &context.store)?
+        }
         Format::Pretty => pretty_print_resource(resource, &context.store)?,
     };
     println!("{}", out);
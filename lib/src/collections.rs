@@ -3,6 +3,7 @@
 use crate::{
     agents::ForAgent,
     errors::AtomicResult,
+    geo::{GeoArea, GeoFilter},
     storelike::{Query, ResourceCollection},
     urls, Resource, Storelike, Value,
 };
@@ -33,6 +34,10 @@ pub struct CollectionBuilder {
     pub include_nested: bool,
     /// Whether to include resources from other servers
     pub include_external: bool,
+    /// Whether to include instances of subclasses, if the collection filters by `isA`
+    pub include_subclasses: bool,
+    /// Only include resources with a location in some area
+    pub geo: Option<GeoFilter>,
 }
 
 impl CollectionBuilder {
@@ -64,6 +69,16 @@ impl CollectionBuilder {
                 store,
             )?;
         }
+        if self.include_subclasses {
+            resource.set_string(
+                crate::urls::COLLECTION_INCLUDE_SUBCLASSES.into(),
+                "true",
+                store,
+            )?;
+        }
+        if let Some(geo) = &self.geo {
+            set_geo_filter(&mut resource, geo, store)?;
+        }
         if self.sort_desc {
             resource.set_string(crate::urls::COLLECTION_SORT_DESC.into(), "true", store)?;
         }
@@ -98,6 +113,8 @@ impl CollectionBuilder {
             name: Some(format!("{} collection", path)),
             include_nested: true,
             include_external: false,
+            include_subclasses: false,
+            geo: None,
         }
     }
 
@@ -144,6 +161,10 @@ pub struct Collection {
     pub include_nested: bool,
     /// Include resources from other servers
     pub include_external: bool,
+    /// Include instances of subclasses, if the collection filters by `isA`
+    pub include_subclasses: bool,
+    /// Only includes resources with a location in some area
+    pub geo: Option<GeoFilter>,
 }
 
 /// Sorts a vector or resources by some property.
@@ -207,6 +228,8 @@ impl Collection {
             sort_desc: collection_builder.sort_desc,
             include_external: collection_builder.include_external,
             include_nested: collection_builder.include_nested,
+            include_subclasses: collection_builder.include_subclasses,
+            geo: collection_builder.geo.clone(),
             for_agent: for_agent.clone(),
         };
 
@@ -239,6 +262,8 @@ impl Collection {
             name: collection_builder.name,
             include_nested: collection_builder.include_nested,
             include_external: collection_builder.include_external,
+            include_subclasses: collection_builder.include_subclasses,
+            geo: collection_builder.geo,
         };
         Ok(collection)
     }
@@ -270,6 +295,9 @@ impl Collection {
         if self.include_nested {
             resource.set_string(crate::urls::COLLECTION_INCLUDE_NESTED.into(), "true", store)?;
         }
+        if let Some(geo) = &self.geo {
+            set_geo_filter(resource, geo, store)?;
+        }
         if self.include_external {
             resource.set_string(
                 crate::urls::COLLECTION_INCLUDE_EXTERNAL.into(),
@@ -277,6 +305,13 @@ impl Collection {
                 store,
             )?;
         }
+        if self.include_subclasses {
+            resource.set_string(
+                crate::urls::COLLECTION_INCLUDE_SUBCLASSES.into(),
+                "true",
+                store,
+            )?;
+        }
         if let Some(val) = &self.value {
             resource.set_string(crate::urls::COLLECTION_VALUE.into(), val, store)?;
         }
@@ -310,6 +345,21 @@ impl Collection {
     }
 }
 
+/// Adds the Property and the area of the [GeoFilter] to the Collection resource.
+fn set_geo_filter(
+    resource: &mut Resource,
+    geo: &GeoFilter,
+    store: &impl Storelike,
+) -> AtomicResult<()> {
+    resource.set_string(urls::COLLECTION_GEO_PROPERTY.into(), &geo.property, store)?;
+    let area_property = match geo.area {
+        GeoArea::BoundingBox { .. } => urls::COLLECTION_BOUNDING_BOX,
+        GeoArea::Radius { .. } => urls::COLLECTION_NEAR,
+    };
+    resource.set_string(area_property.into(), &geo.area.to_string(), store)?;
+    Ok(())
+}
+
 /// Builds a collection from query params and the passed Collection resource.
 /// The query params are used to override the stored Collection resource properties.
 /// This also sets defaults for Collection properties when fields are missing
@@ -329,6 +379,9 @@ pub fn construct_collection_from_params(
     let mut name = None;
     let mut include_nested = false;
     let mut include_external = false;
+    let mut include_subclasses = false;
+    let mut geo_property = None;
+    let mut area = None;
 
     if let Ok(val) = resource.get(urls::COLLECTION_PROPERTY) {
         property = Some(val.to_string());
@@ -348,6 +401,18 @@ pub fn construct_collection_from_params(
     if let Ok(val) = resource.get(urls::COLLECTION_INCLUDE_EXTERNAL) {
         include_external = val.to_bool()?;
     }
+    if let Ok(val) = resource.get(urls::COLLECTION_INCLUDE_SUBCLASSES) {
+        include_subclasses = val.to_bool()?;
+    }
+    if let Ok(val) = resource.get(urls::COLLECTION_GEO_PROPERTY) {
+        geo_property = Some(val.to_string());
+    }
+    if let Ok(val) = resource.get(urls::COLLECTION_BOUNDING_BOX) {
+        area = Some(GeoArea::parse_bounding_box(&val.to_string())?);
+    }
+    if let Ok(val) = resource.get(urls::COLLECTION_NEAR) {
+        area = Some(GeoArea::parse_radius(&val.to_string())?);
+    }
     for (k, v) in query_params {
         match k.as_ref() {
             "property" => property = Some(v.to_string()),
@@ -358,11 +423,24 @@ pub fn construct_collection_from_params(
             "page_size" => page_size = v.parse::<usize>()?,
             "include_nested" => include_nested = v.parse::<bool>()?,
             "include_external" => include_external = v.parse::<bool>()?,
+            "include_subclasses" => include_subclasses = v.parse::<bool>()?,
+            "geo_property" => geo_property = Some(v.to_string()),
+            "bounding_box" => area = Some(GeoArea::parse_bounding_box(&v)?),
+            "near" => area = Some(GeoArea::parse_radius(&v)?),
             e => {
                 return Err(format!("Invalid query param: {}", e).into());
             }
         };
     }
+    let geo = match (geo_property, area) {
+        (Some(property), Some(area)) => Some(GeoFilter { property, area }),
+        (None, Some(_)) => {
+            return Err(
+                "The `bounding_box` and `near` query params require a `geo_property`".into(),
+            )
+        }
+        (_, None) => None,
+    };
     let collection_builder = crate::collections::CollectionBuilder {
         subject: resource.get_subject().into(),
         property,
@@ -374,6 +452,8 @@ pub fn construct_collection_from_params(
         name,
         include_nested,
         include_external,
+        include_subclasses,
+        geo,
     };
     let collection = Collection::collect_members(store, collection_builder, for_agent)?;
     collection.add_to_resource(resource, store)
@@ -450,6 +530,8 @@ mod test {
             name: Some("Test collection".into()),
             include_nested: false,
             include_external: false,
+            include_subclasses: false,
+            geo: None,
         };
         let collection =
             Collection::collect_members(&store, collection_builder, &ForAgent::Sudo).unwrap();
@@ -471,6 +553,8 @@ mod test {
             name: None,
             include_nested: false,
             include_external: false,
+            include_subclasses: false,
+            geo: None,
         };
         let collection =
             Collection::collect_members(&store, collection_builder, &ForAgent::Sudo).unwrap();
@@ -498,6 +582,8 @@ mod test {
             // The important bit here
             include_nested: true,
             include_external: false,
+            include_subclasses: false,
+            geo: None,
         };
         let collection =
             Collection::collect_members(&store, collection_builder, &ForAgent::Sudo).unwrap();
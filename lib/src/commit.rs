@@ -30,7 +30,7 @@ pub struct CommitResponse {
 /// Describes options for applying a Commit.
 /// Skip the checks you don't need to get better performance, or if you want to break the rules a little.
 pub struct CommitOpts {
-    /// Makes sure all `required` properties are present.
+    /// Makes sure all `required` properties are present, and that values match the constraints of their Properties.
     pub validate_schema: bool,
     /// Checks the public key and the signature of the Commit.
     pub validate_signature: bool,
@@ -79,6 +79,10 @@ pub struct Commit {
     /// The previously applied commit to this Resource.
     #[serde(rename = "https://atomicdata.dev/properties/previousCommit")]
     pub previous_commit: Option<String>,
+    /// The default values that the Store added to a new Resource while applying the Commit, see [Resource::set_defaults].
+    /// Applied like `set`, but not part of the signature.
+    #[serde(rename = "https://atomicdata.dev/properties/defaults")]
+    pub defaults: Option<std::collections::HashMap<String, Value>>,
     /// The URL of the Commit
     pub url: Option<String>,
 }
@@ -102,6 +106,9 @@ impl Commit {
         }
 
         if opts.validate_signature {
+            if self.defaults.is_some() {
+                return Err("Commits can not contain `defaults`, these are added while applying the Commit.".into());
+            }
             let signature = match self.signature.as_ref() {
                 Some(sig) => sig,
                 None => return Err("No signature set".into()),
@@ -128,7 +135,6 @@ impl Commit {
         if opts.validate_timestamp {
             check_timestamp(self.created_at)?;
         }
-        let commit_resource: Resource = self.into_resource(store)?;
         let mut is_new = false;
         // Create a new resource if it doens't exist yet
         let mut resource_old = match store.get_resource(&self.subject) {
@@ -186,9 +192,33 @@ impl Commit {
                 hierarchy::check_write(store, &resource_old, &validate_for.into())?;
             }
         };
-        // Check if all required props are there
+        // New Resources get the default values of the Properties that the Commit omits.
+        // This happens after the rights checks, so rejected Commits don't advance counters, but before the schema checks, so defaults can fill required Properties.
+        // These are recorded in the stored Commit, so they can be replayed and are sent to subscribers.
+        let mut commit = self.clone();
+        if is_new && self.destroy != Some(true) {
+            let default_props = resource_new.set_defaults(store, Some(&self.signer))?;
+            if !default_props.is_empty() {
+                let mut defaults = commit.defaults.take().unwrap_or_default();
+                for prop in default_props {
+                    defaults.insert(prop.clone(), resource_new.get(&prop)?.clone());
+                }
+                commit.defaults = Some(defaults);
+            }
+        }
+        let commit_resource: Resource = commit.into_resource(store)?;
+
+        // Check if all required props are there, and if all values match their constraints
+        // The locks for unique values are held until the index is updated, so concurrent Commits can't claim the same value.
+        let mut unique_values_lock = Vec::new();
         if opts.validate_schema {
             resource_new.check_required_props(store)?;
+            resource_new.check_value_constraints(store)?;
+            resource_new.check_rules(store)?;
+            unique_values_lock = resource_new.lock_unique_values(store)?;
+            if !unique_values_lock.is_empty() {
+                resource_new.check_unique_values(store)?;
+            }
         }
 
         // Set the `lastCommit` to the newly created Commit
@@ -208,39 +238,62 @@ impl Commit {
                 urls::INVITE => {
                     crate::plugins::invite::before_apply_commit(store, self, &resource_new)?
                 }
+                urls::VALIDATION_RULE => crate::rules::before_apply_commit_rule(&resource_new)?,
+                urls::PROPERTY if !is_new && opts.validate_schema => {
+                    let for_agent = if opts.validate_rights {
+                        opts.validate_for_agent
+                            .as_ref()
+                            .unwrap_or(&self.signer)
+                            .into()
+                    } else {
+                        crate::agents::ForAgent::Sudo
+                    };
+                    crate::migrate::before_apply_commit_property(
+                        store,
+                        &resource_old,
+                        &resource_new,
+                        &for_agent,
+                    )?
+                }
                 _other => {}
             };
         }
 
-        // If a Destroy field is found, remove the resource and return early
-        // TODO: Should we remove the existing commits too? Probably.
-        if let Some(destroy) = self.destroy {
-            if destroy {
+        // Snapshots of the store wait until all changes of the Commit are written
+        let destroyed = store.hold_snapshots(|| -> AtomicResult<bool> {
+            // If a Destroy field is found, remove the resource and return early
+            // TODO: Should we remove the existing commits too? Probably.
+            if self.destroy == Some(true) {
                 // Note: the value index is updated before this action, in resource.apply_changes()
                 store.remove_resource(&self.subject)?;
                 store.add_resource_opts(&commit_resource, false, opts.update_index, false)?;
-                return Ok(CommitResponse {
-                    resource_new: None,
-                    resource_old: Some(resource_old),
-                    commit_resource,
-                    commit_struct: self.clone(),
-                });
+                return Ok(true);
             }
-        }
 
-        // We apply the changes again, but this time also update the index
-        self.apply_changes(resource_old.clone(), store, opts.update_index)?;
-
-        // Save the Commit to the Store. We can skip the required props checking, but we need to make sure the commit hasn't been applied before.
-        store.add_resource_opts(&commit_resource, false, opts.update_index, false)?;
-        // Save the resource, but skip updating the index - that has been done in a previous step.
-        store.add_resource_opts(&resource_new, false, false, true)?;
+            // We apply the changes again, but this time also update the index
+            commit.apply_changes(resource_old.clone(), store, opts.update_index)?;
+
+            // Save the Commit to the Store. We can skip the required props checking, but we need to make sure the commit hasn't been applied before.
+            store.add_resource_opts(&commit_resource, false, opts.update_index, false)?;
+            // Save the resource, but skip updating the index - that has been done in a previous step.
+            store.add_resource_opts(&resource_new, false, false, true)?;
+            Ok(false)
+        })?;
+        drop(unique_values_lock);
+        if destroyed {
+            return Ok(CommitResponse {
+                resource_new: None,
+                resource_old: Some(resource_old),
+                commit_resource,
+                commit_struct: commit,
+            });
+        }
 
         let commit_response = CommitResponse {
             resource_new: Some(resource_new.clone()),
             resource_old: Some(resource_old),
             commit_resource,
-            commit_struct: self.clone(),
+            commit_struct: commit,
         };
 
         store.handle_commit(&commit_response);
@@ -256,6 +309,15 @@ impl Commit {
                     self,
                     &resource_new,
                 )?,
+                urls::PROPERTY => {
+                    if let Some(resource_old) = &commit_response.resource_old {
+                        crate::migrate::after_apply_commit_property(
+                            store,
+                            resource_old,
+                            &resource_new,
+                        )?
+                    }
+                }
                 _other => {}
             };
         }
@@ -263,7 +325,7 @@ impl Commit {
         Ok(commit_response)
     }
 
-    /// Updates the values in the Resource according to the `set`, `remove`, `push`, and `destroy` attributes in the Commit.
+    /// Updates the values in the Resource according to the `set`, `defaults`, `remove`, `push`, and `destroy` attributes in the Commit.
     /// Optionally also updates the index in the Store.
     /// The Old Resource is only needed when `update_index` is true, and is used for checking
     #[tracing::instrument(skip(store))]
@@ -297,7 +359,7 @@ impl Commit {
                 }
             }
         }
-        if let Some(set) = self.set.clone() {
+        for set in [&self.set, &self.defaults].into_iter().flatten() {
             for (prop, new_val) in set.iter() {
                 resource
                     .set(prop.into(), new_val.to_owned(), store)
@@ -412,6 +474,10 @@ impl Commit {
             Ok(found) => Some(found.to_string()),
             Err(_) => None,
         };
+        let defaults = match resource.get(urls::DEFAULTS) {
+            Ok(found) => Some(found.to_nested()?.to_owned()),
+            Err(_) => None,
+        };
         let signature = resource.get(urls::SIGNATURE)?.to_string();
         let url = Some(resource.get_subject().into());
 
@@ -424,6 +490,7 @@ impl Commit {
             remove,
             destroy,
             previous_commit,
+            defaults,
             signature: Some(signature),
             url,
         })
@@ -464,6 +531,13 @@ impl Commit {
             }
             resource.set_unsafe(urls::SET.into(), newset.into());
         };
+        if let Some(defaults) = &self.defaults {
+            let mut newset = PropVals::new();
+            for (prop, val) in defaults {
+                newset.insert(prop.into(), val.clone());
+            }
+            resource.set_unsafe(urls::DEFAULTS.into(), newset.into());
+        }
         if let Some(remove) = &self.remove {
             if !remove.is_empty() {
                 resource.set_unsafe(urls::REMOVE.into(), remove.clone().into());
@@ -501,6 +575,7 @@ impl Commit {
 
     /// Generates a deterministic serialized JSON-AD representation of the Commit.
     /// Removes the signature from the object before serializing, since this function is used to check if the signature is correct.
+    /// The `defaults` are removed too, as they are added after signing.
     #[tracing::instrument(skip(store))]
     pub fn serialize_deterministically_json_ad(
         &self,
@@ -509,6 +584,7 @@ impl Commit {
         let mut commit_resource = self.into_resource(store)?;
         // A deterministic serialization should not contain the hash (signature), since that would influence the hash.
         commit_resource.remove_propval(urls::SIGNATURE);
+        commit_resource.remove_propval(urls::DEFAULTS);
         let json_obj =
             crate::serialize::propvals_to_json_ad_map(commit_resource.get_propvals(), None)?;
         let json = serde_jcs::to_string(&json_obj)
@@ -630,6 +706,7 @@ fn sign_at(
         previous_commit: commitbuilder.previous_commit,
         signature: None,
         push: Some(commitbuilder.push),
+        defaults: None,
         url: None,
     };
     let stringified = commit
@@ -751,6 +828,7 @@ mod test {
             previous_commit: None,
             destroy: Some(destroy),
             signature: None,
+            defaults: None,
             url: None,
         };
         let serialized = commit.serialize_deterministically_json_ad(&store).unwrap();
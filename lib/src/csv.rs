@@ -0,0 +1,315 @@
+//! Exports the instances of a Class to CSV, and imports CSV rows as new instances.
+//! The columns are the `requires` and `recommends` Properties of the Class, and use their shortnames as header.
+//! The `@id` column contains the subject. When importing, it is optional: rows without one get a new subject.
+//! Resource Arrays are written as JSON arrays, but can be imported as comma separated URLs too.
+
+use serde::Serialize;
+
+use crate::{
+    agents::ForAgent,
+    collections::{construct_collection_from_params, create_collection_resource_for_class},
+    datatype::DataType,
+    errors::AtomicResult,
+    parse::{add_parsed_to_index, save_parsed_resource, ParseOpts},
+    resources::PropVals,
+    schema::{Class, Property},
+    urls, Resource, Storelike, Value,
+};
+
+pub const CSV_MIME: &str = "text/csv";
+/// Header of the column that contains the subject of the Resource.
+pub const SUBJECT_COLUMN: &str = "@id";
+/// Amount of members that are fetched from the Collection at once.
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+/// The result of a CSV import. Rows that failed do not stop the import.
+#[derive(Debug, Default, Serialize)]
+pub struct CsvImportReport {
+    /// Subjects of the imported Resources
+    pub imported: Vec<String>,
+    pub errors: Vec<CsvRowError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CsvRowError {
+    /// The line in the CSV file, starting at 1 for the header.
+    pub line: u64,
+    pub error: String,
+}
+
+/// Returns the Properties that are used as columns for the Class, `requires` first.
+fn class_columns(store: &impl Storelike, class: &Class) -> AtomicResult<Vec<Property>> {
+    let mut columns: Vec<Property> = Vec::new();
+    for subject in class.requires.iter().chain(class.recommends.iter()) {
+        if !columns.iter().any(|p| &p.subject == subject) {
+            columns.push(store.get_property(subject)?);
+        }
+    }
+    Ok(columns)
+}
+
+/// Writes all instances of the Class that the `for_agent` can read to the `writer`.
+/// Returns the amount of exported Resources.
+#[tracing::instrument(skip(store, writer))]
+pub fn export_class_csv(
+    store: &impl Storelike,
+    class_subject: &str,
+    for_agent: &ForAgent,
+    writer: impl std::io::Write,
+) -> AtomicResult<usize> {
+    let class = store.get_class(class_subject)?.with_inherited(store)?;
+    let columns = class_columns(store, &class)?;
+    let mut csv_writer = csv::Writer::from_writer(writer);
+
+    let mut header = vec![SUBJECT_COLUMN.to_string()];
+    header.extend(columns.iter().map(|p| p.shortname.clone()));
+    csv_writer.write_record(&header)?;
+
+    let mut collection = create_collection_resource_for_class(store, class_subject)?;
+    let mut count = 0;
+    let mut current_page = 0;
+    loop {
+        let params = format!("current_page={current_page}&page_size={EXPORT_PAGE_SIZE}");
+        let page = construct_collection_from_params(
+            store,
+            url::form_urlencoded::parse(params.as_bytes()),
+            &mut collection,
+            for_agent,
+        )?;
+        for member in page.get(urls::COLLECTION_MEMBERS)?.to_subjects(None)? {
+            let resource = store.get_resource(&member)?;
+            let mut record = vec![member];
+            for property in columns.iter() {
+                record.push(match resource.get(&property.subject) {
+                    Ok(value) => value_to_cell(value)?,
+                    Err(_) => String::new(),
+                });
+            }
+            csv_writer.write_record(&record)?;
+            count += 1;
+        }
+        current_page += 1;
+        let total_pages = page.get(urls::COLLECTION_TOTAL_PAGES)?.to_int()?;
+        if current_page as i64 >= total_pages {
+            break;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(count)
+}
+
+/// Creates an instance of the Class for every row, below the `importer` of the `parse_opts`.
+/// Rows that can not be parsed or saved are added to the errors of the report.
+/// Fails if the header contains a column that is not a Property.
+#[tracing::instrument(skip(store, reader))]
+pub fn import_class_csv(
+    store: &impl Storelike,
+    class_subject: &str,
+    reader: impl std::io::Read,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<CsvImportReport> {
+    let parent = parse_opts
+        .importer
+        .as_ref()
+        .ok_or("A parent is required to import CSV")?;
+    let class = store.get_class(class_subject)?.with_inherited(store)?;
+    let class_props = class_columns(store, &class)?;
+
+    // Rows with a different amount of cells are reported per row, instead of failing the whole import
+    let mut csv_reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let mut subject_column = None;
+    let mut columns: Vec<Option<Property>> = Vec::new();
+    for (i, name) in csv_reader.headers()?.iter().enumerate() {
+        let name = name.trim();
+        if name == SUBJECT_COLUMN {
+            subject_column = Some(i);
+            columns.push(None);
+            continue;
+        }
+        let property = match class_props
+            .iter()
+            .find(|p| p.shortname == name || p.subject == name)
+        {
+            Some(property) => property.clone(),
+            None => store.get_property(name).map_err(|_| {
+                format!("Column '{}' is not a Property of {}", name, class.shortname)
+            })?,
+        };
+        columns.push(Some(property));
+    }
+
+    let mut report = CsvImportReport::default();
+    let mut resources = Vec::new();
+    for record in csv_reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) if !matches!(e.kind(), csv::ErrorKind::Io(_)) => {
+                report.errors.push(CsvRowError {
+                    line: e.position().map(|p| p.line()).unwrap_or_default(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        if record.len() != columns.len() {
+            report.errors.push(CsvRowError {
+                line,
+                error: format!(
+                    "Row has {} cells, but the header has {} columns",
+                    record.len(),
+                    columns.len()
+                ),
+            });
+            continue;
+        }
+        let result = row_to_propvals(&record, &columns, &class, parent).and_then(|propvals| {
+            let subject = match subject_column.and_then(|i| record.get(i)) {
+                Some(subject) if !subject.trim().is_empty() => subject.trim().to_string(),
+                _ => Resource::new_instance_subject(class_subject, store)?,
+            };
+            save_parsed_resource(subject, propvals, store, parse_opts)
+        });
+        match result {
+            Ok(resource) => {
+                report.imported.push(resource.get_subject().clone());
+                resources.push(resource);
+            }
+            Err(e) => report.errors.push(CsvRowError {
+                line,
+                error: e.to_string(),
+            }),
+        }
+    }
+    add_parsed_to_index(&resources, store, parse_opts)?;
+    Ok(report)
+}
+
+fn row_to_propvals(
+    record: &csv::StringRecord,
+    columns: &[Option<Property>],
+    class: &Class,
+    parent: &str,
+) -> AtomicResult<PropVals> {
+    let mut propvals = PropVals::new();
+    propvals.insert(
+        urls::IS_A.into(),
+        Value::ResourceArray(vec![class.subject.as_str().into()]),
+    );
+    propvals.insert(urls::PARENT.into(), Value::AtomicUrl(parent.into()));
+    for (cell, property) in record.iter().zip(columns.iter()) {
+        let Some(property) = property else {
+            continue;
+        };
+        if cell.trim().is_empty() {
+            continue;
+        }
+        let value = cell_to_value(cell, property)
+            .map_err(|e| format!("Invalid value for '{}': {}", property.shortname, e))?;
+        propvals.insert(property.subject.clone(), value);
+    }
+    for required in class.requires.iter() {
+        if !propvals.contains_key(required) {
+            let name = columns
+                .iter()
+                .flatten()
+                .find(|p| &p.subject == required)
+                .map(|p| p.shortname.as_str())
+                .unwrap_or(required);
+            return Err(format!("Missing required value for '{}'", name).into());
+        }
+    }
+    Ok(propvals)
+}
+
+fn value_to_cell(value: &Value) -> AtomicResult<String> {
+    match value {
+        Value::ResourceArray(_) => Ok(serde_json::to_string(&value.to_subjects(None)?)?),
+        other => Ok(other.to_string()),
+    }
+}
+
+fn cell_to_value(cell: &str, property: &Property) -> AtomicResult<Value> {
+    let cell = cell.trim();
+    if property.data_type == DataType::ResourceArray && !cell.starts_with('[') {
+        let subjects: Vec<String> = cell
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        return Ok(subjects.into());
+    }
+    Value::new(cell, &property.data_type)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse::SaveOpts, Store};
+
+    #[test]
+    fn csv_roundtrip() {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        let agent = store.create_agent(None).unwrap();
+        store.set_default_agent(agent.clone());
+        let parent = format!("{}/csv-import", store.get_server_url());
+        let parse_opts = ParseOpts {
+            importer: Some(parent.clone()),
+            for_agent: ForAgent::Sudo,
+            signer: Some(agent),
+            save: SaveOpts::Commit,
+            overwrite_outside: false,
+        };
+
+        let csv = "shortname,description,datatype,classtype\n\
+            first-prop,The first one,https://atomicdata.dev/datatypes/string,\n\
+            second-prop,The second one,https://atomicdata.dev/datatypes/integer,\n\
+            ,Missing a shortname,https://atomicdata.dev/datatypes/string,\n\
+            bad-datatype,Not a URL,not a url,\n\
+            too-short,Missing cells\n";
+        let report = import_class_csv(&store, urls::PROPERTY, csv.as_bytes(), &parse_opts).unwrap();
+        assert_eq!(report.imported.len(), 2, "{:?}", report.errors);
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(report.errors[0].line, 4);
+        assert!(report.errors[0].error.contains("shortname"));
+        assert_eq!(report.errors[1].line, 5);
+        assert_eq!(report.errors[2].line, 6);
+        assert!(report.errors[2].error.contains("2 cells"));
+
+        let imported = store.get_property(&report.imported[1]).unwrap();
+        assert_eq!(imported.data_type, DataType::Integer);
+        assert_eq!(
+            store
+                .get_resource(&report.imported[0])
+                .unwrap()
+                .get(urls::PARENT)
+                .unwrap()
+                .to_string(),
+            parent
+        );
+
+        let mut exported = Vec::new();
+        let count =
+            export_class_csv(&store, urls::PROPERTY, &ForAgent::Sudo, &mut exported).unwrap();
+        let exported = String::from_utf8(exported).unwrap();
+        assert!(count > 2);
+        assert!(exported.starts_with("@id,shortname,datatype,description,"));
+        assert!(exported.contains(&format!("{},second-prop,", report.imported[1])));
+    }
+
+    #[test]
+    fn unknown_column() {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        let parse_opts = ParseOpts {
+            importer: Some("https://localhost/csv-import".into()),
+            save: SaveOpts::Save,
+            ..Default::default()
+        };
+        let csv = "shortname,nonsense\nname,value\n";
+        assert!(import_class_csv(&store, urls::CLASS, csv.as_bytes(), &parse_opts).is_err());
+    }
+}
@@ -18,38 +18,84 @@ pub enum DataType {
     String,
     Timestamp,
     Unsupported(String),
+    // New variants are added at the end, because the order is part of the stored (bincode) format.
+    /// Decimal number with arbitrary precision, e.g. for money
+    Decimal,
+    /// Integer of any size
+    BigInt,
+    /// A point on earth, see [crate::geo::GeoPoint]
+    GeoPoint,
+    /// Text in one or more languages, see [crate::lang::LangString]
+    LangString,
+    /// ISO 8601 duration, e.g. `PT1H30M`
+    Duration,
+    /// Local time of day, e.g. `09:30:00`
+    Time,
+    /// ISO 8601 date and time with a time zone, e.g. `2024-05-01T09:30:00+02:00`
+    DateTime,
 }
 
 pub fn match_datatype(string: &str) -> DataType {
     match string {
         urls::ATOMIC_URL => DataType::AtomicUrl,
+        urls::BIG_INT => DataType::BigInt,
         urls::BOOLEAN => DataType::Boolean,
         urls::DATE => DataType::Date,
+        urls::DATE_TIME => DataType::DateTime,
+        urls::DECIMAL => DataType::Decimal,
+        urls::DURATION => DataType::Duration,
         urls::INTEGER => DataType::Integer,
+        urls::LANG_STRING => DataType::LangString,
         urls::FLOAT => DataType::Float,
+        urls::GEO_POINT => DataType::GeoPoint,
         urls::MARKDOWN => DataType::Markdown,
         urls::RESOURCE_ARRAY => DataType::ResourceArray,
         urls::SLUG => DataType::Slug,
         urls::STRING => DataType::String,
+        urls::TIME => DataType::Time,
         urls::TIMESTAMP => DataType::Timestamp,
         unsupported_datatype => DataType::Unsupported(unsupported_datatype.into()),
     }
 }
 
+impl DataType {
+    /// The datatype URL to use for literals in RDF serializations.
+    /// Uses XSD datatypes where RDF tools expect them, and the Atomic Datatype otherwise.
+    pub fn rdf_datatype(&self) -> String {
+        match self {
+            DataType::BigInt => "http://www.w3.org/2001/XMLSchema#integer".into(),
+            DataType::DateTime => "http://www.w3.org/2001/XMLSchema#dateTime".into(),
+            DataType::Decimal => "http://www.w3.org/2001/XMLSchema#decimal".into(),
+            DataType::Duration => "http://www.w3.org/2001/XMLSchema#duration".into(),
+            DataType::GeoPoint => "http://www.opengis.net/ont/geosparql#wktLiteral".into(),
+            DataType::LangString => "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString".into(),
+            DataType::Time => "http://www.w3.org/2001/XMLSchema#time".into(),
+            other => other.to_string(),
+        }
+    }
+}
+
 impl std::str::FromStr for DataType {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             urls::ATOMIC_URL => DataType::AtomicUrl,
+            urls::BIG_INT => DataType::BigInt,
             urls::BOOLEAN => DataType::Boolean,
             urls::DATE => DataType::Date,
+            urls::DATE_TIME => DataType::DateTime,
+            urls::DECIMAL => DataType::Decimal,
+            urls::DURATION => DataType::Duration,
             urls::INTEGER => DataType::Integer,
+            urls::LANG_STRING => DataType::LangString,
             urls::FLOAT => DataType::Float,
+            urls::GEO_POINT => DataType::GeoPoint,
             urls::MARKDOWN => DataType::Markdown,
             urls::RESOURCE_ARRAY => DataType::ResourceArray,
             urls::SLUG => DataType::Slug,
             urls::STRING => DataType::String,
+            urls::TIME => DataType::Time,
             urls::TIMESTAMP => DataType::Timestamp,
             unsupported_datatype => DataType::Unsupported(unsupported_datatype.into()),
         })
@@ -60,14 +106,21 @@ impl fmt::Display for DataType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DataType::AtomicUrl => write!(f, "{}", urls::ATOMIC_URL),
+            DataType::BigInt => write!(f, "{}", urls::BIG_INT),
             DataType::Boolean => write!(f, "{}", urls::BOOLEAN),
             DataType::Date => write!(f, "{}", urls::DATE),
+            DataType::DateTime => write!(f, "{}", urls::DATE_TIME),
+            DataType::Decimal => write!(f, "{}", urls::DECIMAL),
+            DataType::Duration => write!(f, "{}", urls::DURATION),
+            DataType::GeoPoint => write!(f, "{}", urls::GEO_POINT),
             DataType::Integer => write!(f, "{}", urls::INTEGER),
             DataType::Float => write!(f, "{}", urls::FLOAT),
+            DataType::LangString => write!(f, "{}", urls::LANG_STRING),
             DataType::Markdown => write!(f, "{}", urls::MARKDOWN),
             DataType::ResourceArray => write!(f, "{}", urls::RESOURCE_ARRAY),
             DataType::Slug => write!(f, "{}", urls::SLUG),
             DataType::String => write!(f, "{}", urls::STRING),
+            DataType::Time => write!(f, "{}", urls::TIME),
             DataType::Timestamp => write!(f, "{}", urls::TIMESTAMP),
             DataType::Unsupported(url) => write!(f, "{}", url),
         }
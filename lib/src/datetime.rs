@@ -0,0 +1,254 @@
+//! ISO 8601 date-times with a time zone, times of day and durations.
+//! Values keep the notation they were created with.
+//! These functions validate them, and convert them to nanoseconds so that they can be compared and sorted.
+
+use regex::Regex;
+
+use crate::errors::AtomicResult;
+
+/// RFC 3339 date-time with a required time zone, e.g. `2024-05-01T09:30:00+02:00` or `2024-05-01T07:30:00.5Z`
+pub const DATE_TIME_REGEX: &str = r"^([0-9]{4})-([0-9]{2})-([0-9]{2})T([0-9]{2}):([0-9]{2}):([0-9]{2})(\.[0-9]{1,9})?(Z|([+-])([0-9]{2}):([0-9]{2}))$";
+/// Local time of day, e.g. `09:30:00` or `23:59:59.999`
+pub const TIME_REGEX: &str = r"^([0-9]{2}):([0-9]{2}):([0-9]{2})(\.[0-9]{1,9})?$";
+/// Duration as in `xsd:duration`, e.g. `P1Y2M`, `PT1H30M` or `-P3DT0.5S`
+pub const DURATION_REGEX: &str = r"^(-)?P(?:([0-9]+)Y)?(?:([0-9]+)M)?(?:([0-9]+)D)?(?:T(?:([0-9]+)H)?(?:([0-9]+)M)?(?:([0-9]+)(\.[0-9]{1,9})?S)?)?$";
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+/// Average lengths of a year and a month in the Gregorian calendar, used to compare durations.
+const SECONDS_PER_YEAR: i128 = 31_556_952;
+const SECONDS_PER_MONTH: i128 = SECONDS_PER_YEAR / 12;
+
+/// Parses a date-time with a time zone, and returns the nanoseconds since 1970-01-01T00:00:00Z.
+pub fn parse_date_time(value: &str) -> AtomicResult<i128> {
+    let invalid = || {
+        format!(
+            "Not a valid date-time: {}. Use a format like 2024-05-01T09:30:00+02:00, including the time zone.",
+            value
+        )
+    };
+    let re = Regex::new(DATE_TIME_REGEX).unwrap();
+    let caps = re.captures(value).ok_or_else(invalid)?;
+    let number = |i: usize| -> AtomicResult<i64> {
+        caps.get(i).map_or(Ok(0), |m| {
+            m.as_str().parse::<i64>().map_err(|_| invalid().into())
+        })
+    };
+    let (year, month, day) = (number(1)?, number(2)?, number(3)?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(invalid().into());
+    }
+    let seconds = seconds_of_day(number(4)?, number(5)?, number(6)?).ok_or_else(invalid)?;
+    let (offset_hours, offset_minutes) = (number(10)?, number(11)?);
+    if offset_hours > 23 || offset_minutes > 59 {
+        return Err(invalid().into());
+    }
+    let mut offset = offset_hours * 3600 + offset_minutes * 60;
+    if caps.get(9).map(|m| m.as_str()) == Some("-") {
+        offset = -offset;
+    }
+    let utc_seconds = days_from_civil(year, month, day) * 86_400 + seconds - offset;
+    Ok(utc_seconds as i128 * NANOS_PER_SECOND + fraction_nanos(caps.get(7).map(|m| m.as_str())))
+}
+
+/// Parses a local time of day, and returns the nanoseconds since midnight.
+pub fn parse_time(value: &str) -> AtomicResult<i128> {
+    let invalid = || {
+        format!(
+            "Not a valid time: {}. Use a format like 09:30:00, without time zone.",
+            value
+        )
+    };
+    let re = Regex::new(TIME_REGEX).unwrap();
+    let caps = re.captures(value).ok_or_else(invalid)?;
+    let number =
+        |i: usize| -> AtomicResult<i64> { caps[i].parse::<i64>().map_err(|_| invalid().into()) };
+    let seconds = seconds_of_day(number(1)?, number(2)?, number(3)?).ok_or_else(invalid)?;
+    Ok(seconds as i128 * NANOS_PER_SECOND + fraction_nanos(caps.get(4).map(|m| m.as_str())))
+}
+
+/// Parses a duration, and returns its length in nanoseconds.
+/// Years and months have no fixed length, so these use the average length in the Gregorian calendar.
+pub fn parse_duration(value: &str) -> AtomicResult<i128> {
+    let invalid = || {
+        format!(
+            "Not a valid duration: {}. Use a format like P1DT2H30M, or PT0.5S.",
+            value
+        )
+    };
+    let re = Regex::new(DURATION_REGEX).unwrap();
+    let caps = re.captures(value).ok_or_else(invalid)?;
+    // `P` and `T` have to be followed by at least one component
+    if value.ends_with('P') || value.ends_with('T') {
+        return Err(invalid().into());
+    }
+    let units = [
+        (2, SECONDS_PER_YEAR),
+        (3, SECONDS_PER_MONTH),
+        (4, 86_400),
+        (5, 3600),
+        (6, 60),
+        (7, 1),
+    ];
+    let mut seconds: i128 = 0;
+    for (i, unit) in units {
+        if let Some(m) = caps.get(i) {
+            seconds = m
+                .as_str()
+                .parse::<i128>()
+                .ok()
+                .and_then(|n| n.checked_mul(unit))
+                .and_then(|n| n.checked_add(seconds))
+                .ok_or_else(|| format!("Duration is too long: {}", value))?;
+        }
+    }
+    let nanos = seconds
+        .checked_mul(NANOS_PER_SECOND)
+        .ok_or_else(|| format!("Duration is too long: {}", value))?
+        + fraction_nanos(caps.get(8).map(|m| m.as_str()));
+    Ok(if caps.get(1).is_some() { -nanos } else { nanos })
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of the number of days since 1970-01-01. The inverse of [days_from_civil].
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Formats a [crate::Value::Timestamp] (milliseconds since the Unix Epoch) as a date-time in UTC, e.g. `2020-08-07T11:15:19.5Z`.
+pub fn timestamp_to_date_time(milliseconds: i64) -> String {
+    let seconds = milliseconds.div_euclid(1000);
+    let millis = milliseconds.rem_euclid(1000);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let second_of_day = seconds.rem_euclid(86_400);
+    let mut date_time = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        second_of_day / 3600,
+        second_of_day % 3600 / 60,
+        second_of_day % 60
+    );
+    if millis > 0 {
+        date_time.push_str(format!(".{:03}", millis).trim_end_matches('0'));
+    }
+    date_time.push('Z');
+    date_time
+}
+
+/// The amount of days in a month of the (proleptic) Gregorian calendar.
+pub fn days_in_month(year: i64, month: i64) -> i64 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)
+}
+
+fn seconds_of_day(hours: i64, minutes: i64, seconds: i64) -> Option<i64> {
+    if hours > 23 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Converts a fraction of a second like `.25` to nanoseconds.
+fn fraction_nanos(fraction: Option<&str>) -> i128 {
+    let Some(digits) = fraction.map(|f| f.trim_start_matches('.')) else {
+        return 0;
+    };
+    format!("{:0<9}", digits).parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_date_times_times_and_durations() {
+        assert_eq!(parse_date_time("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_date_time("1970-01-01T02:00:00.5+02:00").unwrap(),
+            NANOS_PER_SECOND / 2
+        );
+        assert_eq!(
+            parse_date_time("1969-12-31T19:00:00-05:00").unwrap(),
+            parse_date_time("1970-01-01T00:00:00Z").unwrap()
+        );
+        assert_eq!(
+            parse_date_time("2000-03-01T01:02:03Z").unwrap(),
+            951_872_523 * NANOS_PER_SECOND
+        );
+        parse_date_time("2024-02-29T12:00:00Z").unwrap();
+        parse_date_time("2023-02-29T12:00:00Z").unwrap_err();
+        parse_date_time("2024-05-01T09:30:00").unwrap_err();
+        parse_date_time("2024-05-01T24:00:00Z").unwrap_err();
+        parse_date_time("2024-05-01").unwrap_err();
+
+        assert_eq!(
+            parse_time("01:00:01.25").unwrap(),
+            3601 * NANOS_PER_SECOND + NANOS_PER_SECOND / 4
+        );
+        parse_time("09:30").unwrap_err();
+        parse_time("09:60:00").unwrap_err();
+        parse_time("09:30:00Z").unwrap_err();
+        // Only ASCII digits are allowed
+        parse_time("٠٩:30:00").unwrap_err();
+        parse_date_time("２０２４-05-01T09:30:00Z").unwrap_err();
+        parse_duration("PT١H").unwrap_err();
+
+        assert_eq!(parse_duration("PT1H30M").unwrap(), 5400 * NANOS_PER_SECOND);
+        assert_eq!(
+            parse_duration("-P1DT0.5S").unwrap(),
+            -(86_400 * NANOS_PER_SECOND + NANOS_PER_SECOND / 2)
+        );
+        assert_eq!(
+            parse_duration("P1Y").unwrap(),
+            parse_duration("P12M").unwrap()
+        );
+        parse_duration("P").unwrap_err();
+        parse_duration("P1DT").unwrap_err();
+        parse_duration("PT1M1H").unwrap_err();
+        parse_duration("P1W").unwrap_err();
+        parse_duration("1H").unwrap_err();
+
+        for days in [-719_468, -1, 0, 10_957, 11_016, 19_844] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(timestamp_to_date_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            timestamp_to_date_time(1_596_799_119_500),
+            "2020-08-07T11:18:39.5Z"
+        );
+        assert_eq!(
+            parse_date_time(&timestamp_to_date_time(-1)).unwrap(),
+            -1_000_000
+        );
+    }
+}
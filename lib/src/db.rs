@@ -1,6 +1,7 @@
 //! Persistent, ACID compliant, threadsafe to-disk store.
 //! Powered by Sled - an embedded database.
 
+mod geo_index;
 mod migrations;
 mod prop_val_sub_index;
 mod query_index;
@@ -26,13 +27,15 @@ use crate::{
     },
     endpoints::{default_endpoints, Endpoint, HandleGetContext},
     errors::{AtomicError, AtomicResult},
+    geo::{GeoArea, GeoFilter},
     resources::PropVals,
     storelike::{Query, QueryResult, Storelike},
     values::SortableValue,
-    Atom, Resource,
+    Atom, Resource, Value,
 };
 
 use self::{
+    geo_index::{add_point_to_geo_index, find_in_geo_index, remove_point_from_geo_index},
     migrations::migrate_maybe,
     prop_val_sub_index::{
         add_atom_to_prop_val_sub_index, find_in_prop_val_sub_index,
@@ -79,6 +82,12 @@ pub struct Db {
     query_index: sled::Tree,
     /// A list of all the Collections currently being used. Is used to update `query_index`.
     watched_queries: sled::Tree,
+    /// Spatial index of [crate::geo::GeoPoint] values, used for Queries with a [GeoFilter].
+    geo_index: sled::Tree,
+    /// The last values of counters, by key. See [Storelike::next_counter].
+    counters: sled::Tree,
+    /// Held while writing, and exclusively while creating a [Db::snapshot], so a snapshot never contains half of a write.
+    writes: Arc<parking_lot::RwLock<()>>,
     /// The address where the db will be hosted, e.g. http://localhost/
     server_url: String,
     /// Endpoints are checked whenever a resource is requested. They calculate (some properties of) the resource and return it.
@@ -98,6 +107,8 @@ impl Db {
         let query_index = db.open_tree("members_index")?;
         let prop_val_sub_index = db.open_tree("prop_val_sub_index")?;
         let watched_queries = db.open_tree("watched_queries")?;
+        let geo_index = db.open_tree("geo_index")?;
+        let counters = db.open_tree("counters")?;
         let store = Db {
             db,
             default_agent: Arc::new(Mutex::new(None)),
@@ -107,6 +118,9 @@ impl Db {
             prop_val_sub_index,
             server_url,
             watched_queries,
+            geo_index,
+            counters,
+            writes: Arc::new(parking_lot::RwLock::new(())),
             endpoints: default_endpoints(),
             on_commit: None,
         };
@@ -189,13 +203,52 @@ impl Db {
 
     /// Removes all values from the indexes.
     pub fn clear_index(&self) -> AtomicResult<()> {
+        let _writing = self.writes.read_recursive();
         self.reference_index.clear()?;
         self.prop_val_sub_index.clear()?;
         self.query_index.clear()?;
         self.watched_queries.clear()?;
+        self.geo_index.clear()?;
         Ok(())
     }
 
+    /// Copies all Trees of this store to a new Sled database at `path`, and returns the amount of copied Resources.
+    /// This can run while the server is serving requests, but writes wait until the snapshot is done.
+    /// Changes that are written together, such as those of a Commit, are all in the snapshot or none of them are, see [Storelike::hold_snapshots].
+    /// The resulting folder can be opened with [Db::init].
+    #[instrument(skip(self))]
+    pub fn snapshot(&self, path: &std::path::Path) -> AtomicResult<usize> {
+        self.snapshot_with(path, || Ok(()))
+    }
+
+    /// Same as [Db::snapshot], but also runs `while_snapshotting` before writes can continue.
+    /// Use it to copy files that belong to Resources, such as uploads, so the copy matches the snapshot.
+    #[instrument(skip(self, while_snapshotting))]
+    pub fn snapshot_with(
+        &self,
+        path: &std::path::Path,
+        while_snapshotting: impl FnOnce() -> AtomicResult<()>,
+    ) -> AtomicResult<usize> {
+        if path.exists() {
+            return Err(format!("Snapshot path {:?} already exists", path).into());
+        }
+        let _no_writes = self.writes.write();
+        self.db.flush()?;
+        let target = sled::open(path)
+            .map_err(|e| format!("Failed creating snapshot at {:?}. {}", path, e))?;
+        for name in self.db.tree_names() {
+            let source = self.db.open_tree(&name)?;
+            let destination = target.open_tree(&name)?;
+            for item in source.iter() {
+                let (key, value) = item?;
+                destination.insert(key, value)?;
+            }
+        }
+        target.flush()?;
+        while_snapshotting()?;
+        Ok(target.open_tree("resources_v1")?.len())
+    }
+
     fn map_sled_item_to_resource(
         item: Result<(sled::IVec, sled::IVec), sled::Error>,
         self_url: String,
@@ -292,6 +345,93 @@ impl Db {
         })
     }
 
+    /// Finds the Resources in the area of the [GeoFilter] using the spatial index, and then applies the other filters of the Query.
+    /// Results are sorted by distance for a radius, unless the Query has a `sort_by`.
+    fn query_geo(&self, q: &Query, geo: &GeoFilter) -> AtomicResult<QueryResult> {
+        let self_url = self
+            .get_self_url()
+            .ok_or("No self_url set, required for Queries")?;
+
+        let mut hits = find_in_geo_index(self, &geo.property, &geo.area)?;
+        hits.retain(|(subject, _point)| q.include_external || subject.starts_with(&self_url));
+        match &geo.area {
+            GeoArea::Radius { center, .. } => {
+                hits.sort_by(|(_, a), (_, b)| center.distance(a).total_cmp(&center.distance(b)))
+            }
+            GeoArea::BoundingBox { .. } => hits.sort_by(|(a, _), (b, _)| a.cmp(b)),
+        }
+
+        // Like in the query index, `start_val` and `end_val` apply to the value that is sorted by, or else to the filtered Property
+        let range_property = q.sort_by.as_ref().or(q.property.as_ref());
+        let in_range = |resource: &Resource| {
+            let Some(prop) = range_property else {
+                return true;
+            };
+            let value: SortableValue = match resource.get(prop) {
+                Ok(val) => val.to_sortable_string(),
+                Err(_e) => NO_VALUE.to_string(),
+            };
+            q.start_val
+                .as_ref()
+                .is_none_or(|start| value >= start.to_sortable_string())
+                && q.end_val
+                    .as_ref()
+                    .is_none_or(|end| value < end.to_sortable_string())
+        };
+
+        let mut matches: Vec<Resource> = Vec::new();
+        for (subject, _point) in hits {
+            // The index can still point to a Resource that was removed
+            let Ok(resource) = self.get_resource(&subject) else {
+                continue;
+            };
+            let is_match = match (&q.property, &q.value) {
+                (Some(prop), Some(val)) => resource
+                    .get(prop)
+                    .map(|v| v.contains_value(val))
+                    .unwrap_or(false),
+                (Some(prop), None) => resource.get(prop).is_ok(),
+                (None, Some(val)) => resource
+                    .get_propvals()
+                    .values()
+                    .any(|v| v.contains_value(val)),
+                (None, None) => true,
+            };
+            if is_match && in_range(&resource) {
+                matches.push(resource);
+            }
+        }
+        if let Some(sort_by) = &q.sort_by {
+            matches = crate::collections::sort_resources(matches, sort_by, q.sort_desc);
+        }
+
+        let count = matches.len();
+        let mut subjects = Vec::new();
+        let mut resources = Vec::new();
+        for resource in matches
+            .into_iter()
+            .skip(q.offset)
+            .take(q.limit.unwrap_or(usize::MAX))
+        {
+            if !should_include_resource(q) {
+                subjects.push(resource.get_subject().clone());
+                continue;
+            }
+            if let Ok(resource) =
+                self.get_resource_extended(resource.get_subject(), true, &q.for_agent)
+            {
+                subjects.push(resource.get_subject().clone());
+                resources.push(resource);
+            }
+        }
+
+        Ok(QueryResult {
+            subjects,
+            resources,
+            count,
+        })
+    }
+
     fn query_complex(&self, q: &Query) -> AtomicResult<QueryResult> {
         let (mut subjects, mut resources, mut total_count) = query_sorted_indexed(self, q)?;
         let q_filter: QueryFilter = q.into();
@@ -350,9 +490,13 @@ impl Storelike for Db {
 
     #[instrument(skip(self))]
     fn add_atom_to_index(&self, atom: &Atom, resource: &Resource) -> AtomicResult<()> {
+        let _writing = self.writes.read_recursive();
         for index_atom in atom.to_indexable_atoms() {
             add_atom_to_reference_index(&index_atom, self)?;
             add_atom_to_prop_val_sub_index(&index_atom, self)?;
+            if let Value::GeoPoint(point) = &atom.value {
+                add_point_to_geo_index(self, &atom.property, &atom.subject, point)?;
+            }
             // Also update the query index to keep collections performant
             check_if_atom_matches_watched_query_filters(self, &index_atom, atom, false, resource)
                 .map_err(|e| {
@@ -370,6 +514,7 @@ impl Storelike for Db {
         update_index: bool,
         overwrite_existing: bool,
     ) -> AtomicResult<()> {
+        let _writing = self.writes.read_recursive();
         // This only works if no external functions rely on using add_resource for atom-like operations!
         // However, add_atom uses set_propvals, which skips the validation.
         let existing = self.get_propvals(resource.get_subject()).ok();
@@ -405,9 +550,13 @@ impl Storelike for Db {
 
     #[instrument(skip(self))]
     fn remove_atom_from_index(&self, atom: &Atom, resource: &Resource) -> AtomicResult<()> {
+        let _writing = self.writes.read_recursive();
         for index_atom in atom.to_indexable_atoms() {
             remove_atom_from_reference_index(&index_atom, self)?;
             remove_atom_from_prop_val_sub_index(&index_atom, self)?;
+            if let Value::GeoPoint(point) = &atom.value {
+                remove_point_from_geo_index(self, &atom.property, &atom.subject, point)?;
+            }
 
             check_if_atom_matches_watched_query_filters(self, &index_atom, atom, true, resource)
                 .map_err(|e| format!("Checking atom went wrong: {}", e))?;
@@ -445,6 +594,43 @@ impl Storelike for Db {
         }
     }
 
+    fn hold_snapshots<T>(&self, write: impl FnOnce() -> AtomicResult<T>) -> AtomicResult<T> {
+        let _writing = self.writes.read_recursive();
+        write()
+    }
+
+    fn get_resource_local(&self, subject: &str) -> AtomicResult<Resource> {
+        let propvals = self.get_propvals(subject)?;
+        Ok(Resource::from_propvals(propvals, subject.into()))
+    }
+
+    fn next_counter(&self, key: &str, start: &dyn Fn() -> AtomicResult<i64>) -> AtomicResult<i64> {
+        let _writing = self.writes.read_recursive();
+        if !self.counters.contains_key(key)? {
+            // Only sets the start if no other thread has created the counter in the meantime
+            let _created = self.counters.compare_and_swap(
+                key,
+                None as Option<&[u8]>,
+                Some(&start()?.to_be_bytes()[..]),
+            )?;
+        }
+        let next = self
+            .counters
+            .update_and_fetch(key, |current| {
+                let current = current
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map(i64::from_be_bytes)
+                    .unwrap_or(0);
+                Some((current + 1).to_be_bytes().to_vec())
+            })?
+            .ok_or("Counter was not created")?;
+        let bytes: [u8; 8] = next
+            .as_ref()
+            .try_into()
+            .map_err(|_| format!("Counter {} is corrupt", key))?;
+        Ok(i64::from_be_bytes(bytes))
+    }
+
     #[instrument(skip(self))]
     fn get_resource_extended(
         &self,
@@ -574,6 +760,12 @@ impl Storelike for Db {
     /// Tries `query_cache`, which you should implement yourself.
     #[instrument(skip(self))]
     fn query(&self, q: &Query) -> AtomicResult<QueryResult> {
+        if let Some(result) = crate::storelike::query_subclasses(self, q)? {
+            return Ok(result);
+        }
+        if let Some(geo) = &q.geo {
+            return self.query_geo(q, geo);
+        }
         if requires_query_index(q) {
             return self.query_complex(q);
         }
@@ -601,6 +793,7 @@ impl Storelike for Db {
         &self,
         subject: &str,
         body: Vec<u8>,
+        content_type: Option<&str>,
         for_agent: &ForAgent,
     ) -> AtomicResult<Resource> {
         let endpoints = self.endpoints.iter().filter(|e| e.handle_post.is_some());
@@ -611,6 +804,7 @@ impl Storelike for Db {
                     let handle_post_context = crate::endpoints::HandlePostContext {
                         store: self,
                         body,
+                        content_type,
                         for_agent,
                         subject: subj_url,
                     };
@@ -649,6 +843,7 @@ impl Storelike for Db {
 
     #[instrument(skip(self))]
     fn remove_resource(&self, subject: &str) -> AtomicResult<()> {
+        let _writing = self.writes.read_recursive();
         if let Ok(found) = self.get_propvals(subject) {
             let resource = Resource::from_propvals(found, subject.to_string());
             for (prop, val) in resource.get_propvals() {
@@ -0,0 +1,75 @@
+//! Spatial index for [GeoPoint] values, sorted by {Property}-{Geohash}-{Subject}.
+//! Nearby points share a geohash prefix, so the points in an area are found by scanning a few prefixes.
+
+use tracing::instrument;
+
+use crate::{
+    errors::AtomicResult,
+    geo::{GeoArea, GeoPoint, GEOHASH_PRECISION},
+    Db,
+};
+
+use super::query_index::SEPARATION_BIT;
+
+#[instrument(skip(store))]
+pub fn add_point_to_geo_index(
+    store: &Db,
+    property: &str,
+    subject: &str,
+    point: &GeoPoint,
+) -> AtomicResult<()> {
+    store
+        .geo_index
+        .insert(key(property, subject, point), bincode::serialize(point)?)?;
+    Ok(())
+}
+
+#[instrument(skip(store))]
+pub fn remove_point_from_geo_index(
+    store: &Db,
+    property: &str,
+    subject: &str,
+    point: &GeoPoint,
+) -> AtomicResult<()> {
+    store.geo_index.remove(key(property, subject, point))?;
+    Ok(())
+}
+
+/// Finds the subjects and points of the Resources that have a point inside the area.
+pub fn find_in_geo_index(
+    store: &Db,
+    property: &str,
+    area: &GeoArea,
+) -> AtomicResult<Vec<(String, GeoPoint)>> {
+    let mut hits = Vec::new();
+    for cell in area.geohash_cells() {
+        let prefix = [property.as_bytes(), &[SEPARATION_BIT], cell.as_bytes()].concat();
+        for kv in store.geo_index.scan_prefix(prefix) {
+            let (key, value) = kv?;
+            let point: GeoPoint = bincode::deserialize(&value)?;
+            // The cells cover more than the area
+            if !area.contains(&point) {
+                continue;
+            }
+            let subject = key
+                .split(|b| b == &SEPARATION_BIT)
+                .nth(2)
+                .ok_or("Invalid key for geo_index")?;
+            let subject = std::str::from_utf8(subject)
+                .map_err(|_| "Can't parse subject in geo_index into string")?;
+            hits.push((subject.to_string(), point));
+        }
+    }
+    Ok(hits)
+}
+
+fn key(property: &str, subject: &str, point: &GeoPoint) -> Vec<u8> {
+    [
+        property.as_bytes(),
+        &[SEPARATION_BIT],
+        point.geohash(GEOHASH_PRECISION).as_bytes(),
+        &[SEPARATION_BIT],
+        subject.as_bytes(),
+    ]
+    .concat()
+}
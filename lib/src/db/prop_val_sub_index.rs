@@ -10,7 +10,8 @@ use super::query_index::{IndexIterator, SEPARATION_BIT};
 pub fn find_in_prop_val_sub_index(store: &Db, prop: &str, val: Option<&Value>) -> IndexIterator {
     let mut prefix: Vec<u8> = [prop.as_bytes(), &[SEPARATION_BIT]].concat();
     if let Some(value) = val {
-        prefix.extend(value.to_sortable_string().as_bytes());
+        // The second part of the key is the reference string, which for single values is the plain string.
+        prefix.extend(value.to_string().as_bytes());
         prefix.extend([SEPARATION_BIT]);
     }
     Box::new(store.prop_val_sub_index.scan_prefix(prefix).map(|kv| {
@@ -273,6 +273,7 @@ fn queries() {
         include_external: true,
         include_nested: false,
         for_agent: ForAgent::Sudo,
+        ..Default::default()
     };
     let res = store.query(&q).unwrap();
     assert_eq!(
@@ -399,6 +400,7 @@ fn query_include_external() {
         include_external: true,
         include_nested: false,
         for_agent: ForAgent::Sudo,
+        ..Default::default()
     };
     let res_include = store.query(&q).unwrap();
     q.include_external = false;
@@ -411,6 +413,347 @@ fn query_include_external() {
     );
 }
 
+/// Check if `include_subclasses` finds instances of Classes that extend the queried Class.
+#[test]
+fn query_include_subclasses() {
+    let store = &Db::init_temp("query_include_subclasses").unwrap();
+    let server_url = store.get_server_url().to_string();
+    let add_class = |shortname: &str, extends: Vec<String>| {
+        let class = crate::schema::Class {
+            requires: vec![],
+            recommends: vec![urls::NAME.into()],
+            extends,
+            unique: vec![],
+            rules: vec![],
+            shortname: shortname.into(),
+            description: "A class for testing subclass queries".into(),
+            subject: format!("{}/classes/{}", server_url, shortname),
+        };
+        store.add_resource(&class.to_resource()).unwrap();
+        class.subject
+    };
+    let animal = add_class("animal", vec![]);
+    let dog = add_class("dog", vec![animal.clone()]);
+    let puppy = add_class("puppy", vec![dog.clone()]);
+    assert_eq!(
+        crate::schema::class_and_subclasses(store, &animal).unwrap(),
+        vec![animal.clone(), dog.clone(), puppy.clone()]
+    );
+
+    let add_instance = |name: &str, classes: Vec<String>| {
+        let mut resource = Resource::new(format!("{}/{}", server_url, name));
+        resource.set_unsafe(urls::IS_A.into(), classes.into());
+        resource.set_unsafe(urls::NAME.into(), Value::String(name.into()));
+        store.add_resource(&resource).unwrap();
+        resource.get_subject().clone()
+    };
+    let cat = add_instance("cat", vec![animal.clone()]);
+    // Instances of multiple classes should only be listed once
+    let rex = add_instance("rex", vec![animal.clone(), dog.clone()]);
+    let fido = add_instance("fido", vec![puppy]);
+
+    let mut q = Query::new_class(&animal);
+    assert_eq!(
+        store.query(&q).unwrap().subjects,
+        vec![cat.clone(), rex.clone()]
+    );
+
+    q.include_subclasses = true;
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.count, 3);
+    assert_eq!(res.subjects, vec![cat, fido.clone(), rex.clone()]);
+    assert_eq!(res.resources.len(), 3);
+
+    q.sort_by = Some(urls::NAME.into());
+    q.sort_desc = true;
+    q.offset = 1;
+    q.limit = Some(1);
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.count, 3);
+    assert_eq!(res.subjects, vec![fido]);
+    // Only the Resources of the page are loaded
+    assert_eq!(res.resources.len(), 1);
+
+    let mut q = Query::new_class(&dog);
+    q.include_subclasses = true;
+    q.include_nested = false;
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.subjects.len(), 2);
+    assert!(res.resources.is_empty());
+}
+
+#[test]
+fn query_decimals() {
+    let store = &Db::init_temp("query_decimals").unwrap();
+    let server_url = store.get_server_url().to_string();
+    let price = crate::schema::Property {
+        class_type: None,
+        data_type: crate::datatype::DataType::Decimal,
+        shortname: "price".into(),
+        description: "A price with arbitrary precision".into(),
+        subject: format!("{}/properties/price", server_url),
+        allows_only: None,
+        constraints: Default::default(),
+        default: None,
+    };
+    store.add_resource(&price.to_resource()).unwrap();
+    let prices = ["10", "-2.5", "9.99", "0.001", "100.10", "-20"];
+    for (i, amount) in prices.iter().enumerate() {
+        let mut resource = Resource::new(format!("{}/product{}", server_url, i));
+        resource
+            .set_string(price.subject.clone(), amount, store)
+            .unwrap();
+        store.add_resource(&resource).unwrap();
+    }
+
+    // Values are stored without trailing zeros
+    let q = Query::new_prop_val(&price.subject, "100.1");
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.subjects, vec![format!("{}/product4", server_url)]);
+
+    let mut q = Query::new();
+    q.property = Some(price.subject.clone());
+    q.sort_by = Some(price.subject.clone());
+    let res = store.query(&q).unwrap();
+    let sorted: Vec<String> = res
+        .resources
+        .iter()
+        .map(|r| r.get(&price.subject).unwrap().to_string())
+        .collect();
+    assert_eq!(sorted, vec!["-20", "-2.5", "0.001", "9.99", "10", "100.1"]);
+}
+
+#[test]
+fn query_date_times_across_time_zones() {
+    let store = &Db::init_temp("query_date_times_across_time_zones").unwrap();
+    let server_url = store.get_server_url().to_string();
+    let starts_at = crate::schema::Property {
+        class_type: None,
+        data_type: crate::datatype::DataType::DateTime,
+        shortname: "starts-at".into(),
+        description: "When the event starts".into(),
+        subject: format!("{}/properties/startsAt", server_url),
+        allows_only: None,
+        constraints: Default::default(),
+        default: None,
+    };
+    store.add_resource(&starts_at.to_resource()).unwrap();
+    let moments = [
+        "2024-05-01T09:30:00+02:00",
+        "2024-05-01T03:00:00-05:00",
+        "2024-05-01T07:45:00Z",
+    ];
+    for (i, moment) in moments.iter().enumerate() {
+        let mut resource = Resource::new(format!("{}/event{}", server_url, i));
+        resource
+            .set_string(starts_at.subject.clone(), moment, store)
+            .unwrap();
+        store.add_resource(&resource).unwrap();
+    }
+
+    let mut q = Query::new();
+    q.property = Some(starts_at.subject.clone());
+    q.sort_by = Some(starts_at.subject.clone());
+    let res = store.query(&q).unwrap();
+    let sorted: Vec<String> = res
+        .resources
+        .iter()
+        .map(|r| r.get(&starts_at.subject).unwrap().to_string())
+        .collect();
+    assert_eq!(
+        sorted,
+        vec![
+            "2024-05-01T09:30:00+02:00",
+            "2024-05-01T07:45:00Z",
+            "2024-05-01T03:00:00-05:00"
+        ]
+    );
+}
+
+#[test]
+fn migrate_datatype_on_commit() {
+    let store = &Db::init_temp("migrate_datatype_on_commit").unwrap();
+    let server_url = store.get_server_url().to_string();
+    let amount = crate::schema::Property {
+        class_type: None,
+        data_type: crate::datatype::DataType::String,
+        shortname: "amount".into(),
+        description: "An amount that was entered as text".into(),
+        subject: format!("{}/properties/amount", server_url),
+        allows_only: None,
+        constraints: Default::default(),
+        default: None,
+    };
+    store.add_resource(&amount.to_resource()).unwrap();
+    for (i, value) in ["12", "seven", "3"].iter().enumerate() {
+        let mut resource = Resource::new(format!("{}/order{}", server_url, i));
+        resource
+            .set_string(amount.subject.clone(), value, store)
+            .unwrap();
+        store.add_resource(&resource).unwrap();
+    }
+    // Watch a sorted query, so its query_index has to be updated
+    let mut q = Query::new();
+    q.property = Some(amount.subject.clone());
+    q.sort_by = Some(amount.subject.clone());
+    assert_eq!(store.query(&q).unwrap().count, 3);
+
+    let integer = crate::datatype::DataType::Integer;
+    let report = crate::migrate::migrate_datatype(store, &amount.subject, &integer, true).unwrap();
+    assert_eq!(report.converted.len(), 2);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].value, "seven");
+    assert!(matches!(
+        store.get_value(&format!("{}/order0", server_url), &amount.subject),
+        Ok(Value::String(_))
+    ));
+
+    // Values that can't be converted block the change
+    let mut property = store.get_resource(&amount.subject).unwrap();
+    property
+        .set(
+            urls::DATATYPE_PROP.into(),
+            Value::AtomicUrl(urls::INTEGER.into()),
+            store,
+        )
+        .unwrap();
+    let err = property.clone().save_locally(store).unwrap_err();
+    assert!(err.message.contains("'seven'"), "{}", err);
+    assert_eq!(
+        store.get_property(&amount.subject).unwrap().data_type,
+        crate::datatype::DataType::String
+    );
+    let mut order1 = store
+        .get_resource(&format!("{}/order1", server_url))
+        .unwrap();
+    order1
+        .set_string(amount.subject.clone(), "7", store)
+        .unwrap();
+    order1.save_locally(store).unwrap();
+    property.save_locally(store).unwrap();
+
+    let res = store.query(&q).unwrap();
+    let values: Vec<&Value> = res
+        .resources
+        .iter()
+        .map(|r| r.get(&amount.subject).unwrap())
+        .collect();
+    let integers = values
+        .iter()
+        .filter(|v| matches!(v, Value::Integer(_)))
+        .count();
+    assert_eq!(integers, 3, "{:?}", values);
+    let res = store
+        .query(&Query::new_prop_val(&amount.subject, "12"))
+        .unwrap();
+    assert_eq!(res.subjects, vec![format!("{}/order0", server_url)]);
+    // Values are converted using Commits
+    let order0 = store
+        .get_resource(&format!("{}/order0", server_url))
+        .unwrap();
+    let commit = store
+        .get_resource(&order0.get(urls::LAST_COMMIT).unwrap().to_string())
+        .unwrap();
+    let set = commit.get(urls::SET).unwrap().to_nested().unwrap();
+    assert!(matches!(set.get(&amount.subject), Some(Value::Integer(12))));
+
+    let report = crate::migrate::migrate_datatype(store, &amount.subject, &integer, false).unwrap();
+    assert_eq!(report.unchanged, 3);
+    assert!(report.failures.is_empty());
+}
+
+#[test]
+fn query_geo() {
+    use crate::geo::{GeoArea, GeoFilter, GeoPoint};
+
+    let store = &Db::init_temp("query_geo").unwrap();
+    let server_url = store.get_server_url().to_string();
+    let location = crate::schema::Property {
+        class_type: None,
+        data_type: crate::datatype::DataType::GeoPoint,
+        shortname: "location".into(),
+        description: "Where the asset is".into(),
+        subject: format!("{}/properties/location", server_url),
+        allows_only: None,
+        constraints: Default::default(),
+        default: None,
+    };
+    store.add_resource(&location.to_resource()).unwrap();
+    let places = [
+        ("amsterdam", "52.3676,4.9041"),
+        ("utrecht", "POINT(5.1214 52.0907)"),
+        ("berlin", "52.52,13.405"),
+        ("paris", "48.8566,2.3522"),
+    ];
+    for (name, point) in places {
+        let mut resource = Resource::new(format!("{}/{}", server_url, name));
+        resource
+            .set_string(location.subject.clone(), point, store)
+            .unwrap();
+        resource.set_string(urls::NAME.into(), name, store).unwrap();
+        store.add_resource(&resource).unwrap();
+    }
+    let subject = |name: &str| format!("{}/{}", server_url, name);
+
+    let mut q = Query::new();
+    q.geo = Some(GeoFilter {
+        property: location.subject.clone(),
+        area: GeoArea::parse_bounding_box("50.7,3.3,53.6,7.3").unwrap(),
+    });
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.subjects, vec![subject("amsterdam"), subject("utrecht")]);
+
+    // Sorted by distance from the center
+    q.geo = Some(GeoFilter {
+        property: location.subject.clone(),
+        area: GeoArea::parse_radius("52.0,5.0,700000").unwrap(),
+    });
+    let res = store.query(&q).unwrap();
+    assert_eq!(
+        res.subjects,
+        vec![
+            subject("utrecht"),
+            subject("amsterdam"),
+            subject("paris"),
+            subject("berlin")
+        ]
+    );
+    assert_eq!(res.count, 4);
+
+    // Start and end values apply to the sorted value
+    q.sort_by = Some(urls::NAME.into());
+    q.start_val = Some(Value::String("b".into()));
+    q.end_val = Some(Value::String("p".into()));
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.subjects, vec![subject("berlin")]);
+    q.sort_by = None;
+    q.start_val = None;
+    q.end_val = None;
+
+    // Index entries of Resources that no longer exist are skipped
+    let gone = GeoPoint::new(52.1, 5.0).unwrap();
+    geo_index::add_point_to_geo_index(store, &location.subject, &subject("gone"), &gone).unwrap();
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.count, 4);
+
+    // Moving a resource removes its old location from the index
+    let mut utrecht = store.get_resource(&subject("utrecht")).unwrap();
+    utrecht
+        .set(
+            location.subject.clone(),
+            Value::GeoPoint(GeoPoint::new(40.7128, -74.006).unwrap()),
+            store,
+        )
+        .unwrap();
+    store.add_resource(&utrecht).unwrap();
+    q.geo = Some(GeoFilter {
+        property: location.subject.clone(),
+        area: GeoArea::parse_radius("52.0,5.0,100000").unwrap(),
+    });
+    let res = store.query(&q).unwrap();
+    assert_eq!(res.subjects, vec![subject("amsterdam")]);
+}
+
 #[test]
 fn test_db_resources_all() {
     let store = &Db::init_temp("resources_all").unwrap();
@@ -422,6 +765,80 @@ fn test_db_resources_all() {
     );
 }
 
+#[test]
+fn counters_are_unique() {
+    let store = Db::init_temp("counters").unwrap();
+    assert_eq!(store.next_counter("a", &|| Ok(41)).unwrap(), 42);
+    // The start is only used for new counters
+    assert_eq!(store.next_counter("a", &|| Ok(0)).unwrap(), 43);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let store = store.clone();
+            std::thread::spawn(move || {
+                (0..25)
+                    .map(|_| store.next_counter("b", &|| Ok(0)).unwrap())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let mut values: Vec<i64> = handles
+        .into_iter()
+        .flat_map(|h| h.join().unwrap())
+        .collect();
+    values.sort();
+    assert_eq!(values, (1..=100).collect::<Vec<_>>());
+}
+
+#[test]
+fn snapshot_can_be_opened() {
+    let store = &Db::init_temp("snapshot_source").unwrap();
+    let snapshot_path = std::path::Path::new(".temp/db/snapshot_target");
+    let _try_remove_existing = std::fs::remove_dir_all(snapshot_path);
+    let count = store.snapshot(snapshot_path).unwrap();
+    assert_eq!(count, store.all_resources(true).count());
+    assert!(
+        store.snapshot(snapshot_path).is_err(),
+        "Should not overwrite an existing snapshot"
+    );
+
+    let restored = Db::init(snapshot_path, store.get_server_url().into()).unwrap();
+    let agent = store.get_default_agent().unwrap();
+    restored.get_resource(&agent.subject).unwrap();
+    assert_eq!(
+        restored.all_resources(true).count(),
+        store.all_resources(true).count()
+    );
+
+    // Writes during a snapshot end up in it completely, including their index entries, or not at all
+    let description = Value::Markdown("Written during a snapshot".into());
+    let writer = {
+        let store = store.clone();
+        let description = description.clone();
+        std::thread::spawn(move || {
+            for i in 0..200 {
+                let mut resource =
+                    Resource::new(format!("{}/concurrent-{}", store.get_server_url(), i));
+                resource.set_unsafe(urls::DESCRIPTION.into(), description.clone());
+                store.add_resource(&resource).unwrap();
+            }
+        })
+    };
+    let concurrent_path = std::path::Path::new(".temp/db/snapshot_concurrent");
+    let _try_remove_existing = std::fs::remove_dir_all(concurrent_path);
+    store.snapshot(concurrent_path).unwrap();
+    writer.join().unwrap();
+    let restored = Db::init(concurrent_path, store.get_server_url().into()).unwrap();
+    let written = restored
+        .all_resources(true)
+        .filter(|r| r.get_subject().contains("/concurrent-"))
+        .count();
+    let mut q = Query::new();
+    q.property = Some(urls::DESCRIPTION.into());
+    q.value = Some(description);
+    q.include_nested = false;
+    assert_eq!(restored.query(&q).unwrap().subjects.len(), written);
+}
+
 #[test]
 /// Changing these values actually correctly updates the index.
 fn index_invalidate_cache() {
@@ -510,6 +927,7 @@ fn test_collection_update_value(store: &Db, property_url: &str, old_val: Value,
         include_external: true,
         include_nested: true,
         for_agent: ForAgent::Sudo,
+        ..Default::default()
     };
     let mut res = store.query(&q).unwrap();
     assert_eq!(
@@ -30,6 +30,8 @@ pub struct HandlePostContext<'a> {
     pub store: &'a Db,
     pub for_agent: &'a ForAgent,
     pub body: Vec<u8>,
+    /// The MIME type of the body, e.g. from the `Content-Type` header
+    pub content_type: Option<&'a str>,
 }
 /// An API endpoint at some path which accepts requests and returns some Resource.
 #[derive(Clone)]
@@ -12,7 +12,7 @@ use std::{
 
 use base64::DecodeError;
 
-use crate::{urls, Resource, Value};
+use crate::{rules::RuleViolation, urls, values::SubResource, Resource, Value};
 
 /// The default Error type for all Atomic Lib Errors.
 pub type AtomicResult<T> = std::result::Result<T, AtomicError>;
@@ -31,6 +31,9 @@ pub enum AtomicErrorType {
     ParseError,
     OtherError,
     MethodNotAllowed,
+    ConflictError,
+    /// The Resource does not follow the Validation Rules of its Classes.
+    ValidationError(Vec<RuleViolation>),
 }
 
 impl std::error::Error for AtomicError {
@@ -67,6 +70,25 @@ impl AtomicError {
         }
     }
 
+    /// The change conflicts with existing data, such as a value that has to be unique. A server will probably return a 409.
+    pub fn conflict(message: String) -> AtomicError {
+        AtomicError {
+            message: format!("Conflict. {}", message),
+            error_type: AtomicErrorType::ConflictError,
+            subject: None,
+        }
+    }
+
+    /// The Resource does not follow one or more Validation Rules. A server will probably return a 422.
+    pub fn validation(violations: Vec<RuleViolation>) -> AtomicError {
+        let reasons: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        AtomicError {
+            message: format!("Validation failed. {}", reasons.join(". ")),
+            error_type: AtomicErrorType::ValidationError(violations),
+            subject: None,
+        }
+    }
+
     /// A server will probably return a 500.
     pub fn other_error(message: String) -> AtomicError {
         AtomicError {
@@ -106,6 +128,19 @@ impl AtomicError {
         let mut r = Resource::new(subject);
         r.set_class(urls::ERROR);
         r.set_unsafe(urls::DESCRIPTION.into(), Value::String(self.message));
+        if let AtomicErrorType::ValidationError(violations) = self.error_type {
+            let violations = violations
+                .into_iter()
+                .map(|v| {
+                    let mut propvals = std::collections::HashMap::new();
+                    propvals.insert(urls::RULE.into(), Value::AtomicUrl(v.rule));
+                    propvals.insert(urls::PROPERTIES.into(), Value::from(v.properties));
+                    propvals.insert(urls::DESCRIPTION.into(), Value::String(v.message));
+                    SubResource::Nested(propvals)
+                })
+                .collect();
+            r.set_unsafe(urls::VIOLATIONS.into(), Value::ResourceArray(violations));
+        }
         r
     }
 
@@ -253,6 +288,28 @@ impl From<Infallible> for AtomicError {
     }
 }
 
+#[cfg(feature = "csv")]
+impl From<csv::Error> for AtomicError {
+    fn from(error: csv::Error) -> Self {
+        AtomicError {
+            message: error.to_string(),
+            error_type: AtomicErrorType::ParseError,
+            subject: None,
+        }
+    }
+}
+
+#[cfg(feature = "rdf")]
+impl From<rio_turtle::TurtleError> for AtomicError {
+    fn from(error: rio_turtle::TurtleError) -> Self {
+        AtomicError {
+            message: error.to_string(),
+            error_type: AtomicErrorType::ParseError,
+            subject: None,
+        }
+    }
+}
+
 #[cfg(feature = "db")]
 impl From<sled::Error> for AtomicError {
     fn from(error: sled::Error) -> Self {
@@ -0,0 +1,412 @@
+//! Geographic points ([GeoPoint]) and the areas ([GeoArea]) that [crate::storelike::Query]s can filter them by.
+//! In plain JSON and JSON-AD, points are serialized as [GeoJSON](https://geojson.org/), in RDF as [WKT](https://en.wikipedia.org/wiki/Well-known_text_representation_of_geometry).
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::AtomicResult;
+
+/// Mean radius of the earth in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+/// Characters used in geohashes
+const GEOHASH_BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+/// Length of the geohashes in the spatial index, which is precise up to a few centimeters
+pub const GEOHASH_PRECISION: usize = 12;
+/// Maximum amount of geohash cells that are scanned for one area.
+/// A geohash of one character covers the world in 32 cells, so every area fits.
+const MAX_CELLS: usize = 32;
+
+/// A point on earth, in WGS 84 degrees.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    /// Latitude, between -90 (south) and 90 (north)
+    pub lat: f64,
+    /// Longitude, between -180 (west) and 180 (east)
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// Checks that the coordinates are on earth.
+    pub fn new(lat: f64, lon: f64) -> AtomicResult<GeoPoint> {
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(format!("Latitude {} is not between -90 and 90", lat).into());
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(format!("Longitude {} is not between -180 and 180", lon).into());
+        }
+        Ok(GeoPoint { lat, lon })
+    }
+
+    /// Parses a WKT point (`POINT(4.89 52.37)`, longitude first), a GeoJSON Point or `latitude,longitude` (`52.37,4.89`).
+    pub fn parse(value: &str) -> AtomicResult<GeoPoint> {
+        let value = value.trim();
+        if value.starts_with('{') {
+            let json: serde_json::Value = serde_json::from_str(value)
+                .map_err(|e| format!("Not a valid GeoJSON Point: {}. {}", value, e))?;
+            return GeoPoint::from_geo_json(&json);
+        }
+        if value
+            .get(..5)
+            .is_some_and(|p| p.eq_ignore_ascii_case("point"))
+        {
+            let coordinates = value[5..]
+                .trim()
+                .strip_prefix('(')
+                .and_then(|v| v.strip_suffix(')'))
+                .ok_or_else(|| format!("Not a valid WKT Point: {}", value))?;
+            let numbers = parse_numbers(coordinates.split_whitespace(), value)?;
+            return match numbers[..] {
+                [lon, lat] => GeoPoint::new(lat, lon),
+                _ => Err(format!("A WKT Point needs two coordinates, got {}", value).into()),
+            };
+        }
+        let numbers = parse_numbers(value.split(','), value)?;
+        match numbers[..] {
+            [lat, lon] => GeoPoint::new(lat, lon),
+            _ => Err(format!(
+                "Not a valid GeoPoint: {}. Use `POINT(longitude latitude)`, GeoJSON or `latitude,longitude`.",
+                value
+            )
+            .into()),
+        }
+    }
+
+    /// Reads a GeoJSON Point, e.g. `{"type": "Point", "coordinates": [4.89, 52.37]}`.
+    pub fn from_geo_json(json: &serde_json::Value) -> AtomicResult<GeoPoint> {
+        if json.get("type").and_then(|t| t.as_str()) != Some("Point") {
+            return Err(format!("Not a GeoJSON Point: {}", json).into());
+        }
+        let coordinates: Vec<f64> = json
+            .get("coordinates")
+            .and_then(|c| c.as_array())
+            .map(|c| c.iter().filter_map(|n| n.as_f64()).collect())
+            .unwrap_or_default();
+        match coordinates[..] {
+            [lon, lat] => GeoPoint::new(lat, lon),
+            _ => Err(format!("A GeoJSON Point needs two coordinates, got {}", json).into()),
+        }
+    }
+
+    /// Writes a GeoJSON Point. Note that GeoJSON puts the longitude first.
+    pub fn to_geo_json(&self) -> serde_json::Value {
+        serde_json::json!({ "type": "Point", "coordinates": [self.lon, self.lat] })
+    }
+
+    /// Distance to the other point in meters, along the surface of the earth.
+    pub fn distance(&self, other: &GeoPoint) -> f64 {
+        let d_lat = (other.lat - self.lat).to_radians();
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2)
+            + self.lat.to_radians().cos()
+                * other.lat.to_radians().cos()
+                * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+
+    /// Encodes the point as a [geohash](https://en.wikipedia.org/wiki/Geohash).
+    /// Points that are close to each other often share a prefix, which is what the spatial index uses.
+    pub fn geohash(&self, precision: usize) -> String {
+        let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+        let mut hash = String::with_capacity(precision);
+        let mut even_bit = true;
+        for _ in 0..precision {
+            let mut index = 0;
+            for _ in 0..5 {
+                let (range, coordinate) = if even_bit {
+                    (&mut lon_range, self.lon)
+                } else {
+                    (&mut lat_range, self.lat)
+                };
+                let mid = (range.0 + range.1) / 2.0;
+                index <<= 1;
+                if coordinate >= mid {
+                    index |= 1;
+                    range.0 = mid;
+                } else {
+                    range.1 = mid;
+                }
+                even_bit = !even_bit;
+            }
+            hash.push(GEOHASH_BASE32[index] as char);
+        }
+        hash
+    }
+}
+
+/// Serializes as WKT, e.g. `POINT(4.89 52.37)`.
+impl std::fmt::Display for GeoPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "POINT({} {})", self.lon, self.lat)
+    }
+}
+
+/// Filters a Query by the [GeoPoint] values of a Property.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoFilter {
+    /// The Property that contains the [GeoPoint]
+    pub property: String,
+    /// The area in which the point has to be
+    pub area: GeoArea,
+}
+
+impl GeoFilter {
+    /// Checks if the Resource has a point inside the area.
+    pub fn matches(&self, resource: &crate::Resource) -> bool {
+        match resource.get(&self.property) {
+            Ok(crate::Value::GeoPoint(point)) => self.area.contains(point),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeoArea {
+    /// Points between two corners. Does not cross the antimeridian.
+    BoundingBox {
+        south_west: GeoPoint,
+        north_east: GeoPoint,
+    },
+    /// Points that are at most `meters` away from the `center`.
+    Radius { center: GeoPoint, meters: f64 },
+}
+
+impl GeoArea {
+    /// Parses `south,west,north,east`, e.g. `52.3,4.8,52.4,5.0`.
+    pub fn parse_bounding_box(value: &str) -> AtomicResult<GeoArea> {
+        match parse_numbers(value.split(','), value)?[..] {
+            [south, west, north, east] => {
+                if south > north || west > east {
+                    return Err(format!(
+                        "Invalid bounding box {}: south must be below north, and west must be left of east.",
+                        value
+                    )
+                    .into());
+                }
+                Ok(GeoArea::BoundingBox {
+                    south_west: GeoPoint::new(south, west)?,
+                    north_east: GeoPoint::new(north, east)?,
+                })
+            }
+            _ => Err(format!(
+                "Invalid bounding box {}. Use `south,west,north,east`.",
+                value
+            )
+            .into()),
+        }
+    }
+
+    /// Parses `latitude,longitude,meters`, e.g. `52.37,4.89,500`.
+    pub fn parse_radius(value: &str) -> AtomicResult<GeoArea> {
+        match parse_numbers(value.split(','), value)?[..] {
+            [lat, lon, meters] if meters >= 0.0 => Ok(GeoArea::Radius {
+                center: GeoPoint::new(lat, lon)?,
+                meters,
+            }),
+            _ => Err(format!("Invalid radius {}. Use `latitude,longitude,meters`.", value).into()),
+        }
+    }
+
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        match self {
+            GeoArea::BoundingBox {
+                south_west,
+                north_east,
+            } => {
+                (south_west.lat..=north_east.lat).contains(&point.lat)
+                    && (south_west.lon..=north_east.lon).contains(&point.lon)
+            }
+            GeoArea::Radius { center, meters } => center.distance(point) <= *meters,
+        }
+    }
+
+    /// The south west and north east corners of boxes that together contain the area.
+    /// A radius that crosses the antimeridian is split in a box on either side of it.
+    pub fn boxes(&self) -> Vec<(GeoPoint, GeoPoint)> {
+        match self {
+            GeoArea::BoundingBox {
+                south_west,
+                north_east,
+            } => vec![(*south_west, *north_east)],
+            GeoArea::Radius { center, meters } => {
+                let angle = meters / EARTH_RADIUS;
+                let south = (center.lat - angle.to_degrees()).max(-90.0);
+                let north = (center.lat + angle.to_degrees()).min(90.0);
+                // A circle that contains a pole contains every longitude
+                let d_lon = if angle >= std::f64::consts::FRAC_PI_2 - center.lat.to_radians().abs()
+                {
+                    180.0
+                } else {
+                    (angle.sin() / center.lat.to_radians().cos())
+                        .asin()
+                        .to_degrees()
+                };
+                let corners = |west: f64, east: f64| {
+                    (
+                        GeoPoint {
+                            lat: south,
+                            lon: west,
+                        },
+                        GeoPoint {
+                            lat: north,
+                            lon: east,
+                        },
+                    )
+                };
+                let (west, east) = (center.lon - d_lon, center.lon + d_lon);
+                if d_lon >= 180.0 {
+                    vec![corners(-180.0, 180.0)]
+                } else if west < -180.0 {
+                    vec![corners(-180.0, east), corners(west + 360.0, 180.0)]
+                } else if east > 180.0 {
+                    vec![corners(west, 180.0), corners(-180.0, east - 360.0)]
+                } else {
+                    vec![corners(west, east)]
+                }
+            }
+        }
+    }
+
+    /// Geohashes of the cells that together cover the area.
+    /// Uses the smallest cells of which at most [MAX_CELLS] are needed.
+    pub fn geohash_cells(&self) -> Vec<String> {
+        let boxes = self.boxes();
+        for precision in (1..=GEOHASH_PRECISION).rev() {
+            let lon_bits = (5 * precision).div_ceil(2);
+            let lat_bits = 5 * precision / 2;
+            let width = 360.0 / (1u64 << lon_bits) as f64;
+            let height = 180.0 / (1u64 << lat_bits) as f64;
+            let column = |lon: f64| (((lon + 180.0) / width) as u64).min((1 << lon_bits) - 1);
+            let row = |lat: f64| (((lat + 90.0) / height) as u64).min((1 << lat_bits) - 1);
+            let ranges: Vec<_> = boxes
+                .iter()
+                .map(|(south_west, north_east)| {
+                    (
+                        column(south_west.lon)..=column(north_east.lon),
+                        row(south_west.lat)..=row(north_east.lat),
+                    )
+                })
+                .collect();
+            let amount: u64 = ranges
+                .iter()
+                .map(|(columns, rows)| {
+                    (columns.end() - columns.start() + 1) * (rows.end() - rows.start() + 1)
+                })
+                .sum();
+            if amount as usize > MAX_CELLS && precision > 1 {
+                continue;
+            }
+            let mut cells = Vec::new();
+            for (columns, rows) in ranges {
+                for x in columns {
+                    for y in rows.clone() {
+                        let center = GeoPoint {
+                            lat: -90.0 + (y as f64 + 0.5) * height,
+                            lon: -180.0 + (x as f64 + 0.5) * width,
+                        };
+                        cells.push(center.geohash(precision));
+                    }
+                }
+            }
+            // The boxes of a split area can share a cell
+            cells.sort();
+            cells.dedup();
+            return cells;
+        }
+        vec![]
+    }
+}
+
+/// Serializes in the format of [GeoArea::parse_bounding_box] or [GeoArea::parse_radius].
+impl std::fmt::Display for GeoArea {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeoArea::BoundingBox {
+                south_west,
+                north_east,
+            } => write!(
+                f,
+                "{},{},{},{}",
+                south_west.lat, south_west.lon, north_east.lat, north_east.lon
+            ),
+            GeoArea::Radius { center, meters } => {
+                write!(f, "{},{},{}", center.lat, center.lon, meters)
+            }
+        }
+    }
+}
+
+fn parse_numbers<'a>(
+    parts: impl Iterator<Item = &'a str>,
+    original: &str,
+) -> AtomicResult<Vec<f64>> {
+    parts
+        .map(|n| {
+            n.trim()
+                .parse::<f64>()
+                .map_err(|e| format!("Invalid number in {}: {}", original, e).into())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_serialize_points() {
+        let amsterdam = GeoPoint::new(52.37, 4.89).unwrap();
+        assert_eq!(GeoPoint::parse("POINT(4.89 52.37)").unwrap(), amsterdam);
+        assert_eq!(GeoPoint::parse("52.37, 4.89").unwrap(), amsterdam);
+        assert_eq!(amsterdam.to_string(), "POINT(4.89 52.37)");
+        let json = amsterdam.to_geo_json().to_string();
+        assert_eq!(GeoPoint::parse(&json).unwrap(), amsterdam);
+        GeoPoint::parse("4.89 52.37").unwrap_err();
+        GeoPoint::parse("91,0").unwrap_err();
+        GeoPoint::parse(r#"{"type": "LineString", "coordinates": [[1, 2], [3, 4]]}"#).unwrap_err();
+    }
+
+    #[test]
+    fn geohashes_and_areas() {
+        let point = GeoPoint::new(57.64911, 10.40744).unwrap();
+        assert_eq!(point.geohash(11), "u4pruydqqvj");
+
+        let amsterdam = GeoPoint::new(52.3676, 4.9041).unwrap();
+        let utrecht = GeoPoint::new(52.0907, 5.1214).unwrap();
+        let distance = amsterdam.distance(&utrecht);
+        assert!((34_000.0..36_000.0).contains(&distance), "{}", distance);
+
+        let near = GeoArea::parse_radius("52.3676,4.9041,40000").unwrap();
+        assert!(near.contains(&utrecht));
+        assert!(!GeoArea::parse_radius("52.3676,4.9041,30000")
+            .unwrap()
+            .contains(&utrecht));
+
+        let bbox = GeoArea::parse_bounding_box("52,4.5,52.5,5").unwrap();
+        assert!(bbox.contains(&amsterdam));
+        assert!(!bbox.contains(&utrecht));
+        GeoArea::parse_bounding_box("53,4,52,5").unwrap_err();
+
+        // Areas around the antimeridian include points on both sides of it
+        let fiji = GeoPoint::new(-17.8, 179.9).unwrap();
+        let samoa = GeoPoint::new(-17.8, -179.9).unwrap();
+        let around = GeoArea::parse_radius("-17.8,179.95,20000").unwrap();
+        assert!(around.contains(&fiji) && around.contains(&samoa));
+        assert_eq!(around.boxes().len(), 2);
+        // Areas around a pole include every longitude
+        let pole = GeoArea::parse_radius("89.9,0,50000").unwrap();
+        assert_eq!(pole.boxes()[0].0.lon, -180.0);
+        assert!(pole.contains(&GeoPoint::new(89.9, 180.0).unwrap()));
+
+        for (area, point) in [
+            (near, amsterdam),
+            (bbox, amsterdam),
+            (around.clone(), fiji),
+            (around, samoa),
+            (pole, GeoPoint::new(89.9, 180.0).unwrap()),
+        ] {
+            let cells = area.geohash_cells();
+            assert!(!cells.is_empty() && cells.len() <= MAX_CELLS);
+            let hash = point.geohash(GEOHASH_PRECISION);
+            assert!(cells.iter().any(|cell| hash.starts_with(cell.as_str())));
+        }
+    }
+}
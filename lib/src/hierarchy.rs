@@ -60,6 +60,20 @@ pub fn check_read(
     check_rights(store, resource, for_agent, Right::Read)
 }
 
+/// Returns all internal Resources that the Public Agent can read, except for Commits.
+/// Useful for publishing the public parts of a store, e.g. in sitemaps or static sites.
+/// Checks the rights of every Resource, which can be expensive for large stores.
+pub fn public_resources(store: &impl Storelike) -> impl Iterator<Item = Resource> + '_ {
+    store.all_resources(false).filter(move |resource| {
+        let is_commit = resource
+            .get(urls::IS_A)
+            .and_then(|v| v.to_subjects(None))
+            .map(|classes| classes.iter().any(|c| c == urls::COMMIT))
+            .unwrap_or(false);
+        !is_commit && check_read(store, resource, &ForAgent::Public).is_ok()
+    })
+}
+
 /// Does the Agent have the right to _append_ to its parent?
 /// This checks the `append` rights, and if that fails, checks the `write` right.
 /// Throws if not allowed.
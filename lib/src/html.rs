@@ -0,0 +1,590 @@
+//! Renders Resources as plain HTML pages, and exports all public Resources as a static website.
+//! Pages work without JavaScript: Markdown is rendered on the server, and links point to other pages.
+//! Raw HTML in Markdown is escaped, and links only use safe schemes (see [safe_url]), so rendered pages can't contain scripts.
+
+use std::collections::HashMap;
+
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+use crate::{
+    agents::ForAgent, errors::AtomicResult, hierarchy, storelike::Query, urls, values::SubResource,
+    Resource, Storelike, Value,
+};
+
+/// Name of the HTML file in every folder of an exported site.
+pub const SITE_PAGE_FILE: &str = "index.html";
+/// Name of the JSON-AD file next to every page of an exported site.
+pub const SITE_JSON_AD_FILE: &str = "index.json";
+
+/// Determines how a page links to other Resources.
+pub struct PageContext<'a> {
+    /// Maps a subject to the `href` of a link.
+    pub href: &'a dyn Fn(&str) -> String,
+    /// Returns a human readable name for a subject, or `None` if it is unknown or not readable.
+    pub label: &'a dyn Fn(&str) -> Option<String>,
+    /// Subjects of the Resources that are listed as the contents of the page, e.g. its children.
+    pub children: &'a [String],
+    /// Link to the JSON-AD representation of the page, added as an `alternate` link.
+    pub json_ad_href: Option<&'a str>,
+}
+
+impl PageContext<'_> {
+    fn link(&self, subject: &str) -> String {
+        let label = (self.label)(subject).unwrap_or_else(|| subject.to_string());
+        format!(
+            "<a href=\"{}\">{}</a>",
+            escape_html(safe_url(&(self.href)(subject))),
+            escape_html(&label)
+        )
+    }
+}
+
+/// The name, shortname or subject of a Resource.
+pub fn resource_title(resource: &Resource) -> String {
+    resource
+        .get(urls::NAME)
+        .or_else(|_| resource.get(urls::SHORTNAME))
+        .map(value_text)
+        .unwrap_or_else(|_| resource.get_subject().clone())
+}
+
+/// The text of a Value, for titles and descriptions.
+/// Language-tagged strings use their first language, use [Resource::localize] to pick the language of the reader.
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::LangString(texts) => texts.first().to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Escapes text for use in HTML elements and attributes.
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// URL schemes that links and images can use. URLs without a scheme (relative URLs) are allowed too.
+const SAFE_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Returns the URL if it is relative or uses one of the [SAFE_URL_SCHEMES], or `#` otherwise.
+/// This prevents links like `javascript:alert(1)`.
+pub fn safe_url(url: &str) -> &str {
+    // Browsers ignore leading spaces and control characters, and tabs and newlines anywhere in the URL
+    let cleaned: String = url
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let scheme_end = cleaned.find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c)));
+    match scheme_end {
+        Some(end) if cleaned[end..].starts_with(':') => {
+            let scheme = cleaned[..end].to_ascii_lowercase();
+            if SAFE_URL_SCHEMES.contains(&scheme.as_str()) {
+                url
+            } else {
+                "#"
+            }
+        }
+        _ => url,
+    }
+}
+
+/// Renders Markdown to HTML. Link and image URLs are passed through `href` and checked with [safe_url], raw HTML is escaped.
+pub fn markdown_to_html(markdown: &str, href: &dyn Fn(&str) -> String) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) => Event::Text(raw),
+        Event::Start(Tag::Link(kind, url, title)) => Event::Start(Tag::Link(
+            kind,
+            CowStr::from(safe_url(&href(&url)).to_string()),
+            title,
+        )),
+        Event::Start(Tag::Image(kind, url, title)) => Event::Start(Tag::Image(
+            kind,
+            CowStr::from(safe_url(&href(&url)).to_string()),
+            title,
+        )),
+        other => other,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+fn sub_resource_to_html(sub_resource: &SubResource, context: &PageContext) -> String {
+    match sub_resource {
+        SubResource::Subject(subject) => context.link(subject),
+        SubResource::Resource(resource) => context.link(resource.get_subject()),
+        SubResource::Nested(propvals) => {
+            let mut out = String::from("<dl>");
+            for (property, value) in propvals.iter() {
+                out.push_str(&format!(
+                    "<dt>{}</dt><dd>{}</dd>",
+                    context.link(property),
+                    value_to_html(value, context)
+                ));
+            }
+            out.push_str("</dl>");
+            out
+        }
+    }
+}
+
+fn value_to_html(value: &Value, context: &PageContext) -> String {
+    match value {
+        Value::AtomicUrl(subject) => context.link(subject),
+        Value::ResourceArray(items) => {
+            let items: String = items
+                .iter()
+                .map(|item| format!("<li>{}</li>", sub_resource_to_html(item, context)))
+                .collect();
+            format!("<ul>{}</ul>", items)
+        }
+        Value::NestedResource(sub_resource) => sub_resource_to_html(sub_resource, context),
+        Value::Resource(resource) => context.link(resource.get_subject()),
+        Value::Markdown(markdown) => markdown_to_html(markdown, context.href),
+        Value::LangString(texts) => texts
+            .iter()
+            .map(|(language, text)| {
+                format!(
+                    "<span lang=\"{}\">{}</span>",
+                    escape_html(language),
+                    escape_html(text)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("<br>"),
+        other => escape_html(&other.to_string()),
+    }
+}
+
+/// Renders the Resource as a complete HTML document. See [resource_to_html] for the contents.
+pub fn resource_to_html_page(resource: &Resource, context: &PageContext) -> String {
+    let title = resource_title(resource);
+    let description = resource
+        .get(urls::DESCRIPTION)
+        .map(value_text)
+        .unwrap_or_default();
+
+    let mut head = format!(
+        "<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n",
+        escape_html(&title)
+    );
+    if !description.is_empty() {
+        let summary: String = description.chars().take(200).collect();
+        head.push_str(&format!(
+            "<meta name=\"description\" content=\"{}\">\n",
+            escape_html(&summary)
+        ));
+    }
+    if let Some(json_ad_href) = context.json_ad_href {
+        head.push_str(&format!(
+            "<link rel=\"alternate\" type=\"{}\" href=\"{}\">\n",
+            crate::parse::JSON_AD_MIME,
+            escape_html(json_ad_href)
+        ));
+    }
+    head.push_str("<style>body{font-family:sans-serif;max-width:50rem;margin:2rem auto;padding:0 1rem;line-height:1.5}dt{font-weight:bold}img{max-width:100%}</style>\n");
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n{}</head>\n<body>\n<main>\n{}</main>\n</body>\n</html>\n",
+        head,
+        resource_to_html(resource, context)
+    )
+}
+
+/// Renders the contents of the Resource as HTML, without a surrounding document.
+/// The `name` is the title, the `description` is rendered as Markdown, and the other Properties are listed below.
+/// Pages of Collections link to the previous and next page.
+pub fn resource_to_html(resource: &Resource, context: &PageContext) -> String {
+    let subject = resource.get_subject();
+    let title = resource_title(resource);
+
+    let mut body = String::new();
+    if let Ok(parent) = resource.get(urls::PARENT) {
+        body.push_str(&format!(
+            "<nav>{}</nav>\n",
+            context.link(&parent.to_string())
+        ));
+    }
+    body.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+    match resource.get(urls::DESCRIPTION) {
+        Ok(Value::Markdown(markdown)) => body.push_str(&markdown_to_html(markdown, context.href)),
+        Ok(description) => body.push_str(&format!(
+            "<p>{}</p>\n",
+            escape_html(&value_text(description))
+        )),
+        Err(_) => {}
+    }
+
+    let mut propvals: Vec<(&String, &Value)> = resource
+        .get_propvals()
+        .iter()
+        .filter(|(property, _)| {
+            ![urls::NAME, urls::DESCRIPTION, urls::PARENT, urls::CHILDREN]
+                .contains(&property.as_str())
+        })
+        .collect();
+    propvals.sort_by(|a, b| a.0.cmp(b.0));
+    if !propvals.is_empty() {
+        body.push_str("<dl>\n");
+        for (property, value) in propvals {
+            body.push_str(&format!(
+                "<dt>{}</dt>\n<dd>{}</dd>\n",
+                context.link(property),
+                value_to_html(value, context)
+            ));
+        }
+        body.push_str("</dl>\n");
+    }
+    body.push_str(&collection_navigation(resource, context));
+
+    if !context.children.is_empty() {
+        body.push_str("<h2>Contents</h2>\n<ul>\n");
+        for child in context.children {
+            body.push_str(&format!("<li>{}</li>\n", context.link(child)));
+        }
+        body.push_str("</ul>\n");
+    }
+    body.push_str(&format!(
+        "<footer><small>{}</small></footer>\n",
+        escape_html(subject)
+    ));
+    body
+}
+
+/// Links to the previous and next page, if the Resource is a page of a Collection with multiple pages.
+fn collection_navigation(resource: &Resource, context: &PageContext) -> String {
+    let (Ok(current), Ok(total)) = (
+        resource
+            .get(urls::COLLECTION_CURRENT_PAGE)
+            .and_then(|v| v.to_int()),
+        resource
+            .get(urls::COLLECTION_TOTAL_PAGES)
+            .and_then(|v| v.to_int()),
+    ) else {
+        return String::new();
+    };
+    let Ok(url) = url::Url::parse(resource.get_subject()) else {
+        return String::new();
+    };
+    if total <= 1 {
+        return String::new();
+    }
+    let page_link = |page: i64, text: &str, rel: &str| {
+        let mut page_url = url.clone();
+        let params: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(k, _)| k != "current_page")
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        page_url
+            .query_pairs_mut()
+            .clear()
+            .extend_pairs(params)
+            .append_pair("current_page", &page.to_string());
+        format!(
+            "<a rel=\"{}\" href=\"{}\">{}</a>",
+            rel,
+            escape_html(safe_url(&(context.href)(page_url.as_str()))),
+            text
+        )
+    };
+    let mut links = Vec::new();
+    if current > 0 {
+        links.push(page_link(current - 1, "Previous page", "prev"));
+    }
+    links.push(format!("Page {} of {}", current + 1, total));
+    if current + 1 < total {
+        links.push(page_link(current + 1, "Next page", "next"));
+    }
+    format!("<nav>{}</nav>\n", links.join(" "))
+}
+
+/// Renders the contents of a Resource from the Store as HTML, as it can be seen by `for_agent`.
+/// Links point to the subjects themselves. Readable children (Resources with this Resource as `parent`) are listed.
+/// Links are labeled using Resources that are in the Store, other Resources are never fetched and are shown by their subject.
+pub fn render_resource_html(
+    store: &impl Storelike,
+    resource: &Resource,
+    for_agent: &ForAgent,
+) -> String {
+    let server_url = store.get_server_url();
+    let mut children_query = Query::new_prop_val(urls::PARENT, resource.get_subject());
+    children_query.for_agent = for_agent.clone();
+    let children = store
+        .query(&children_query)
+        .map(|r| r.subjects)
+        .unwrap_or_default();
+    let href = |subject: &str| subject.to_string();
+    let label = |subject: &str| {
+        let found = store.get_resource_local(subject).ok()?;
+        if subject.starts_with(server_url)
+            && hierarchy::check_read(store, &found, for_agent).is_err()
+        {
+            return None;
+        }
+        Some(resource_title(&found))
+    };
+    let context = PageContext {
+        href: &href,
+        label: &label,
+        children: &children,
+        json_ad_href: None,
+    };
+    resource_to_html(resource, &context)
+}
+
+/// The folder of a page in the exported site, as path segments.
+/// Segments are percent-encoded, so every subject maps to a valid file name.
+/// Subjects with empty, `.` or `..` segments are not exported, as these would share a folder with another subject or end up outside the site.
+fn site_path(subject: &str, server_url: &str) -> Option<Vec<String>> {
+    let path = subject.strip_prefix(server_url)?;
+    if path.is_empty() {
+        return Some(Vec::new());
+    }
+    if !path.starts_with('/') || path.contains(['?', '#']) {
+        return None;
+    }
+    path[1..]
+        .split('/')
+        .map(|s| match s {
+            "" | "." | ".." => None,
+            s => Some(urlencoding::encode(s).into_owned()),
+        })
+        .collect()
+}
+
+/// A relative link from the page in `from` to the page in `to`.
+fn relative_href(from: &[String], to: &[String]) -> String {
+    let mut href = "../".repeat(from.len());
+    for segment in to {
+        // The file names are percent-encoded, so the `%` has to be encoded again in the URL.
+        href.push_str(&urlencoding::encode(segment));
+        href.push('/');
+    }
+    if href.is_empty() {
+        href.push_str("./");
+    }
+    href
+}
+
+/// Renders every Resource that can be read by the public to an HTML page in `dir`, with its JSON-AD next to it.
+/// A Resource at `{server_url}/foo/bar` is written to `foo/bar/index.html` and `foo/bar/index.json`.
+/// Links to exported Resources are relative, so the site can be hosted anywhere. Commits are not exported.
+/// Returns the amount of exported pages.
+#[tracing::instrument(skip(store))]
+pub fn export_site(store: &impl Storelike, dir: &std::path::Path) -> AtomicResult<usize> {
+    let server_url = store.get_server_url().trim_end_matches('/').to_string();
+    let mut pages: Vec<(Resource, Vec<String>)> = Vec::new();
+    for resource in hierarchy::public_resources(store) {
+        if let Some(path) = site_path(resource.get_subject(), &server_url) {
+            pages.push((resource, path));
+        }
+    }
+
+    let paths: HashMap<String, Vec<String>> = pages
+        .iter()
+        .map(|(r, path)| (r.get_subject().clone(), path.clone()))
+        .collect();
+    // External Resources (e.g. Properties) and public Resources can be shown by name.
+    let mut labels: HashMap<String, String> = HashMap::new();
+    for resource in store.all_resources(true) {
+        let subject = resource.get_subject();
+        if !subject.starts_with(&server_url) || paths.contains_key(subject) {
+            labels.insert(subject.clone(), resource_title(&resource));
+        }
+    }
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    for (resource, _) in pages.iter() {
+        if let Ok(parent) = resource.get(urls::PARENT) {
+            children
+                .entry(parent.to_string())
+                .or_default()
+                .push(resource.get_subject().clone());
+        }
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|s| labels.get(s).cloned().unwrap_or_default().to_lowercase());
+    }
+
+    let no_children = Vec::new();
+    for (resource, path) in pages.iter() {
+        let href = |subject: &str| match paths.get(subject) {
+            Some(target) => relative_href(path, target),
+            None => subject.to_string(),
+        };
+        let label = |subject: &str| labels.get(subject).cloned();
+        let context = PageContext {
+            href: &href,
+            label: &label,
+            children: children.get(resource.get_subject()).unwrap_or(&no_children),
+            json_ad_href: Some(SITE_JSON_AD_FILE),
+        };
+        let mut folder = dir.to_path_buf();
+        folder.extend(path);
+        std::fs::create_dir_all(&folder)
+            .map_err(|e| format!("Unable to create directory {:?}: {}", folder, e))?;
+        std::fs::write(
+            folder.join(SITE_PAGE_FILE),
+            resource_to_html_page(resource, &context),
+        )
+        .map_err(|e| format!("Unable to write page to {:?}: {}", folder, e))?;
+        std::fs::write(folder.join(SITE_JSON_AD_FILE), resource.to_json_ad()?)
+            .map_err(|e| format!("Unable to write JSON-AD to {:?}: {}", folder, e))?;
+    }
+    Ok(pages.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn markdown_is_sanitized() {
+        let href = |url: &str| url.replace("https://example.com", "..");
+        let html = markdown_to_html(
+            "# Hi\n\n<script>alert(1)</script>\n\n[link](https://example.com/page)",
+            &href,
+        );
+        assert!(html.contains("<h1>Hi</h1>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("<a href=\"../page\">link</a>"));
+
+        let html = markdown_to_html(
+            "[a](javascript:alert(1)) [b]( JaVaScRiPt:alert(2)) ![c](data:text/html,x) [d](<java\tscript:alert(3)>) [e](mailto:a@example.com) [f](/relative#top)",
+            &|url: &str| url.to_string(),
+        );
+        assert!(!html.to_lowercase().contains("script:"), "{}", html);
+        assert!(!html.contains("data:"), "{}", html);
+        assert_eq!(html.matches("href=\"#\"").count(), 3, "{}", html);
+        assert!(html.contains("href=\"mailto:a@example.com\""));
+        assert!(html.contains("href=\"/relative#top\""));
+    }
+
+    #[test]
+    fn export_public_site() {
+        let store = crate::Store::init().unwrap();
+        store.populate().unwrap();
+        let server_url = store.get_server_url().to_string();
+        let mut drive = Resource::new(server_url.clone());
+        drive.set_unsafe(urls::READ.into(), vec![urls::PUBLIC_AGENT].into());
+        drive.set_unsafe(urls::NAME.into(), Value::String("Docs".into()));
+        store.add_resource(&drive).unwrap();
+        let mut page = Resource::new(format!("{}/guide", server_url));
+        page.set_unsafe(urls::PARENT.into(), Value::AtomicUrl(server_url.clone()));
+        page.set_unsafe(urls::NAME.into(), Value::String("Guide".into()));
+        page.set_unsafe(
+            urls::DESCRIPTION.into(),
+            Value::Markdown(format!("Back to [the docs]({})", server_url)),
+        );
+        store.add_resource(&page).unwrap();
+        let secret = Resource::new(format!("{}/secret", server_url));
+        store.add_resource(&secret).unwrap();
+
+        let dir =
+            std::env::temp_dir().join(format!("atomic-site-{}", crate::utils::random_string(10)));
+        let count = export_site(&store, &dir).unwrap();
+        assert_eq!(count, 2);
+        let root = std::fs::read_to_string(dir.join(SITE_PAGE_FILE)).unwrap();
+        assert!(root.contains("<a href=\"guide/\">Guide</a>"));
+        let guide = std::fs::read_to_string(dir.join("guide").join(SITE_PAGE_FILE)).unwrap();
+        assert!(guide.contains("<a href=\"../\">the docs</a>"));
+        assert!(guide.contains("<nav><a href=\"../\">Docs</a></nav>"));
+        assert!(dir.join("guide").join(SITE_JSON_AD_FILE).exists());
+        assert!(!dir.join("secret").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn collection_pages_and_children() {
+        let store = crate::Store::init().unwrap();
+        store.populate().unwrap();
+        let mut collection =
+            Resource::new("https://example.com/c?page_size=2&current_page=1".into());
+        collection.set_unsafe(urls::COLLECTION_CURRENT_PAGE.into(), Value::Integer(1));
+        collection.set_unsafe(urls::COLLECTION_TOTAL_PAGES.into(), Value::Integer(3));
+        let html = render_resource_html(&store, &collection, &ForAgent::Sudo);
+        assert!(html.contains(
+            "<a rel=\"prev\" href=\"https://example.com/c?page_size=2&amp;current_page=0\">"
+        ));
+        assert!(html.contains("Page 2 of 3"));
+        assert!(html.contains("current_page=2\">Next page</a>"));
+
+        let mut child = Resource::new("https://example.com/c/child".into());
+        child.set_unsafe(
+            urls::PARENT.into(),
+            Value::AtomicUrl("https://example.com/c".into()),
+        );
+        child.set_unsafe(urls::NAME.into(), Value::String("Child".into()));
+        store.add_resource(&child).unwrap();
+        let parent = Resource::new("https://example.com/c".into());
+        let html = render_resource_html(&store, &parent, &ForAgent::Sudo);
+        assert!(html.contains("<li><a href=\"https://example.com/c/child\">Child</a></li>"));
+
+        // Unknown Resources are not fetched, and unsafe links are removed
+        let mut page = Resource::new("https://example.com/page".into());
+        page.set_unsafe(
+            urls::DESTINATION.into(),
+            Value::AtomicUrl("https://unknown.invalid/thing".into()),
+        );
+        page.set_unsafe(
+            urls::DESCRIPTION.into(),
+            Value::Markdown("[click](javascript:alert(1))".into()),
+        );
+        let html = render_resource_html(&store, &page, &ForAgent::Sudo);
+        assert!(html.contains(
+            "<a href=\"https://unknown.invalid/thing\">https://unknown.invalid/thing</a>"
+        ));
+        assert!(html.contains("<a href=\"#\">click</a>"), "{}", html);
+    }
+
+    #[test]
+    fn relative_links() {
+        let root: Vec<String> = vec![];
+        let page = vec!["docs".to_string(), "a%2Bb".to_string()];
+        assert_eq!(relative_href(&root, &page), "docs/a%252Bb/");
+        assert_eq!(relative_href(&page, &root), "../../");
+        assert_eq!(relative_href(&root, &root), "./");
+        assert_eq!(
+            site_path("https://example.com/docs/a+b", "https://example.com"),
+            Some(page)
+        );
+        assert_eq!(
+            site_path("https://example.com/c?page=1", "https://example.com"),
+            None
+        );
+        assert_eq!(
+            site_path("https://example.comm/c", "https://example.com"),
+            None
+        );
+        for escaping in ["/../x", "/a/./b", "/a//b", "/a/", "/"] {
+            assert_eq!(
+                site_path(
+                    &format!("https://example.com{}", escaping),
+                    "https://example.com"
+                ),
+                None,
+                "{}",
+                escaping
+            );
+        }
+        assert_eq!(
+            site_path("https://example.com/...", "https://example.com"),
+            Some(vec!["...".to_string()])
+        );
+    }
+}
@@ -0,0 +1,631 @@
+//! Generates [JSON Schema](https://json-schema.org/) documents from Classes, and validates JSON against them.
+//! Schemas describe either the JSON-AD shape (Property URLs as keys) or the plain JSON shape (shortnames as keys).
+//! The validator supports the keywords that are used in the generated schemas.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::{
+    datatype::DataType,
+    datetime::{DATE_TIME_REGEX, DURATION_REGEX, TIME_REGEX},
+    errors::AtomicResult,
+    lang::LANGUAGE_TAG_REGEX,
+    schema::{Class, Property},
+    values::{BIG_INT_REGEX, DATE_REGEX, DECIMAL_REGEX, SLUG_REGEX},
+    Storelike,
+};
+
+pub const JSON_SCHEMA_MIME: &str = "application/schema+json";
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+/// Path of the endpoint that serves the schemas. Used in `$id` and `$ref`.
+pub const JSON_SCHEMA_PATH: &str = "/json-schema";
+
+/// The keys that are used in the JSON objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonShape {
+    /// Property URLs as keys
+    JsonAd,
+    /// Property shortnames as keys, as in [crate::Resource::to_json]
+    Json,
+}
+
+impl JsonShape {
+    pub fn as_str(&self) -> &str {
+        match self {
+            JsonShape::JsonAd => "json-ad",
+            JsonShape::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for JsonShape {
+    type Err = crate::errors::AtomicError;
+
+    fn from_str(s: &str) -> AtomicResult<Self> {
+        match s {
+            "json-ad" => Ok(JsonShape::JsonAd),
+            "json" => Ok(JsonShape::Json),
+            other => Err(format!("Unknown shape '{}', use 'json-ad' or 'json'", other).into()),
+        }
+    }
+}
+
+/// A value that does not match the schema.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaError {
+    /// JSON Pointer to the invalid value, e.g. `/0/name`
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// The URL of the JSON Schema of a Class.
+pub fn schema_url(store: &impl Storelike, class_subject: &str, shape: JsonShape) -> String {
+    let query: String = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("class", class_subject)
+        .append_pair("shape", shape.as_str())
+        .finish();
+    format!("{}{}?{}", store.get_server_url(), JSON_SCHEMA_PATH, query)
+}
+
+/// Creates a JSON Schema for the instances of the Class.
+/// Required Properties are required in the schema, recommended ones are only described.
+/// Other keys are allowed, as Resources can have any Property.
+pub fn class_to_json_schema(
+    store: &impl Storelike,
+    class_subject: &str,
+    shape: JsonShape,
+) -> AtomicResult<JsonValue> {
+    let class = store.get_class(class_subject)?.with_inherited(store)?;
+    let mut properties = Map::new();
+    properties.insert(
+        "@id".into(),
+        json!({ "type": "string", "format": "uri", "description": "The subject of the resource" }),
+    );
+    let mut required = Vec::new();
+    for subject in class.requires.iter().chain(class.recommends.iter()) {
+        let property = store.get_property(subject)?;
+        let key = match shape {
+            JsonShape::JsonAd => property.subject.clone(),
+            JsonShape::Json => property.shortname.clone(),
+        };
+        if class.requires.contains(subject) && !required.contains(&key) {
+            required.push(key.clone());
+        }
+        properties.insert(key, property_to_json_schema(store, &property, shape));
+    }
+    Ok(json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "$id": schema_url(store, class_subject, shape),
+        "title": class.shortname,
+        "description": class.description,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": true,
+    }))
+}
+
+fn property_to_json_schema(
+    store: &impl Storelike,
+    property: &Property,
+    shape: JsonShape,
+) -> JsonValue {
+    let mut schema = match &property.data_type {
+        DataType::AtomicUrl => reference_schema(store, property, shape),
+        DataType::ResourceArray => json!({
+            "type": "array",
+            "items": reference_schema(store, property, shape),
+        }),
+        DataType::Boolean => json!({ "type": "boolean" }),
+        DataType::Date => json!({ "type": "string", "format": "date", "pattern": DATE_REGEX }),
+        DataType::Integer => json!({ "type": "integer" }),
+        DataType::Float => json!({ "type": "number" }),
+        // Serialized as strings to keep their precision, but numbers are accepted too
+        DataType::Decimal => json!({ "type": ["string", "number"], "pattern": DECIMAL_REGEX }),
+        DataType::BigInt => json!({ "type": ["string", "integer"], "pattern": BIG_INT_REGEX }),
+        DataType::GeoPoint => json!({
+            "type": "object",
+            "description": "A GeoJSON Point",
+            "required": ["type", "coordinates"],
+            "properties": {
+                "type": { "const": "Point" },
+                "coordinates": {
+                    "type": "array",
+                    "items": { "type": "number" },
+                    "minItems": 2,
+                    "maxItems": 2,
+                },
+            },
+        }),
+        DataType::LangString => json!({
+            "type": "object",
+            "description": "Texts by language tag",
+            "minProperties": 1,
+            "propertyNames": { "pattern": LANGUAGE_TAG_REGEX },
+            "additionalProperties": { "type": "string" },
+        }),
+        DataType::Duration => {
+            json!({ "type": "string", "format": "duration", "pattern": DURATION_REGEX })
+        }
+        DataType::Time => json!({ "type": "string", "format": "time", "pattern": TIME_REGEX }),
+        DataType::DateTime => {
+            json!({ "type": "string", "format": "date-time", "pattern": DATE_TIME_REGEX })
+        }
+        DataType::Markdown | DataType::String => json!({ "type": "string" }),
+        DataType::Slug => json!({ "type": "string", "pattern": SLUG_REGEX }),
+        DataType::Timestamp => json!({ "type": "integer", "minimum": 0 }),
+        DataType::Unsupported(_) => json!({}),
+    };
+    add_constraints(&mut schema, property);
+    schema["title"] = property.shortname.clone().into();
+    schema["description"] = property.description.clone().into();
+    schema["$comment"] = property.subject.clone().into();
+    schema
+}
+
+/// Adds the [crate::schema::ValueConstraints] of the Property as JSON Schema keywords.
+fn add_constraints(schema: &mut JsonValue, property: &Property) {
+    let constraints = &property.constraints;
+    match property.data_type {
+        DataType::Integer | DataType::Float | DataType::Decimal | DataType::BigInt => {
+            // Integer bounds are written as integers, so large ones don't get rounded
+            let minimum = match (constraints.min, constraints.min_float) {
+                (Some(min), Some(min_float)) if min_float > min as f64 => Some(number(min_float)),
+                (Some(min), _) => Some(min.into()),
+                (None, min_float) => min_float.map(number),
+            };
+            let maximum = match (constraints.max, constraints.max_float) {
+                (Some(max), Some(max_float)) if max_float < max as f64 => Some(number(max_float)),
+                (Some(max), _) => Some(max.into()),
+                (None, max_float) => max_float.map(number),
+            };
+            if let Some(minimum) = minimum {
+                schema["minimum"] = minimum;
+            }
+            if let Some(maximum) = maximum {
+                schema["maximum"] = maximum;
+            }
+        }
+        // The constraints apply to the text in every language
+        DataType::LangString => {
+            let mut text_schema = json!({ "type": "string" });
+            let text_property = Property {
+                data_type: DataType::String,
+                ..property.clone()
+            };
+            add_constraints(&mut text_schema, &text_property);
+            schema["additionalProperties"] = text_schema;
+        }
+        DataType::String
+        | DataType::Markdown
+        | DataType::Slug
+        | DataType::Date
+        | DataType::Duration
+        | DataType::Time
+        | DataType::DateTime => {
+            if let Some(min_length) = constraints.min_length {
+                schema["minLength"] = min_length.into();
+            }
+            if let Some(max_length) = constraints.max_length {
+                schema["maxLength"] = max_length.into();
+            }
+            if let Some(pattern) = &constraints.pattern {
+                // Slugs, Dates and times already have a pattern, and a schema can only have one.
+                if schema.get("pattern").is_some() {
+                    schema["allOf"] = json!([{ "pattern": pattern }]);
+                } else {
+                    schema["pattern"] = pattern.clone().into();
+                }
+            }
+        }
+        DataType::ResourceArray => {
+            if let Some(min) = constraints.min {
+                schema["minItems"] = min.into();
+            }
+            if let Some(max) = constraints.max {
+                schema["maxItems"] = max.into();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whole numbers are written as integers, so `minimum: 1` doesn't become `1.0`.
+fn number(n: f64) -> JsonValue {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        (n as i64).into()
+    } else {
+        n.into()
+    }
+}
+
+/// A link to a Resource: a subject, or a nested Resource that matches the `classtype`.
+/// Subjects can also be localIds when importing, so they are URI references.
+fn reference_schema(store: &impl Storelike, property: &Property, shape: JsonShape) -> JsonValue {
+    let subject = match &property.allows_only {
+        Some(allowed) => json!({ "enum": allowed }),
+        None => json!({ "type": "string", "format": "uri-reference" }),
+    };
+    let nested = match &property.class_type {
+        Some(classtype) => json!({ "$ref": schema_url(store, classtype, shape) }),
+        None => json!({ "type": "object" }),
+    };
+    json!({ "anyOf": [subject, nested] })
+}
+
+/// Validates JSON (an object, or an array of objects) against the schema of the Class.
+/// Returns all errors, so they can be reported at once.
+pub fn validate_json_with_class(
+    store: &impl Storelike,
+    class_subject: &str,
+    shape: JsonShape,
+    instance: &JsonValue,
+) -> AtomicResult<Vec<SchemaError>> {
+    let schema = class_to_json_schema(store, class_subject, shape)?;
+    let mut errors = Vec::new();
+    match instance {
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                validate_json(store, &schema, item, &format!("/{}", i), &mut errors);
+            }
+        }
+        other => validate_json(store, &schema, other, "", &mut errors),
+    }
+    Ok(errors)
+}
+
+/// Like [validate_json_with_class], but returns a single error that lists all problems.
+/// Used to check JSON before it is imported.
+pub fn check_json_with_class(
+    store: &impl Storelike,
+    class_subject: &str,
+    shape: JsonShape,
+    json: &str,
+) -> AtomicResult<()> {
+    let instance: JsonValue =
+        serde_json::from_str(json).map_err(|e| format!("Unable to parse JSON: {}", e))?;
+    let errors = validate_json_with_class(store, class_subject, shape, &instance)?;
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    Err(format!(
+        "JSON does not match the schema of {}: {}",
+        class_subject,
+        errors.join(", ")
+    )
+    .into())
+}
+
+/// Validates an instance against a schema, and adds the problems to `errors`.
+/// `$ref`s to schemas of this server are resolved, other references are not checked.
+pub fn validate_json(
+    store: &impl Storelike,
+    schema: &JsonValue,
+    instance: &JsonValue,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let mut error = |message: String| {
+        errors.push(SchemaError {
+            path: path.into(),
+            message,
+        })
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(JsonValue::as_str) {
+        match resolve_ref(store, reference) {
+            Some(Ok(resolved)) => validate_json(store, &resolved, instance, path, errors),
+            Some(Err(e)) => error(format!("Unable to resolve {}: {}", reference, e)),
+            None => {}
+        }
+        return;
+    }
+
+    if let Some(types) = schema.get("type") {
+        let matches = match types {
+            JsonValue::Array(types) => types.iter().any(|t| type_matches(t, instance)),
+            t => type_matches(t, instance),
+        };
+        if !matches {
+            error(format!("Expected {}, got {}", types, instance));
+            return;
+        }
+    }
+
+    if let Some(JsonValue::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(instance) {
+            error(format!(
+                "{} is not one of {}",
+                instance,
+                JsonValue::from(allowed.clone())
+            ));
+        }
+    }
+
+    if let Some(JsonValue::Array(options)) = schema.get("anyOf") {
+        let valid = options.iter().any(|option| {
+            let mut option_errors = Vec::new();
+            validate_json(store, option, instance, path, &mut option_errors);
+            option_errors.is_empty()
+        });
+        if !valid {
+            error(format!(
+                "{} does not match any of the allowed schemas",
+                instance
+            ));
+        }
+    }
+
+    if let Some(JsonValue::Array(subschemas)) = schema.get("allOf") {
+        for subschema in subschemas {
+            let mut sub_errors = Vec::new();
+            validate_json(store, subschema, instance, path, &mut sub_errors);
+            for sub_error in sub_errors {
+                error(sub_error.message);
+            }
+        }
+    }
+
+    if let JsonValue::String(s) = instance {
+        let length = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(JsonValue::as_u64) {
+            if length < min {
+                error(format!("'{}' is shorter than {} characters", s, min));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(JsonValue::as_u64) {
+            if length > max {
+                error(format!("'{}' is longer than {} characters", s, max));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(JsonValue::as_str) {
+            match crate::schema::compiled_pattern(pattern) {
+                Ok(re) if !re.is_match(s) => {
+                    error(format!("'{}' does not match the pattern {}", s, pattern))
+                }
+                Ok(_) => {}
+                Err(e) => error(e),
+            }
+        }
+        if schema.get("format").and_then(JsonValue::as_str) == Some("uri")
+            && crate::utils::check_valid_url(s).is_err()
+        {
+            error(format!("'{}' is not a valid URL", s));
+        }
+    }
+
+    if let Some(n) = instance.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(JsonValue::as_f64) {
+            if n < minimum {
+                error(format!("{} is less than the minimum of {}", n, minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(JsonValue::as_f64) {
+            if n > maximum {
+                error(format!("{} is more than the maximum of {}", n, maximum));
+            }
+        }
+    }
+
+    if let JsonValue::Array(items) = instance {
+        let count = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(JsonValue::as_u64) {
+            if count < min {
+                error(format!("Expected at least {} items, got {}", min, count));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(JsonValue::as_u64) {
+            if count > max {
+                error(format!("Expected at most {} items, got {}", max, count));
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                validate_json(store, item_schema, item, &format!("{}/{}", path, i), errors);
+            }
+        }
+    }
+
+    if let JsonValue::Object(object) = instance {
+        if let Some(JsonValue::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(JsonValue::as_str) {
+                if !object.contains_key(key) {
+                    errors.push(SchemaError {
+                        path: path.into(),
+                        message: format!("Missing required property '{}'", key),
+                    });
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(JsonValue::as_object);
+        for (key, value) in object {
+            let child_path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+            match properties.and_then(|p| p.get(key)) {
+                Some(property_schema) => {
+                    validate_json(store, property_schema, value, &child_path, errors)
+                }
+                None if schema.get("additionalProperties") == Some(&JsonValue::Bool(false)) => {
+                    errors.push(SchemaError {
+                        path: child_path,
+                        message: format!("Property '{}' is not allowed", key),
+                    })
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+fn type_matches(schema_type: &JsonValue, instance: &JsonValue) -> bool {
+    match schema_type.as_str() {
+        Some("object") => instance.is_object(),
+        Some("array") => instance.is_array(),
+        Some("string") => instance.is_string(),
+        Some("boolean") => instance.is_boolean(),
+        Some("null") => instance.is_null(),
+        Some("number") => instance.is_number(),
+        Some("integer") => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+/// Generates the schema for references to the JSON Schema endpoint of this server.
+/// Returns `None` for other URLs.
+fn resolve_ref(store: &impl Storelike, reference: &str) -> Option<AtomicResult<JsonValue>> {
+    let prefix = format!("{}{}?", store.get_server_url(), JSON_SCHEMA_PATH);
+    let query = reference.strip_prefix(&prefix)?;
+    let mut class = None;
+    let mut shape = JsonShape::JsonAd;
+    for (k, v) in url::form_urlencoded::parse(query.as_bytes()) {
+        match k.as_ref() {
+            "class" => class = Some(v.to_string()),
+            "shape" => match v.parse() {
+                Ok(s) => shape = s,
+                Err(e) => return Some(Err(e)),
+            },
+            _ => {}
+        }
+    }
+    let class = class?;
+    Some(class_to_json_schema(store, &class, shape))
+}
+
+impl Class {
+    /// Creates a JSON Schema for the instances of this Class, see [class_to_json_schema].
+    pub fn to_json_schema(
+        &self,
+        store: &impl Storelike,
+        shape: JsonShape,
+    ) -> AtomicResult<JsonValue> {
+        class_to_json_schema(store, &self.subject, shape)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::urls;
+
+    fn init() -> crate::Store {
+        let store = crate::Store::init().unwrap();
+        store.populate().unwrap();
+        store
+    }
+
+    #[test]
+    fn property_schema() {
+        let store = init();
+        let schema = class_to_json_schema(&store, urls::PROPERTY, JsonShape::Json).unwrap();
+        assert_eq!(schema["title"], "property");
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&"shortname".into()));
+        assert!(required.contains(&"datatype".into()));
+        assert_eq!(schema["properties"]["shortname"]["pattern"], SLUG_REGEX);
+        // The datatype is a link to one of the Datatypes
+        let datatype = &schema["properties"]["datatype"]["anyOf"];
+        assert!(datatype[0]["enum"].is_array() || datatype[0]["format"] == "uri-reference");
+        // A classtype is a $ref to the schema of that class
+        let json_ad = class_to_json_schema(&store, urls::PROPERTY, JsonShape::JsonAd).unwrap();
+        assert_eq!(
+            json_ad["properties"][urls::DATATYPE_PROP]["anyOf"][1]["$ref"],
+            schema_url(&store, urls::DATATYPE_CLASS, JsonShape::JsonAd)
+        );
+    }
+
+    #[test]
+    fn constraints_in_schema() {
+        let store = init();
+        let property = Property {
+            class_type: None,
+            data_type: DataType::Slug,
+            shortname: "code".into(),
+            description: "A short code".into(),
+            subject: "https://example.com/code".into(),
+            allows_only: None,
+            constraints: crate::schema::ValueConstraints {
+                max_length: Some(4),
+                pattern: Some("^x".into()),
+                ..Default::default()
+            },
+            default: None,
+        };
+        let schema = property_to_json_schema(&store, &property, JsonShape::Json);
+        assert_eq!(schema["maxLength"], 4);
+        assert_eq!(schema["pattern"], SLUG_REGEX);
+        let mut errors = Vec::new();
+        validate_json(&store, &schema, &json!("x-1"), "", &mut errors);
+        assert!(errors.is_empty(), "{:?}", errors);
+        validate_json(&store, &schema, &json!("y-12345"), "", &mut errors);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+
+    #[test]
+    fn validate_plain_json() {
+        let store = init();
+        let valid = json!({
+            "shortname": "valid-prop",
+            "description": "A property",
+            "datatype": urls::STRING,
+        });
+        let errors =
+            validate_json_with_class(&store, urls::PROPERTY, JsonShape::Json, &valid).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let invalid = json!([valid, {
+            "shortname": "Not A Slug",
+            "datatype": 5,
+        }]);
+        let errors =
+            validate_json_with_class(&store, urls::PROPERTY, JsonShape::Json, &invalid).unwrap();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 3, "{:?}", messages);
+        assert!(messages.contains(&"/1: Missing required property 'description'".to_string()));
+        assert!(messages.iter().any(|m| m.starts_with("/1/shortname: ")));
+        assert!(messages.iter().any(|m| m.starts_with("/1/datatype: ")));
+
+        let err = check_json_with_class(
+            &store,
+            urls::PROPERTY,
+            JsonShape::Json,
+            &invalid.to_string(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Missing required property"));
+    }
+
+    #[test]
+    fn validate_nested_with_ref() {
+        let store = init();
+        // A nested Datatype resource has to match the schema of the Datatype class
+        let instance = json!({
+            "@id": "https://example.com/prop",
+            urls::SHORTNAME: "prop",
+            urls::DESCRIPTION: "A property",
+            urls::DATATYPE_PROP: { urls::SHORTNAME: 5 },
+        });
+        let errors =
+            validate_json_with_class(&store, urls::PROPERTY, JsonShape::JsonAd, &instance).unwrap();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        // Slashes in keys are escaped in JSON Pointers
+        assert_eq!(
+            errors[0].path,
+            format!("/{}", urls::DATATYPE_PROP.replace('/', "~1"))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Language-tagged strings ([LangString]) for multilingual content.
+//! In JSON-AD, these are objects that map language tags to texts, e.g. `{"en": "Hello", "nl": "Hallo"}`.
+//! Servers can pick the text in the language of the client using [LangString::pick], see [crate::Resource::localize].
+
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::AtomicResult;
+
+/// A [BCP 47](https://www.rfc-editor.org/info/bcp47) language tag, such as `en` or `nl-BE`
+pub const LANGUAGE_TAG_REGEX: &str = r"^[a-zA-Z]{1,8}(-[a-zA-Z0-9]{1,8})*$";
+
+/// The same text in one or more languages.
+/// Language tags are stored in lowercase, as they are case-insensitive.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LangString {
+    texts: BTreeMap<String, String>,
+}
+
+impl LangString {
+    /// Checks the language tags. Needs at least one text.
+    pub fn new(texts: impl IntoIterator<Item = (String, String)>) -> AtomicResult<LangString> {
+        let re = Regex::new(LANGUAGE_TAG_REGEX).unwrap();
+        let mut map = BTreeMap::new();
+        for (language, text) in texts {
+            if !re.is_match(&language) {
+                return Err(format!("Not a valid language tag: '{}'", language).into());
+            }
+            map.insert(language.to_lowercase(), text);
+        }
+        if map.is_empty() {
+            return Err("A language-tagged string needs at least one language".into());
+        }
+        Ok(LangString { texts: map })
+    }
+
+    /// Parses a JSON object of language tags and texts, e.g. `{"en": "Hello", "nl": "Hallo"}`.
+    pub fn parse(value: &str) -> AtomicResult<LangString> {
+        let json: serde_json::Value = serde_json::from_str(value).map_err(|e| {
+            format!(
+                "Not a valid language-tagged string: {}. Use a JSON object like {{\"en\": \"Hello\"}}. {}",
+                value, e
+            )
+        })?;
+        LangString::from_json(&json)
+    }
+
+    /// Reads a JSON object of language tags and texts.
+    pub fn from_json(json: &serde_json::Value) -> AtomicResult<LangString> {
+        let serde_json::Value::Object(map) = json else {
+            return Err(format!("Expected an object of languages and texts, got {}", json).into());
+        };
+        let mut texts = Vec::new();
+        for (language, text) in map {
+            let serde_json::Value::String(text) = text else {
+                return Err(
+                    format!("The text for '{}' is not a string: {}", language, text).into(),
+                );
+            };
+            texts.push((language.clone(), text.clone()));
+        }
+        LangString::new(texts)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            self.texts
+                .iter()
+                .map(|(language, text)| (language.clone(), text.clone().into()))
+                .collect(),
+        )
+    }
+
+    /// Returns the text of exactly this language.
+    pub fn get(&self, language: &str) -> Option<&str> {
+        self.texts.get(&language.to_lowercase()).map(|t| t.as_str())
+    }
+
+    /// Pairs of language tags and texts, sorted by language tag.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.texts.iter().map(|(l, t)| (l.as_str(), t.as_str()))
+    }
+
+    /// The text of the first language tag. Use this if the language of the reader is unknown.
+    pub fn first(&self) -> &str {
+        self.texts.values().next().map(|t| t.as_str()).unwrap_or("")
+    }
+
+    /// Picks the text for the first of the preferred languages that is available, or `None` if none are.
+    /// A preferred language matches its more general tags (`en-GB` matches `en`),
+    /// and its more specific tags (`en` matches `en-GB`) if there is no exact match.
+    /// `*` matches any language.
+    pub fn pick(&self, languages: &[String]) -> Option<&str> {
+        self.pick_entry(languages).map(|(_, text)| text.as_str())
+    }
+
+    /// Keeps only the text that [LangString::pick] picks, together with its language tag.
+    pub fn localized(&self, languages: &[String]) -> Option<LangString> {
+        self.pick_entry(languages)
+            .map(|(language, text)| LangString {
+                texts: BTreeMap::from([(language.clone(), text.clone())]),
+            })
+    }
+
+    fn pick_entry(&self, languages: &[String]) -> Option<(&String, &String)> {
+        for language in languages {
+            if language == "*" {
+                return self.texts.iter().next();
+            }
+            let mut tag = language.to_lowercase();
+            let specific = format!("{}-", tag);
+            loop {
+                if let Some(entry) = self.texts.get_key_value(&tag) {
+                    return Some(entry);
+                }
+                match tag.rfind('-') {
+                    Some(i) => tag.truncate(i),
+                    None => break,
+                }
+            }
+            if let Some(entry) = self.texts.iter().find(|(l, _)| l.starts_with(&specific)) {
+                return Some(entry);
+            }
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for LangString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_pick() {
+        let hello =
+            LangString::parse(r#"{"en": "Hello", "nl-BE": "Hallo", "de": "Hallo!"}"#).unwrap();
+        assert_eq!(hello.get("NL-be"), Some("Hallo"));
+        assert_eq!(
+            hello.to_string(),
+            r#"{"de":"Hallo!","en":"Hello","nl-be":"Hallo"}"#
+        );
+
+        let pick = |languages: &[&str]| {
+            let languages: Vec<String> = languages.iter().map(|l| l.to_string()).collect();
+            hello.pick(&languages).map(String::from)
+        };
+        assert_eq!(pick(&["en-US", "nl"]).as_deref(), Some("Hello"));
+        assert_eq!(pick(&["fr", "nl"]).as_deref(), Some("Hallo"));
+        assert_eq!(pick(&["fr"]), None);
+        assert_eq!(pick(&["fr", "*"]).as_deref(), Some("Hallo!"));
+        assert_eq!(pick(&[]), None);
+
+        assert!(LangString::parse(r#"{"en US": "Hello"}"#).is_err());
+        assert!(LangString::parse(r#"{"en": 1}"#).is_err());
+        assert!(LangString::parse("{}").is_err());
+        assert!(LangString::parse("Hello").is_err());
+    }
+}
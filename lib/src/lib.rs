@@ -66,19 +66,31 @@ pub mod collections;
 pub mod commit;
 #[cfg(feature = "config")]
 pub mod config;
+#[cfg(feature = "csv")]
+pub mod csv;
 pub mod datatype;
+pub mod datetime;
 #[cfg(feature = "db")]
 pub mod db;
 #[cfg(feature = "db")]
 pub mod endpoints;
 pub mod errors;
+pub mod geo;
 pub mod hierarchy;
+#[cfg(feature = "html")]
+pub mod html;
+pub mod json_schema;
+pub mod lang;
 pub mod mapping;
+pub mod migrate;
 pub mod parse;
 #[cfg(feature = "db")]
 pub mod plugins;
 pub mod populate;
+#[cfg(feature = "rdf")]
+pub mod rdf;
 pub mod resources;
+pub mod rules;
 pub mod schema;
 pub mod serialize;
 pub mod store;
@@ -89,6 +101,8 @@ pub mod urls;
 pub mod utils;
 pub mod validate;
 pub mod values;
+#[cfg(feature = "vault")]
+pub mod vault;
 
 pub use atoms::Atom;
 pub use commit::Commit;
@@ -0,0 +1,329 @@
+//! Migrates the values of a Property when its [DataType] changes.
+//! Values are converted where possible, using Commits signed by the default Agent of the Store.
+//! Values that can't be converted are kept as they are, and listed in the [MigrationReport].
+//! A Commit that changes the datatype of a Property is rejected if any of its values can't be converted.
+
+use std::collections::HashSet;
+
+use crate::{
+    commit::CommitOpts,
+    datatype::DataType,
+    datetime::{days_from_civil, parse_date_time, timestamp_to_date_time},
+    errors::AtomicResult,
+    storelike::Query,
+    values::SubResource,
+    Storelike, Value,
+};
+
+/// The outcome of a [migrate_datatype].
+#[derive(Debug)]
+pub struct MigrationReport {
+    pub property: String,
+    pub datatype: DataType,
+    /// If true, nothing has been changed in the store.
+    pub dry_run: bool,
+    /// Subjects of the Resources with a value that is (or would be) converted
+    pub converted: Vec<String>,
+    /// Amount of values that already had the right datatype
+    pub unchanged: usize,
+    pub failures: Vec<MigrationFailure>,
+}
+
+/// A value that could not be converted to the new datatype.
+#[derive(Debug)]
+pub struct MigrationFailure {
+    pub subject: String,
+    pub value: String,
+    pub error: String,
+}
+
+impl std::fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} values of {} to {}: {} converted, {} unchanged, {} failed.",
+            if self.dry_run {
+                "Dry run, migrating"
+            } else {
+                "Migrated"
+            },
+            self.property,
+            self.datatype,
+            self.converted.len(),
+            self.unchanged,
+            self.failures.len()
+        )?;
+        for failure in &self.failures {
+            writeln!(
+                f,
+                "- {}: '{}'. {}",
+                failure.subject, failure.value, failure.error
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts the values of `property` in all Resources to `datatype`.
+/// Every changed Resource gets a Commit, signed by the default Agent of the Store. Rights are not checked.
+/// Does not change the Property itself, so the Property should already have the new `datatype`.
+/// Values in Nested Resources are not migrated.
+/// With `dry_run`, only reports what would change.
+#[tracing::instrument(skip(store))]
+pub fn migrate_datatype(
+    store: &impl Storelike,
+    property: &str,
+    datatype: &DataType,
+    dry_run: bool,
+) -> AtomicResult<MigrationReport> {
+    if let DataType::Unsupported(url) = datatype {
+        return Err(format!("Can't migrate values to unsupported datatype {}", url).into());
+    }
+    let mut query = Query::new();
+    query.property = Some(property.into());
+    query.include_external = true;
+    query.include_nested = false;
+    // Resource Arrays have an index entry for every item
+    let subjects: HashSet<String> = store.query(&query)?.subjects.into_iter().collect();
+
+    let mut report = MigrationReport {
+        property: property.into(),
+        datatype: datatype.clone(),
+        dry_run,
+        converted: Vec::new(),
+        unchanged: 0,
+        failures: Vec::new(),
+    };
+    for subject in subjects {
+        let mut resource = store.get_resource(&subject)?;
+        let Ok(value) = resource.get(property) else {
+            continue;
+        };
+        if &value.datatype() == datatype {
+            report.unchanged += 1;
+            continue;
+        }
+        let value = value.to_string();
+        let converted = match convert_value(resource.get(property)?, datatype) {
+            Ok(converted) => converted,
+            Err(e) => {
+                report.failures.push(MigrationFailure {
+                    value,
+                    subject,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if !dry_run {
+            if let Err(e) = commit_value(store, &mut resource, property, converted) {
+                report.failures.push(MigrationFailure {
+                    value,
+                    subject,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        }
+        report.converted.push(subject);
+    }
+    report.converted.sort();
+    report.failures.sort_by(|a, b| a.subject.cmp(&b.subject));
+    Ok(report)
+}
+
+/// Sets the converted value using a Commit, so it is part of the history of the Resource.
+fn commit_value(
+    store: &impl Storelike,
+    resource: &mut crate::Resource,
+    property: &str,
+    value: Value,
+) -> AtomicResult<()> {
+    let agent = store.get_default_agent()?;
+    resource.set(property.into(), value, store)?;
+    let commit = resource
+        .get_commit_builder()
+        .clone()
+        .sign(&agent, store, resource)?;
+    let opts = CommitOpts {
+        // Other values of the Resource may not be valid, but only this value is changed
+        validate_schema: false,
+        validate_signature: false,
+        validate_timestamp: false,
+        validate_rights: false,
+        validate_previous_commit: false,
+        validate_for_agent: None,
+        update_index: true,
+    };
+    commit.apply_opts(store, &opts)?;
+    Ok(())
+}
+
+/// Converts a Value to another DataType, e.g. the String `"12"` to the Integer `12`.
+/// Most values are converted through their string representation.
+/// Fails if the value does not fit the new DataType, or if information would be lost.
+pub fn convert_value(value: &Value, datatype: &DataType) -> AtomicResult<Value> {
+    if &value.datatype() == datatype {
+        return Ok(value.clone());
+    }
+    let converted = match (value, datatype) {
+        (Value::AtomicUrl(subject), DataType::ResourceArray) => {
+            Value::ResourceArray(vec![SubResource::Subject(subject.clone())])
+        }
+        (Value::ResourceArray(items), DataType::AtomicUrl) => match items.as_slice() {
+            [SubResource::Subject(subject)] => Value::AtomicUrl(subject.clone()),
+            _ => return Err("Only a single subject can become an Atomic URL".into()),
+        },
+        (Value::ResourceArray(_) | Value::NestedResource(_) | Value::Resource(_), _) => {
+            return Err("Resources can only be converted to Atomic URLs or Resource Arrays".into())
+        }
+        (Value::LangString(texts), _) => {
+            let mut iter = texts.iter();
+            match (iter.next(), iter.next()) {
+                (Some((_language, text)), None) => Value::new(text, datatype)?,
+                _ => return Err("The text has more than one language".into()),
+            }
+        }
+        (Value::Timestamp(milliseconds), DataType::DateTime) => {
+            Value::DateTime(timestamp_to_date_time(*milliseconds))
+        }
+        (Value::DateTime(date_time), DataType::Timestamp) => {
+            Value::Timestamp((parse_date_time(date_time)? / 1_000_000) as i64)
+        }
+        (Value::Date(date), DataType::DateTime) => Value::DateTime(format!("{}T00:00:00Z", date)),
+        (Value::Date(date), DataType::Timestamp) => {
+            let mut parts = date.split('-').map(|p| p.parse::<i64>().unwrap_or(1));
+            let (year, month, day) = (
+                parts.next().unwrap_or(1970),
+                parts.next().unwrap_or(1),
+                parts.next().unwrap_or(1),
+            );
+            Value::Timestamp(days_from_civil(year, month, day) * 86_400_000)
+        }
+        (other, _) => Value::new(&other.to_string(), datatype)?,
+    };
+    Ok(converted)
+}
+
+/// Returns the new datatype, if the Commit changes it.
+#[cfg(feature = "db")]
+fn changed_datatype(
+    resource_old: &crate::Resource,
+    resource_new: &crate::Resource,
+) -> Option<DataType> {
+    let old = resource_old.get(crate::urls::DATATYPE_PROP).ok()?;
+    let new = resource_new.get(crate::urls::DATATYPE_PROP).ok()?;
+    if old.to_string() == new.to_string() {
+        return None;
+    }
+    Some(crate::datatype::match_datatype(&new.to_string()))
+}
+
+/// Checks whether a Commit can change the datatype of a Property.
+/// Throws with a [MigrationReport] if some values can't be converted, so they can be fixed first.
+/// Also throws if the `for_agent` can't edit one of the Resources that have a value.
+#[cfg(feature = "db")]
+pub(crate) fn before_apply_commit_property(
+    store: &impl Storelike,
+    resource_old: &crate::Resource,
+    resource_new: &crate::Resource,
+    for_agent: &crate::agents::ForAgent,
+) -> AtomicResult<()> {
+    let Some(datatype) = changed_datatype(resource_old, resource_new) else {
+        return Ok(());
+    };
+    let report = migrate_datatype(store, resource_new.get_subject(), &datatype, true)?;
+    if !report.failures.is_empty() {
+        return Err(crate::AtomicError {
+            message: format!(
+                "The datatype can't be changed, because some values can't be converted. Change or remove these first. {}",
+                report
+            ),
+            error_type: crate::AtomicErrorType::ConflictError,
+            subject: Some(resource_new.get_subject().into()),
+        });
+    }
+    if for_agent != &crate::agents::ForAgent::Sudo {
+        for subject in &report.converted {
+            crate::hierarchy::check_write(store, &store.get_resource(subject)?, for_agent)
+                .map_err(|mut e| {
+                    e.message = format!(
+                        "The datatype can't be changed, because the value in {} has to be converted, and you can't edit it. {}",
+                        subject, e.message
+                    );
+                    e
+                })?;
+        }
+    }
+    Ok(())
+}
+
+/// Migrates the values of a Property after a Commit changed its datatype.
+/// [before_apply_commit_property] has checked that all values can be converted.
+/// Values that changed in the meantime and can't be converted are logged, they need to be fixed by hand.
+#[cfg(feature = "db")]
+pub(crate) fn after_apply_commit_property(
+    store: &impl Storelike,
+    resource_old: &crate::Resource,
+    resource_new: &crate::Resource,
+) -> AtomicResult<()> {
+    let Some(datatype) = changed_datatype(resource_old, resource_new) else {
+        return Ok(());
+    };
+    let report = migrate_datatype(store, resource_new.get_subject(), &datatype, false)?;
+    if report.failures.is_empty() {
+        tracing::info!("{}", report);
+    } else {
+        tracing::warn!("{}", report);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::urls;
+
+    #[test]
+    fn converts_values() {
+        let convert = |value: Value, datatype: DataType| {
+            convert_value(&value, &datatype).map(|v| (v.to_string(), v.datatype()))
+        };
+        assert_eq!(
+            convert(Value::String("12".into()), DataType::Integer).unwrap(),
+            ("12".into(), DataType::Integer)
+        );
+        convert(Value::String("twelve".into()), DataType::Integer).unwrap_err();
+        convert(Value::Float(1.5), DataType::Integer).unwrap_err();
+        assert_eq!(
+            convert(Value::Integer(12), DataType::Decimal).unwrap().0,
+            "12"
+        );
+        assert_eq!(
+            convert(Value::Timestamp(1_596_798_919_000), DataType::DateTime)
+                .unwrap()
+                .0,
+            "2020-08-07T11:15:19Z"
+        );
+        assert_eq!(
+            convert(Value::Date("2020-08-07".into()), DataType::Timestamp)
+                .unwrap()
+                .0,
+            "1596758400000"
+        );
+        assert_eq!(
+            convert(
+                Value::AtomicUrl(urls::CLASS.into()),
+                DataType::ResourceArray
+            )
+            .unwrap()
+            .1,
+            DataType::ResourceArray
+        );
+        convert(
+            vec![urls::CLASS, urls::PROPERTY].into(),
+            DataType::AtomicUrl,
+        )
+        .unwrap_err();
+    }
+}
@@ -1,12 +1,16 @@
 //! Parsing / deserialization / decoding
 
+use std::collections::HashMap;
+
 use crate::{
     agents::ForAgent, commit::CommitOpts, datatype::DataType, errors::AtomicResult,
-    resources::PropVals, urls, utils::check_valid_url, values::SubResource, AtomicError, Resource,
-    Storelike, Value,
+    resources::PropVals, schema::Property, urls, utils::check_valid_url, values::SubResource,
+    AtomicError, Resource, Storelike, Value,
 };
 
 pub const JSON_AD_MIME: &str = "application/ad+json";
+/// Binary JSON-AD, see [crate::serialize::cbor]
+pub const JSON_AD_CBOR_MIME: &str = "application/ad+cbor";
 
 pub fn parse_json_array(string: &str) -> AtomicResult<Vec<String>> {
     let vector: Vec<String> = serde_json::from_str(string)?;
@@ -103,14 +107,176 @@ pub fn parse_json_ad_string(
 ) -> AtomicResult<Vec<Resource>> {
     let parsed: serde_json::Value = serde_json::from_str(string)
         .map_err(|e| AtomicError::parse_error(&format!("Invalid JSON: {}", e), None, None))?;
+    parse_json_ad_value(parsed, store, parse_opts)
+}
+
+/// Parses binary JSON-AD (CBOR), as created by [crate::serialize::resources_to_json_ad_cbor].
+/// Accepts an array containing multiple objects, or one single object.
+#[tracing::instrument(skip(store, bytes))]
+pub fn parse_json_ad_cbor(
+    bytes: &[u8],
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<Vec<Resource>> {
+    parse_json_ad_value(cbor_to_json(bytes)?, store, parse_opts)
+}
+
+/// Parses plain JSON, as created by [Resource::to_json], as instances of the Class.
+/// Keys are the shortnames of Properties (resolved with [Resource::resolve_shortname_to_property]) or Property URLs,
+/// and values are converted to the [DataType] of the Property.
+/// Objects without an `@id` get a new subject.
+/// Accepts an array containing multiple objects, or one single object.
+#[tracing::instrument(skip(store))]
+pub fn parse_json_with_class(
+    string: &str,
+    class_subject: &str,
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<Vec<Resource>> {
+    let parsed: serde_json::Value = serde_json::from_str(string)
+        .map_err(|e| AtomicError::parse_error(&format!("Invalid JSON: {}", e), None, None))?;
+    let objects = match parsed {
+        serde_json::Value::Array(arr) => arr,
+        obj @ serde_json::Value::Object(_) => vec![obj],
+        _other => return Err("Root JSON element must be an object or array.".into()),
+    };
+    parse_plain_json_objects(objects, class_subject, store, parse_opts)
+}
+
+/// Parses plain JSON objects as instances of the Class, see [parse_json_with_class].
+pub(crate) fn parse_plain_json_objects(
+    objects: Vec<serde_json::Value>,
+    class_subject: &str,
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<Vec<Resource>> {
+    // Used for resolving shortnames. Resolved Properties are cached, as resolving is costly.
+    let mut template = Resource::new(class_subject.into());
+    template.set_class(class_subject);
+    let mut properties: HashMap<String, Property> = HashMap::new();
+
+    let mut json_ad = Vec::new();
+    for (i, item) in objects.into_iter().enumerate() {
+        let serde_json::Value::Object(obj) = item else {
+            return Err(format!("Item {} is not an object", i).into());
+        };
+        let map = plain_json_to_json_ad(obj, &template, &mut properties, store)
+            .map_err(|e| format!("Unable to convert item {} to JSON-AD. {}", i, e))?;
+        json_ad.push(serde_json::Value::Object(map));
+    }
+    parse_json_ad_value(serde_json::Value::Array(json_ad), store, parse_opts)
+}
+
+/// Replaces the shortnames with Property URLs and converts the values to the right datatype.
+fn plain_json_to_json_ad(
+    obj: Map<String, serde_json::Value>,
+    template: &Resource,
+    properties: &mut HashMap<String, Property>,
+    store: &impl Storelike,
+) -> AtomicResult<Map<String, serde_json::Value>> {
+    let mut map = Map::new();
+    for (key, value) in obj {
+        if key == "@id" {
+            map.insert(key, value);
+            continue;
+        }
+        if value.is_null() {
+            continue;
+        }
+        let property = match properties.get(&key) {
+            Some(property) => property.clone(),
+            None => {
+                let property = resolve_plain_json_key(&key, template, store)?;
+                properties.insert(key.clone(), property.clone());
+                property
+            }
+        };
+        let value = match &property.data_type {
+            // These can contain localIds and nested resources, which the JSON-AD parser handles.
+            DataType::AtomicUrl | DataType::ResourceArray => value,
+            datatype => {
+                let text = match value {
+                    serde_json::Value::String(s) => s,
+                    serde_json::Value::Number(n) => n.to_string(),
+                    serde_json::Value::Bool(b) => b.to_string(),
+                    object @ serde_json::Value::Object(_)
+                        if matches!(datatype, DataType::GeoPoint | DataType::LangString) =>
+                    {
+                        object.to_string()
+                    }
+                    other => {
+                        return Err(
+                            format!("Expected a {} for '{}', got {}", datatype, key, other).into(),
+                        )
+                    }
+                };
+                let value = Value::new(&text, datatype)
+                    .map_err(|e| format!("Invalid value for '{}': {}", key, e))?;
+                crate::serialize::val_to_serde(value)?
+            }
+        };
+        map.insert(property.subject, value);
+    }
+    if !map.contains_key(urls::IS_A) {
+        map.insert(
+            urls::IS_A.into(),
+            template.get(urls::IS_A)?.to_subjects(None)?.into(),
+        );
+    }
+    if !map.contains_key("@id") {
+        let class = template.get_subject();
+        let subject = Resource::new_instance_subject(class, store)?;
+        map.insert("@id".into(), subject.into());
+    }
+    Ok(map)
+}
+
+/// Resolves a key using the Class. Keys that are not used by the Class, such as `parent`, are looked up in all Properties.
+pub(crate) fn resolve_plain_json_key(
+    key: &str,
+    template: &Resource,
+    store: &impl Storelike,
+) -> AtomicResult<Property> {
+    if let Ok(property) = template.resolve_shortname_to_property(key, store) {
+        return Ok(property);
+    }
+    let query = crate::storelike::Query::new_prop_val(urls::SHORTNAME, key);
+    let mut found = store
+        .query(&query)?
+        .subjects
+        .into_iter()
+        .filter_map(|subject| store.get_property(&subject).ok());
+    match (found.next(), found.next()) {
+        (Some(property), None) => Ok(property),
+        (Some(_), Some(_)) => Err(format!(
+            "Shortname '{}' is used by multiple Properties, use the Property URL instead",
+            key
+        )
+        .into()),
+        (None, _) => {
+            Err(format!("'{}' is not a Property of {}", key, template.get_subject()).into())
+        }
+    }
+}
+
+fn parse_json_ad_value(
+    parsed: serde_json::Value,
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<Vec<Resource>> {
     let mut vec = Vec::new();
     match parsed {
         serde_json::Value::Array(arr) => {
             for item in arr {
                 match item {
                     serde_json::Value::Object(obj) => {
-                        let resource = json_ad_object_to_resource(obj, store, parse_opts)
-                            .map_err(|e| format!("Unable to process resource in array. {}", e))?;
+                        let resource = json_ad_object_to_resource(obj, store, parse_opts).map_err(
+                            |mut e| {
+                                e.message =
+                                    format!("Unable to process resource in array. {}", e.message);
+                                e
+                            },
+                        )?;
                         vec.push(resource);
                     }
                     wrong => {
@@ -122,21 +288,79 @@ pub fn parse_json_ad_string(
             }
         }
         serde_json::Value::Object(obj) => vec.push(
-            json_ad_object_to_resource(obj, store, parse_opts)
-                .map_err(|e| format!("Unable to parse object. {}", e))?,
+            json_ad_object_to_resource(obj, store, parse_opts).map_err(|mut e| {
+                e.message = format!("Unable to parse object. {}", e.message);
+                e
+            })?,
         ),
         _other => return Err("Root JSON element must be an object or array.".into()),
     }
-    // For most save menthods, we need to add the atoms to the index here.
-    // The `Commit` feature adds to index by itself, so we can skip that step here.
+    add_parsed_to_index(&vec, store, parse_opts)?;
+    Ok(vec)
+}
+
+/// Parses a Newline Delimited JSON-AD stream, where every line contains a single JSON-AD object.
+/// Lines are parsed and saved in batches of `batch_size`, so the full input is never kept in memory.
+/// Calls `on_progress` with the total amount of parsed resources after every batch.
+/// Returns the amount of parsed resources.
+#[tracing::instrument(skip(reader, store, on_progress))]
+pub fn parse_ndjson_ad(
+    reader: impl std::io::BufRead,
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+    batch_size: usize,
+    on_progress: &mut dyn FnMut(usize),
+) -> AtomicResult<usize> {
+    if batch_size < 1 {
+        return Err("Batch size must be greater than 0".into());
+    }
+    let mut count = 0;
+    let mut batch: Vec<Resource> = Vec::with_capacity(batch_size);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let obj: Map<String, serde_json::Value> = serde_json::from_str(&line).map_err(|e| {
+            AtomicError::parse_error(
+                &format!("Invalid JSON on line {}: {}", i + 1, e),
+                None,
+                None,
+            )
+        })?;
+        let resource = json_ad_object_to_resource(obj, store, parse_opts)
+            .map_err(|e| format!("Unable to process resource on line {}. {}", i + 1, e))?;
+        batch.push(resource);
+        if batch.len() >= batch_size {
+            add_parsed_to_index(&batch, store, parse_opts)?;
+            count += batch.len();
+            batch.clear();
+            on_progress(count);
+        }
+    }
+    if !batch.is_empty() {
+        add_parsed_to_index(&batch, store, parse_opts)?;
+        count += batch.len();
+        on_progress(count);
+    }
+    Ok(count)
+}
+
+/// For most save methods, we need to add the atoms to the index after parsing.
+/// The `Commit` feature adds to index by itself, so we can skip that step there.
+pub(crate) fn add_parsed_to_index(
+    resources: &[Resource],
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<()> {
     if parse_opts.save != SaveOpts::Commit {
-        for res in &vec {
+        for res in resources {
             for atom in res.to_atoms() {
                 store.add_atom_to_index(&atom, res)?;
             }
         }
     }
-    Ok(vec)
+    Ok(())
 }
 
 /// Parse a single Json AD string that represents an incoming Commit.
@@ -148,6 +372,25 @@ pub fn parse_json_ad_commit_resource(
     store: &impl crate::Storelike,
 ) -> AtomicResult<Resource> {
     let json: Map<String, serde_json::Value> = serde_json::from_str(string)?;
+    json_ad_commit_map_to_resource(json, store)
+}
+
+/// Parse a single binary JSON-AD (CBOR) Commit, see [parse_json_ad_commit_resource].
+#[tracing::instrument(skip(store, bytes))]
+pub fn parse_json_ad_cbor_commit_resource(
+    bytes: &[u8],
+    store: &impl crate::Storelike,
+) -> AtomicResult<Resource> {
+    match cbor_to_json(bytes)? {
+        serde_json::Value::Object(json) => json_ad_commit_map_to_resource(json, store),
+        _other => Err("Commit should be an object".into()),
+    }
+}
+
+fn json_ad_commit_map_to_resource(
+    json: Map<String, serde_json::Value>,
+    store: &impl crate::Storelike,
+) -> AtomicResult<Resource> {
     let signature = json
         .get(urls::SUBJECT)
         .ok_or("No subject field in Commit.")?
@@ -167,6 +410,83 @@ pub fn parse_json_ad_commit_resource(
     Ok(resource)
 }
 
+/// Decodes binary JSON-AD to the JSON-AD it represents, resolving [stringref](http://cbor.schmorp.de/stringref) references.
+fn cbor_to_json(bytes: &[u8]) -> AtomicResult<serde_json::Value> {
+    let cbor: ciborium::value::Value = ciborium::de::from_reader(bytes).map_err(|e| {
+        AtomicError::parse_error(&format!("Invalid binary JSON-AD: {}", e), None, None)
+    })?;
+    // Outside of a namespace, references are not allowed
+    cbor_value_to_json(cbor, &mut None)
+}
+
+/// Converts in the order in which the values were written, because strings are added to the `table` in that order.
+fn cbor_value_to_json(
+    value: ciborium::value::Value,
+    table: &mut Option<Vec<String>>,
+) -> AtomicResult<serde_json::Value> {
+    use crate::serialize::cbor::{stringref_min_length, STRINGREF_NAMESPACE_TAG, STRINGREF_TAG};
+    use ciborium::value::Value as CborValue;
+
+    let json = match value {
+        CborValue::Null => serde_json::Value::Null,
+        CborValue::Bool(b) => serde_json::Value::Bool(b),
+        CborValue::Integer(i) => {
+            let i: i128 = i.into();
+            match i64::try_from(i) {
+                Ok(i) => i.into(),
+                Err(_) => u64::try_from(i)
+                    .map_err(|_| format!("Integer {} is too large for JSON-AD", i))?
+                    .into(),
+            }
+        }
+        CborValue::Float(f) => serde_json::Number::from_f64(f)
+            .ok_or_else(|| format!("Float {} is not valid in JSON-AD", f))?
+            .into(),
+        CborValue::Text(s) => {
+            if let Some(table) = table {
+                if s.len() >= stringref_min_length(table.len()) {
+                    table.push(s.clone());
+                }
+            }
+            serde_json::Value::String(s)
+        }
+        CborValue::Array(items) => serde_json::Value::Array(
+            items
+                .into_iter()
+                .map(|item| cbor_value_to_json(item, table))
+                .collect::<AtomicResult<_>>()?,
+        ),
+        CborValue::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let serde_json::Value::String(key) = cbor_value_to_json(key, table)? else {
+                    return Err("Keys in binary JSON-AD must be strings".into());
+                };
+                map.insert(key, cbor_value_to_json(value, table)?);
+            }
+            serde_json::Value::Object(map)
+        }
+        CborValue::Tag(STRINGREF_NAMESPACE_TAG, inner) => {
+            cbor_value_to_json(*inner, &mut Some(Vec::new()))?
+        }
+        CborValue::Tag(STRINGREF_TAG, index) => {
+            let table = table
+                .as_ref()
+                .ok_or("String reference outside of a stringref namespace")?;
+            let index = index
+                .as_integer()
+                .and_then(|i| usize::try_from(i).ok())
+                .ok_or("String reference must be an unsigned integer")?;
+            let s = table
+                .get(index)
+                .ok_or_else(|| format!("String reference {} does not exist", index))?;
+            serde_json::Value::String(s.clone())
+        }
+        other => return Err(format!("Unsupported value in binary JSON-AD: {:?}", other).into()),
+    };
+    Ok(json)
+}
+
 /// Parse a single Json AD string, convert to Atoms
 /// Does not match all props to datatypes, so it could result in invalid data.
 /// Adds to the store if `add` is true.
@@ -228,7 +548,9 @@ fn parse_json_ad_map_to_resource(
                 let property = store.get_property(&prop)?;
                 // Also converts numbers to strings, not sure what to think about this.
                 // Does not result in invalid atomic data, but does allow for weird inputs
-                Value::new(&num.to_string(), &property.data_type)?
+                let value = Value::new(&num.to_string(), &property.data_type)?;
+                check_parsed_value(&property, &value, parse_opts)?;
+                value
             }
             serde_json::Value::String(str) => {
                 // LocalIDs are mapped to @ids by appending the `localId` to the `importer`'s `parent`.
@@ -249,20 +571,22 @@ fn parse_json_ad_map_to_resource(
                     )
                 })?;
 
-                match property.data_type {
+                let value = match &property.data_type {
                     DataType::AtomicUrl => {
                         // If the value is not a valid URL, and we have an importer, we can generate_id_from_local_id
                         let url = try_to_subject(&str, &prop)?;
                         Value::new(&url, &property.data_type)?
                     }
-                    other => Value::new(&str.to_string(), &other).map_err(|e| {
+                    other => Value::new(&str.to_string(), other).map_err(|e| {
                         AtomicError::parse_error(
                             &format!("Unable to parse value for prop {prop}: {e}. Value: {str}"),
                             subject.as_deref(),
                             Some(&prop),
                         )
                     })?,
-                }
+                };
+                check_parsed_value(&property, &value, parse_opts)?;
+                value
             }
             // In Atomic Data, all arrays are Resource Arrays which are serialized JSON things.
             // Maybe this step could be simplified? Just serialize to string?
@@ -288,7 +612,30 @@ fn parse_json_ad_map_to_resource(
                         }
                     }
                 }
-                Value::ResourceArray(newvec)
+                let value = Value::ResourceArray(newvec);
+                if parse_opts.save == SaveOpts::Save {
+                    if let Ok(property) = store.get_property(&prop) {
+                        check_parsed_value(&property, &value, parse_opts)?;
+                    }
+                }
+                value
+            }
+            // JSON-AD keys are URLs, so other keys (GeoJSON's `type`, language tags) mean this is a value instead of a Nested Resource
+            serde_json::Value::Object(map) if map.keys().any(|k| !is_json_ad_key(k)) => {
+                let property = store.get_property(&prop)?;
+                let value = Value::new(
+                    &serde_json::Value::Object(map).to_string(),
+                    &property.data_type,
+                )
+                .map_err(|e| {
+                    AtomicError::parse_error(
+                        &format!("Unable to parse value for prop {prop}: {e}"),
+                        subject.as_deref(),
+                        Some(&prop),
+                    )
+                })?;
+                check_parsed_value(&property, &value, parse_opts)?;
+                value
             }
             serde_json::Value::Object(map) => {
                 Value::NestedResource(parse_json_ad_map_to_resource(map, store, parse_opts)?)
@@ -304,67 +651,109 @@ fn parse_json_ad_map_to_resource(
         }
     }
     if let Some(subj) = { subject } {
-        let r = match &parse_opts.save {
-            SaveOpts::DontSave => {
-                let mut r = Resource::new(subj);
-                r.set_propvals_unsafe(propvals);
-                r
+        Ok(save_parsed_resource(subj, propvals, store, parse_opts)?.into())
+    } else {
+        Ok(SubResource::Nested(propvals))
+    }
+}
+
+/// Keys of JSON-AD objects are Property URLs, or special keys such as `@id`.
+fn is_json_ad_key(key: &str) -> bool {
+    key.starts_with('@') || key.contains(':')
+}
+
+/// Checks the constraints of the Property (e.g. `min`, `max` and `pattern`) for values that are saved without Commits.
+/// When saving with Commits, these are checked in [Resource::set].
+fn check_parsed_value(
+    property: &crate::schema::Property,
+    value: &Value,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<()> {
+    if parse_opts.save == SaveOpts::Save {
+        property.check_constraints(value)?;
+    }
+    Ok(())
+}
+
+/// Creates a Resource from parsed PropVals, and saves it according to the [SaveOpts] of the [ParseOpts].
+/// Does not add the Resource to the index, see [add_parsed_to_index].
+pub(crate) fn save_parsed_resource(
+    subj: String,
+    propvals: PropVals,
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<Resource> {
+    let r = match &parse_opts.save {
+        SaveOpts::DontSave => {
+            let mut r = Resource::new(subj);
+            r.set_propvals_unsafe(propvals);
+            r
+        }
+        SaveOpts::Save => {
+            let mut r = Resource::new(subj);
+            r.set_propvals_unsafe(propvals);
+            // Classes can be defined further on in the imported data, so only the available ones are checked
+            if let Ok(classes) = r.get_classes(store) {
+                crate::rules::check_rules(&r, &classes, store)?;
             }
-            SaveOpts::Save => {
-                let mut r = Resource::new(subj);
-                r.set_propvals_unsafe(propvals);
-                store.add_resource(&r)?;
-                r
+            // The lock is held until the Resource is added to the index, so later Resources can't claim the same values
+            let unique_values_lock = r.lock_unique_values(store).unwrap_or_default();
+            if !unique_values_lock.is_empty() {
+                r.check_unique_values(store)?;
             }
-            SaveOpts::Commit => {
-                let mut r = if let Ok(orig) = store.get_resource(&subj) {
-                    // If the resource already exists, and overwrites outside are not permitted, and it does not have the importer as parent...
-                    // Then we throw!
-                    // Because this would enable malicious users to overwrite resources that they shouldn't.
-                    if !parse_opts.overwrite_outside {
-                        let importer = parse_opts.importer.as_deref().unwrap();
-                        if !orig.has_parent(store, importer) {
-                            Err(
-                                format!("Cannot overwrite {subj} outside of importer! Enable `overwrite_outside`"),
-                            )?
-                        }
-                    };
-                    orig
-                } else {
-                    Resource::new(subj)
-                };
-                for (prop, val) in propvals {
-                    r.set(prop, val, store)?;
-                }
-                let signer = parse_opts
-                    .signer
-                    .clone()
-                    .ok_or("No agent to sign Commit with. Either pass a `for_agent` or ")?;
-                let commit = r.get_commit_builder().clone().sign(&signer, store, &r)?;
-                let opts = CommitOpts {
-                    validate_schema: true,
-                    validate_signature: true,
-                    validate_timestamp: false,
-                    validate_rights: parse_opts.for_agent != ForAgent::Sudo,
-                    validate_previous_commit: false,
-                    validate_for_agent: Some(parse_opts.for_agent.to_string()),
-                    update_index: true,
+            store.add_resource(&r)?;
+            drop(unique_values_lock);
+            r
+        }
+        SaveOpts::Commit => {
+            let mut r = if let Ok(orig) = store.get_resource(&subj) {
+                // If the resource already exists, and overwrites outside are not permitted, and it does not have the importer as parent...
+                // Then we throw!
+                // Because this would enable malicious users to overwrite resources that they shouldn't.
+                if !parse_opts.overwrite_outside {
+                    let importer = parse_opts.importer.as_deref().unwrap();
+                    if !orig.has_parent(store, importer) {
+                        Err(
+                            format!("Cannot overwrite {subj} outside of importer! Enable `overwrite_outside`"),
+                        )?
+                    }
                 };
-
-                commit
-                    .apply_opts(store, &opts)
-                    .map_err(|e| format!("Failed to save {}: {}", r.get_subject(), e))?
-                    .resource_new
-                    .unwrap()
+                orig
+            } else {
+                Resource::new(subj)
+            };
+            for (prop, val) in propvals {
+                r.set(prop, val, store)?;
             }
-        };
-        Ok(r.into())
-    } else {
-        Ok(SubResource::Nested(propvals))
-    }
+            let signer = parse_opts
+                .signer
+                .clone()
+                .ok_or("No agent to sign Commit with. Either pass a `for_agent` or ")?;
+            let commit = r.get_commit_builder().clone().sign(&signer, store, &r)?;
+            let opts = CommitOpts {
+                validate_schema: true,
+                validate_signature: true,
+                validate_timestamp: false,
+                validate_rights: parse_opts.for_agent != ForAgent::Sudo,
+                validate_previous_commit: false,
+                validate_for_agent: Some(parse_opts.for_agent.to_string()),
+                update_index: true,
+            };
+
+            commit
+                .apply_opts(store, &opts)
+                .map_err(|mut e| {
+                    e.message = format!("Failed to save {}: {}", r.get_subject(), e.message);
+                    e
+                })?
+                .resource_new
+                .unwrap()
+        }
+    };
+    Ok(r)
 }
 
-fn generate_id_from_local_id(importer_subject: &str, local_id: &str) -> String {
+pub(crate) fn generate_id_from_local_id(importer_subject: &str, local_id: &str) -> String {
     format!("{}/{}", importer_subject, local_id)
 }
 
@@ -464,6 +853,38 @@ mod test {
         assert_eq!(found_shortname.to_string(), "class");
     }
 
+    #[test]
+    fn ndjson_export_import_roundtrip() {
+        let store1 = crate::Store::init().unwrap();
+        store1.populate().unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        let exported = store1.export_ndjson(true, &mut out).unwrap();
+        assert_eq!(exported, store1.all_resources(true).count());
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap().lines().count(),
+            exported
+        );
+
+        let store2 = crate::Store::init().unwrap();
+        store2.populate().unwrap();
+        let mut progress = Vec::new();
+        let imported = store2
+            .import_ndjson(out.as_slice(), &ParseOpts::default(), 10, &mut |n| {
+                progress.push(n)
+            })
+            .unwrap();
+        assert_eq!(imported, exported);
+        assert_eq!(progress.last(), Some(&imported));
+        assert_eq!(progress.len(), exported.div_ceil(10));
+        let found_shortname = store2
+            .get_resource(urls::CLASS)
+            .unwrap()
+            .get(urls::SHORTNAME)
+            .unwrap()
+            .to_string();
+        assert_eq!(found_shortname, "class");
+    }
+
     #[test]
     fn parse_nested_resource_map_roundtrip() {
         let store = crate::Store::init().unwrap();
@@ -634,4 +1055,164 @@ mod test {
         parse_opts.overwrite_outside = true;
         store.import(&json, &parse_opts).unwrap();
     }
+
+    #[test]
+    fn import_checks_constraints() {
+        let store = crate::Store::init().unwrap();
+        store.populate().unwrap();
+        let property = crate::schema::Property {
+            class_type: None,
+            data_type: DataType::Integer,
+            shortname: "rating".into(),
+            description: "A rating from 1 to 5".into(),
+            subject: "https://localhost/properties/rating".into(),
+            allows_only: None,
+            constraints: crate::schema::ValueConstraints {
+                min: Some(1),
+                max: Some(5),
+                ..Default::default()
+            },
+            default: None,
+        };
+        store.add_resource(&property.to_resource()).unwrap();
+        let json = |rating: i64| {
+            format!(
+                r#"{{"@id": "https://localhost/review", "{}": {}}}"#,
+                property.subject, rating
+            )
+        };
+        store.import(&json(4), &ParseOpts::default()).unwrap();
+        let err = store.import(&json(6), &ParseOpts::default()).unwrap_err();
+        assert!(err.to_string().contains("maximum is 5"), "{}", err);
+    }
+
+    #[test]
+    fn import_checks_unique_values() {
+        let store = crate::Store::init().unwrap();
+        store.populate().unwrap();
+        let class = crate::schema::Class {
+            requires: vec![],
+            recommends: vec![],
+            extends: vec![],
+            unique: vec![urls::NAME.into()],
+            rules: vec![],
+            shortname: "team".into(),
+            description: "A team with a unique name".into(),
+            subject: "https://localhost/classes/team".into(),
+        };
+        store.add_resource(&class.to_resource()).unwrap();
+        let team = |subject: &str, name: &str| {
+            format!(
+                r#"{{"@id": "https://localhost/{}", "{}": ["{}"], "{}": "{}"}}"#,
+                subject,
+                urls::IS_A,
+                class.subject,
+                urls::NAME,
+                name
+            )
+        };
+        store
+            .import(&team("red", "Red"), &ParseOpts::default())
+            .unwrap();
+        let err = store
+            .import(&team("crimson", "Red"), &ParseOpts::default())
+            .unwrap_err();
+        assert!(err.message.contains("red"), "{}", err);
+        // Duplicates within the same import are found too
+        let batch = format!("[{}, {}]", team("blue", "Blue"), team("navy", "Blue"));
+        store.import(&batch, &ParseOpts::default()).unwrap_err();
+    }
+
+    #[test]
+    fn import_language_tagged_strings() {
+        let store = crate::Store::init().unwrap();
+        store.populate().unwrap();
+        let property = crate::schema::Property {
+            class_type: None,
+            data_type: DataType::LangString,
+            shortname: "title".into(),
+            description: "A translated title".into(),
+            subject: "https://localhost/properties/title".into(),
+            allows_only: None,
+            constraints: Default::default(),
+            default: None,
+        };
+        store.add_resource(&property.to_resource()).unwrap();
+        let json = format!(
+            r#"{{"@id": "https://localhost/greeting", "{}": {{"en": "Hello", "nl": "Hallo"}}}}"#,
+            property.subject
+        );
+        store.import(&json, &ParseOpts::default()).unwrap();
+        let greeting = store.get_resource("https://localhost/greeting").unwrap();
+        match greeting.get(&property.subject).unwrap() {
+            Value::LangString(texts) => assert_eq!(texts.get("nl"), Some("Hallo")),
+            other => panic!("Should be a language-tagged string, got {:?}", other),
+        }
+        let serialized = greeting.to_json_ad().unwrap();
+        assert!(serialized.contains(r#""nl": "Hallo""#), "{}", serialized);
+    }
+
+    #[test]
+    fn import_plain_json() {
+        let (store, importer) = create_store_and_importer();
+        let class = crate::schema::Class {
+            subject: format!("{}/book", importer),
+            shortname: "book".into(),
+            description: "A book".into(),
+            requires: vec![urls::NAME.into()],
+            recommends: vec![urls::COLLECTION_PAGE_SIZE.into(), urls::DESCRIPTION.into()],
+            extends: vec![],
+            unique: vec![],
+            rules: vec![],
+        };
+        store.add_resource(&class.to_resource()).unwrap();
+        let parse_opts = ParseOpts {
+            importer: Some(importer.clone()),
+            ..Default::default()
+        };
+
+        let json = r#"[
+            {"name": "Dune", "page-size": "412", "description": "Spice"},
+            {"name": "Emma", "page-size": 474, "parent": "https://example.com/shelf"}
+        ]"#;
+        let imported = parse_json_with_class(json, &class.subject, &store, &parse_opts).unwrap();
+        assert_eq!(imported.len(), 2);
+        let dune = store.get_resource(imported[0].get_subject()).unwrap();
+        assert_eq!(dune.get(urls::NAME).unwrap().to_string(), "Dune");
+        assert!(matches!(
+            dune.get(urls::COLLECTION_PAGE_SIZE).unwrap(),
+            Value::Integer(412)
+        ));
+        assert_eq!(dune.get(urls::PARENT).unwrap().to_string(), importer);
+        assert_eq!(dune.get_main_class().unwrap(), class.subject);
+        // `parent` is not a Property of the Class, but its shortname is unique
+        assert_eq!(
+            imported[1].get(urls::PARENT).unwrap().to_string(),
+            "https://example.com/shelf"
+        );
+
+        // Plain JSON created by `to_json` can be imported again
+        let mut roundtrip: serde_json::Value =
+            serde_json::from_str(&dune.to_json(&store).unwrap()).unwrap();
+        roundtrip["name"] = "Dune Messiah".into();
+        roundtrip["@id"] = format!("{}/messiah", importer).into();
+        let imported =
+            parse_json_with_class(&roundtrip.to_string(), &class.subject, &store, &parse_opts)
+                .unwrap();
+        assert_eq!(
+            imported[0].get(urls::DESCRIPTION).unwrap().to_string(),
+            "Spice"
+        );
+
+        let err = parse_json_with_class(
+            r#"{"name": "X", "page-size": "many"}"#,
+            &class.subject,
+            &store,
+            &parse_opts,
+        )
+        .unwrap_err();
+        assert!(err.message.contains("page-size"), "{}", err);
+        parse_json_with_class(r#"{"nonsense": 1}"#, &class.subject, &store, &parse_opts)
+            .unwrap_err();
+    }
 }
@@ -45,6 +45,7 @@ pub fn construct_chatroom(
         include_external: false,
         include_nested: true,
         for_agent: for_agent.clone(),
+        ..Default::default()
     };
 
     let mut messages_unfiltered = store.query(&query_children)?.resources;
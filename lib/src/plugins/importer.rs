@@ -1,5 +1,9 @@
 /*!
 Importers allow users to (periodically) import JSON-AD files from a remote source.
+RDF (Turtle, N-Triples and JSON-LD) can be imported by POSTing it with the matching `Content-Type` header.
+Plain JSON, with shortnames as keys, can be imported by adding a `class` query param.
+Add `validate=true` to check it against the JSON Schema of the Class first, see [crate::json_schema].
+A zip file of Markdown files (`Content-Type: application/zip`) is imported as a vault, see [crate::vault].
 */
 
 use crate::{
@@ -9,6 +13,9 @@ use crate::{
     urls, Resource, Storelike,
 };
 
+#[cfg(feature = "vault")]
+const ZIP_MIME: &str = "application/zip";
+
 pub fn import_endpoint() -> Endpoint {
     Endpoint {
         path: "/import".to_string(),
@@ -17,7 +24,7 @@ pub fn import_endpoint() -> Endpoint {
             urls::IMPORTER_PARENT.to_string(),
             urls::IMPORTER_URL.to_string(),
         ].into(),
-        description: "Imports one or more Resources to some parent. POST your JSON-AD and add a `parent` query param to the URL. See https://docs.atomicdata.dev/create-json-ad.html".to_string(),
+        description: "Imports one or more Resources to some parent. POST your JSON-AD and add a `parent` query param to the URL. To import RDF, set the `Content-Type` header to `text/turtle`, `application/n-triples` or `application/ld+json`. To import plain JSON with shortnames as keys, add a `class` query param with the URL of the Class of the imported resources, and `validate=true` to check the JSON against the JSON Schema of that Class first. To import a zip file of Markdown files (e.g. an Obsidian vault), set the `Content-Type` header to `application/zip`. See https://docs.atomicdata.dev/create-json-ad.html".to_string(),
         shortname: "path".to_string(),
         // Not sure if we need this, or if we should derive it from `None` here.
         handle: Some(handle_get),
@@ -37,16 +44,21 @@ pub fn handle_post(context: HandlePostContext) -> AtomicResult<Resource> {
         body,
         for_agent,
         subject,
+        content_type,
     } = context;
     let mut url = None;
     let mut json = None;
     let mut parent_maybe = None;
+    let mut class = None;
+    let mut validate = false;
     let mut overwrite_outside = false;
     for (k, v) in subject.query_pairs() {
         match k.as_ref() {
             "json" | urls::IMPORTER_URL => return Err("JSON must be POSTed in the body".into()),
             "url" | urls::IMPORTER_JSON => url = Some(v.to_string()),
             "parent" | urls::IMPORTER_PARENT => parent_maybe = Some(v.to_string()),
+            "class" => class = Some(v.to_string()),
+            "validate" => validate = v == "true",
             "overwrite-outside" | urls::IMPORTER_OVERWRITE_OUTSIDE => {
                 overwrite_outside = v == "true"
             }
@@ -56,6 +68,26 @@ pub fn handle_post(context: HandlePostContext) -> AtomicResult<Resource> {
 
     let parent = parent_maybe.ok_or("No parent specified for importer")?;
 
+    let parse_opts = crate::parse::ParseOpts {
+        for_agent: for_agent.clone(),
+        importer: Some(parent),
+        overwrite_outside,
+        // We sign the importer Commits with the default agent,
+        // not the one performing the import, because we don't have their private key.
+        signer: Some(store.get_default_agent()?),
+        save: crate::parse::SaveOpts::Commit,
+    };
+
+    #[cfg(feature = "vault")]
+    if content_type == Some(ZIP_MIME) {
+        if for_agent == &ForAgent::Public {
+            return Err("No agent specified for importer".to_string().into());
+        }
+        let files = crate::vault::read_vault_zip(std::io::Cursor::new(body))?;
+        crate::vault::import_vault(files, class.as_deref(), store, &parse_opts)?;
+        return import_endpoint().to_resource(context.store);
+    }
+
     if !body.is_empty() {
         json =
             Some(String::from_utf8(body).map_err(|e| {
@@ -70,21 +102,24 @@ pub fn handle_post(context: HandlePostContext) -> AtomicResult<Resource> {
         );
     }
 
-    let parse_opts = crate::parse::ParseOpts {
-        for_agent: for_agent.clone(),
-        importer: Some(parent),
-        overwrite_outside,
-        // We sign the importer Commits with the default agent,
-        // not the one performing the import, because we don't have their private key.
-        signer: Some(store.get_default_agent()?),
-        save: crate::parse::SaveOpts::Commit,
-    };
-
     if let Some(json_string) = json {
         if for_agent == &ForAgent::Public {
             return Err("No agent specified for importer".to_string().into());
         }
-        store.import(&json_string, &parse_opts)?;
+        match class {
+            Some(class) => {
+                if validate {
+                    crate::json_schema::check_json_with_class(
+                        store,
+                        &class,
+                        crate::json_schema::JsonShape::Json,
+                        &json_string,
+                    )?;
+                }
+                store.import_json(&json_string, &class, &parse_opts)?
+            }
+            None => import_body(store, &json_string, content_type, &parse_opts)?,
+        };
     } else {
         return Err(
             "No JSON specified for importer. Pass a `url` query param, or post a JSON-AD body."
@@ -95,3 +130,18 @@ pub fn handle_post(context: HandlePostContext) -> AtomicResult<Resource> {
 
     import_endpoint().to_resource(context.store)
 }
+
+/// Imports the body as JSON-AD, or as RDF if the `content_type` is an RDF MIME type.
+#[cfg_attr(not(feature = "rdf"), allow(unused_variables))]
+fn import_body(
+    store: &impl Storelike,
+    body: &str,
+    content_type: Option<&str>,
+    parse_opts: &crate::parse::ParseOpts,
+) -> AtomicResult<usize> {
+    #[cfg(feature = "rdf")]
+    if let Some(format) = content_type.and_then(crate::rdf::RdfFormat::from_mime) {
+        return store.import_rdf(body, format, parse_opts);
+    }
+    store.import(body, parse_opts)
+}
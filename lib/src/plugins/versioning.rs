@@ -78,6 +78,8 @@ fn handle_all_versions_request(context: HandleGetContext) -> AtomicResult<Resour
         name: Some(format!("Versions of {}", target)),
         include_nested: false,
         include_external: false,
+        include_subclasses: false,
+        geo: None,
     };
     let mut collection = collection_builder.into_collection(store, for_agent)?;
     let new_members = collection
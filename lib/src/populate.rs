@@ -7,7 +7,7 @@ use crate::{
     datatype::DataType,
     errors::AtomicResult,
     parse::ParseOpts,
-    schema::{Class, Property},
+    schema::{Class, Property, ValueConstraints},
     storelike::Query,
     urls, Storelike, Value,
 };
@@ -28,6 +28,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "A short name of something. It can only contain letters, numbers and dashes `-`. Use dashes to denote spaces between words. Not case sensitive - lowercase only. Useful in programming contexts where the user should be able to type something short to identify a specific thing.".into(),
             subject: urls::SHORTNAME.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: None,
@@ -36,6 +38,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "A textual description of something. When making a description, make sure that the first few words tell the most important part. Give examples. Since the text supports markdown, you're free to use links and more.".into(),
             subject: urls::DESCRIPTION.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: Some(urls::CLASS.into()),
@@ -44,6 +48,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "A list of Classes of which the thing is an instance of. The Classes of a Resource determine which Properties are recommended and required.".into(),
             subject: urls::IS_A.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: Some(urls::DATATYPE_CLASS.into()),
@@ -52,6 +58,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "The Datatype of a property, such as String or Timestamp.".into(),
             subject: urls::DATATYPE_PROP.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: Some(urls::CLASS.into()),
@@ -62,6 +70,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
                .into(),
             subject: urls::CLASSTYPE_PROP.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: Some(urls::PROPERTY.into()),
@@ -70,6 +80,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "The Properties that are not required, but recommended for this Class.".into(),
             subject: urls::RECOMMENDS.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: Some(urls::PROPERTY.into()),
@@ -78,6 +90,48 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "The Properties that are required for this Class.".into(),
             subject: urls::REQUIRES.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
+        },
+        Property {
+            class_type: Some(urls::CLASS.into()),
+            data_type: DataType::ResourceArray,
+            shortname: "extends".into(),
+            description: "The parent Classes of this Class. A Class inherits the required and recommended Properties of the Classes it extends.".into(),
+            subject: urls::EXTENDS.into(),
+            allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
+        },
+        Property {
+            class_type: None,
+            data_type: DataType::Boolean,
+            shortname: "is-locked".into(),
+            description: "If this is true, the Property should probably not be edited, because doing so could lead to serious errors.".into(),
+            subject: urls::IS_LOCKED.into(),
+            allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
+        },
+        Property {
+            class_type: Some(urls::PROPERTY.into()),
+            data_type: DataType::ResourceArray,
+            shortname: "unique".into(),
+            description: "The Properties of which the values have to be unique among the instances of this Class.".into(),
+            subject: urls::UNIQUE.into(),
+            allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
+        },
+        Property {
+            class_type: Some(urls::VALIDATION_RULE.into()),
+            data_type: DataType::ResourceArray,
+            shortname: "rules".into(),
+            description: "The Validation Rules that the instances of this Class have to follow, such as `endDate >= startDate`.".into(),
+            subject: urls::RULES.into(),
+            allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: None,
@@ -86,6 +140,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "The parent of a Resource sets the hierarchical structure of the Resource, and therefore also the rights / grants. It is used for both navigation, structure and authorization. Parents are the inverse of [children](https://atomicdata.dev/properties/children).".into(),
             subject: urls::PARENT.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         },
         Property {
             class_type: None,
@@ -94,6 +150,8 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description: "Restricts this Property to only the values inside this one. This essentially turns the Property into an `enum`.".into(),
             subject: urls::ALLOWS_ONLY.into(),
             allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
         }
     ];
 
@@ -104,13 +162,19 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             shortname: "property".into(),
             description: "A Property is a single field in a Class. It's the thing that a property field in an Atom points to. An example is `birthdate`. An instance of Property requires various Properties, most notably a `datatype` (e.g. `string` or `integer`), a human readable `description` (such as the thing you're reading), and a `shortname`.".into(),
             subject: urls::PROPERTY.into(),
+            extends: vec![],
+            unique: vec![],
+            rules: vec![],
         },
         Class {
             requires: vec![urls::SHORTNAME.into(), urls::DESCRIPTION.into()],
-            recommends: vec![urls::RECOMMENDS.into(), urls::REQUIRES.into()],
+            recommends: vec![urls::RECOMMENDS.into(), urls::REQUIRES.into(), urls::EXTENDS.into(), urls::UNIQUE.into(), urls::RULES.into()],
             shortname: "class".into(),
             description: "A Class describes an abstract concept, such as 'Person' or 'Blogpost'. It describes the data shape of data (which fields are required and recommended) and explains what the concept represents. It is convention to use Uppercase in its URL.Resources use the [is-a](https://atomicdata.dev/properties/isA) attribute to indicate which classes they are instances of. Note that in Atomic Data, a Resource can have several Classes - not just a single one.".into(),
             subject: urls::CLASS.into(),
+            extends: vec![],
+            unique: vec![],
+            rules: vec![],
         },
         Class {
             requires: vec![urls::SHORTNAME.into(), urls::DESCRIPTION.into()],
@@ -119,6 +183,9 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description:
                 "A Datatype describes a possible type of value, such as 'string' or 'integer'.".into(),
             subject: urls::DATATYPE_CLASS.into(),
+            extends: vec![],
+            unique: vec![],
+            rules: vec![],
         },
         Class {
             requires: vec![urls::PUBLIC_KEY.into()],
@@ -127,6 +194,9 @@ pub fn populate_base_models(store: &impl Storelike) -> AtomicResult<()> {
             description:
                 "An Agent is a user that can create or modify data. It has two keys: a private and a public one. The private key should be kept secret. The public key is used to verify signatures (on [Commits](https://atomicdata.dev/classes/Commit)) set by the of the Agent.".into(),
             subject: urls::AGENT.into(),
+            extends: vec![],
+            unique: vec![],
+            rules: vec![],
         }
     ];
 
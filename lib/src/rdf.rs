@@ -0,0 +1,1231 @@
+//! Parsing RDF (Turtle, N-Triples and JSON-LD) into Atomic Data.
+//!
+//! Triples are grouped by their subject into Resources.
+//! Predicates are mapped to existing [Property]s.
+//! For unknown predicates, a new Property is created (at the URL of the predicate), of which the [DataType] is guessed from the objects.
+//! Blank nodes that are referenced once become Nested Resources, other blank nodes get an `@id` below the importer.
+//! RDF Lists (`( a b c )` in Turtle, `@list` in JSON-LD) become Resource Arrays.
+//! OWL and RDFS definitions of Classes and Properties are converted to Atomic Classes and Properties, see [ontology].
+
+mod ontology;
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Map;
+
+use crate::{
+    datatype::{match_datatype, DataType},
+    errors::AtomicResult,
+    lang::LangString,
+    parse::{add_parsed_to_index, generate_id_from_local_id, save_parsed_resource, ParseOpts},
+    resources::PropVals,
+    schema::{Class, Property, ValueConstraints},
+    urls,
+    values::SubResource,
+    Resource, Storelike, Value,
+};
+
+pub const TURTLE_MIME: &str = "text/turtle";
+pub const NTRIPLES_MIME: &str = "application/n-triples";
+pub const JSON_LD_MIME: &str = "application/ld+json";
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDF_FIRST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#first";
+const RDF_REST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#rest";
+const RDF_NIL: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#nil";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+
+/// The RDF serialization formats that can be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RdfFormat {
+    Turtle,
+    NTriples,
+    JsonLd,
+}
+
+impl RdfFormat {
+    /// Finds the format for a MIME type, such as the value of a `Content-Type` header.
+    pub fn from_mime(mime: &str) -> Option<RdfFormat> {
+        match mime.split(';').next().unwrap_or_default().trim() {
+            TURTLE_MIME => Some(RdfFormat::Turtle),
+            NTRIPLES_MIME => Some(RdfFormat::NTriples),
+            JSON_LD_MIME => Some(RdfFormat::JsonLd),
+            _ => None,
+        }
+    }
+
+    /// Finds the format for a file extension, without the dot.
+    pub fn from_extension(extension: &str) -> Option<RdfFormat> {
+        match extension {
+            "ttl" => Some(RdfFormat::Turtle),
+            "nt" => Some(RdfFormat::NTriples),
+            "jsonld" => Some(RdfFormat::JsonLd),
+            _ => None,
+        }
+    }
+}
+
+/// Subject of a Triple, or an object that refers to a Resource.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    Iri(String),
+    Blank(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    Node(Node),
+    Literal {
+        value: String,
+        datatype: Option<String>,
+        /// Language tag of a language-tagged string
+        language: Option<String>,
+    },
+    List(Vec<Object>),
+}
+
+#[derive(Debug)]
+struct Triple {
+    subject: Node,
+    predicate: String,
+    object: Object,
+}
+
+/// Parses an RDF string and saves the Resources according to the `parse_opts`.
+/// Relative IRIs are resolved using the `importer` as base.
+/// Returns the parsed Resources, including the newly created Properties.
+#[tracing::instrument(skip(string, store))]
+pub fn parse_rdf(
+    string: &str,
+    format: RdfFormat,
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<Vec<Resource>> {
+    let base = parse_opts.importer.as_ref().map(|i| format!("{}/", i));
+    let triples = match format {
+        RdfFormat::Turtle => {
+            let base_iri = base
+                .map(|b| oxiri::Iri::parse(b).map_err(|e| format!("Invalid base IRI: {}", e)))
+                .transpose()?;
+            let mut parser = rio_turtle::TurtleParser::new(string.as_bytes(), base_iri);
+            rio_to_triples(&mut parser)?
+        }
+        RdfFormat::NTriples => {
+            let mut parser = rio_turtle::NTriplesParser::new(string.as_bytes());
+            rio_to_triples(&mut parser)?
+        }
+        RdfFormat::JsonLd => json_ld_to_triples(string, base.as_deref())?,
+    };
+    let mut graph = Graph::new(triples, store, parse_opts);
+    let resources = graph.save()?;
+    add_parsed_to_index(&resources, store, parse_opts)?;
+    Ok(resources)
+}
+
+fn rio_to_triples<P>(parser: &mut P) -> AtomicResult<Vec<Triple>>
+where
+    P: rio_api::parser::TriplesParser,
+    crate::AtomicError: From<P::Error>,
+{
+    use rio_api::model::{Literal, Subject, Term};
+
+    let mut triples = Vec::new();
+    parser.parse_all(&mut |t| -> AtomicResult<()> {
+        let subject = match t.subject {
+            Subject::NamedNode(n) => Node::Iri(n.iri.into()),
+            Subject::BlankNode(b) => Node::Blank(b.id.into()),
+            Subject::Triple(_) => return Err("RDF-star triples are not supported".into()),
+        };
+        let object = match t.object {
+            Term::NamedNode(n) => Object::Node(Node::Iri(n.iri.into())),
+            Term::BlankNode(b) => Object::Node(Node::Blank(b.id.into())),
+            Term::Literal(Literal::Simple { value }) => Object::Literal {
+                value: value.into(),
+                datatype: None,
+                language: None,
+            },
+            Term::Literal(Literal::LanguageTaggedString { value, language }) => Object::Literal {
+                value: value.into(),
+                datatype: None,
+                language: Some(language.into()),
+            },
+            Term::Literal(Literal::Typed { value, datatype }) => Object::Literal {
+                value: value.into(),
+                datatype: Some(datatype.iri.into()),
+                language: None,
+            },
+            Term::Triple(_) => return Err("RDF-star triples are not supported".into()),
+        };
+        triples.push(Triple {
+            subject,
+            predicate: t.predicate.iri.into(),
+            object,
+        });
+        Ok(())
+    })?;
+    Ok(triples)
+}
+
+/// Triples grouped by subject, which are converted to Resources.
+struct Graph<'a, S: Storelike> {
+    /// Subjects in the order in which they were found
+    subjects: Vec<Node>,
+    statements: HashMap<Node, Vec<(String, Object)>>,
+    /// Blank nodes that are converted to Nested Resources
+    nested: HashSet<Node>,
+    /// Resolved or created Properties
+    properties: HashMap<String, Property>,
+    /// Classes defined in the graph, which are not yet in the store
+    classes: HashSet<String>,
+    /// Newly created Properties and Classes, which have to be returned too
+    created: Vec<Resource>,
+    store: &'a S,
+    parse_opts: &'a ParseOpts,
+}
+
+impl<'a, S: Storelike> Graph<'a, S> {
+    fn new(triples: Vec<Triple>, store: &'a S, parse_opts: &'a ParseOpts) -> Self {
+        let mut subjects = Vec::new();
+        let mut statements: HashMap<Node, Vec<(String, Object)>> = HashMap::new();
+        for t in triples {
+            let pvs = statements.entry(t.subject.clone()).or_insert_with(|| {
+                subjects.push(t.subject);
+                Vec::new()
+            });
+            pvs.push((t.predicate, t.object));
+        }
+        let mut graph = Graph {
+            subjects,
+            statements,
+            nested: HashSet::new(),
+            properties: HashMap::new(),
+            classes: HashSet::new(),
+            created: Vec::new(),
+            store,
+            parse_opts,
+        };
+        graph.collapse_lists();
+        graph.find_nested();
+        graph
+    }
+
+    /// Replaces `rdf:first` / `rdf:rest` chains of blank nodes with [Object::List].
+    fn collapse_lists(&mut self) {
+        let list_nodes: HashSet<Node> = self
+            .statements
+            .iter()
+            .filter(|(node, pvs)| {
+                matches!(node, Node::Blank(_))
+                    && pvs.len() == 2
+                    && pvs.iter().any(|(p, _)| p == RDF_FIRST)
+                    && pvs.iter().any(|(p, _)| p == RDF_REST)
+            })
+            .map(|(node, _)| node.clone())
+            .collect();
+
+        let mut lists: HashMap<Node, Vec<Object>> = HashMap::new();
+        for head in list_nodes.iter() {
+            let mut items = Vec::new();
+            let mut current = head.clone();
+            let mut visited = HashSet::new();
+            loop {
+                if !visited.insert(current.clone()) {
+                    break;
+                }
+                let pvs = &self.statements[&current];
+                if let Some((_, first)) = pvs.iter().find(|(p, _)| p == RDF_FIRST) {
+                    items.push(first.clone());
+                }
+                match pvs.iter().find(|(p, _)| p == RDF_REST) {
+                    Some((_, Object::Node(next))) if list_nodes.contains(next) => {
+                        current = next.clone()
+                    }
+                    _ => break,
+                }
+            }
+            lists.insert(head.clone(), items);
+        }
+
+        let nil = Object::Node(Node::Iri(RDF_NIL.into()));
+        for pvs in self.statements.values_mut() {
+            for (_, object) in pvs.iter_mut() {
+                if let Object::Node(node) = object {
+                    if let Some(items) = lists.get(node) {
+                        *object = Object::List(items.clone());
+                    }
+                } else if *object == nil {
+                    *object = Object::List(Vec::new());
+                }
+            }
+        }
+        self.statements.retain(|node, _| !list_nodes.contains(node));
+        self.subjects.retain(|node| !list_nodes.contains(node));
+    }
+
+    /// Blank nodes that are referenced exactly once are converted to Nested Resources.
+    fn find_nested(&mut self) {
+        fn count(object: &Object, counts: &mut HashMap<Node, usize>) {
+            match object {
+                Object::Node(node @ Node::Blank(_)) => {
+                    *counts.entry(node.clone()).or_default() += 1
+                }
+                Object::List(items) => items.iter().for_each(|item| count(item, counts)),
+                _ => {}
+            }
+        }
+        let mut counts = HashMap::new();
+        for pvs in self.statements.values() {
+            for (_, object) in pvs {
+                count(object, &mut counts);
+            }
+        }
+        self.nested = counts
+            .into_iter()
+            .filter(|(node, n)| *n == 1 && self.statements.contains_key(node))
+            .map(|(node, _)| node)
+            .collect();
+    }
+
+    /// Creates all the Resources and saves them.
+    fn save(&mut self) -> AtomicResult<Vec<Resource>> {
+        self.convert_ontology()?;
+        let mut converted = Vec::new();
+        for node in self.subjects.clone() {
+            if self.nested.contains(&node) {
+                continue;
+            }
+            let subject = self.node_subject(&node)?;
+            let mut propvals = self.node_to_propvals(&node, &mut Vec::new())?;
+            if let Some(importer) = &self.parse_opts.importer {
+                if !propvals.contains_key(urls::PARENT) {
+                    propvals.insert(urls::PARENT.into(), Value::AtomicUrl(importer.into()));
+                }
+            }
+            converted.push((subject, propvals));
+        }
+        let mut resources = std::mem::take(&mut self.created);
+        for (subject, propvals) in converted {
+            resources.push(save_parsed_resource(
+                subject,
+                propvals,
+                self.store,
+                self.parse_opts,
+            )?);
+        }
+        Ok(resources)
+    }
+
+    /// Returns the `@id` of a Resource. Blank nodes are placed below the importer.
+    fn node_subject(&self, node: &Node) -> AtomicResult<String> {
+        match node {
+            Node::Iri(iri) => Ok(iri.clone()),
+            Node::Blank(id) => {
+                let importer = self.parse_opts.importer.as_ref().ok_or(format!(
+                    "Blank node _:{} needs an importer (parent) to create an @id",
+                    id
+                ))?;
+                Ok(generate_id_from_local_id(importer, id))
+            }
+        }
+    }
+
+    fn node_to_propvals(&mut self, node: &Node, stack: &mut Vec<Node>) -> AtomicResult<PropVals> {
+        if stack.contains(node) {
+            return Err(format!("Blank nodes can not refer to themselves: {:?}", node).into());
+        }
+        stack.push(node.clone());
+        let statements = self.statements.get(node).cloned().unwrap_or_default();
+
+        // Group the objects by predicate, while maintaining their order
+        let mut predicates: Vec<String> = Vec::new();
+        let mut objects: HashMap<String, Vec<Object>> = HashMap::new();
+        for (predicate, object) in statements {
+            objects
+                .entry(predicate.clone())
+                .or_insert_with(|| {
+                    predicates.push(predicate);
+                    Vec::new()
+                })
+                .push(object);
+        }
+
+        let mut propvals = PropVals::new();
+        for predicate in predicates {
+            let objects = &objects[&predicate];
+            if predicate == RDF_TYPE && self.all_classes(objects) {
+                let classes = objects
+                    .iter()
+                    .filter_map(|o| match o {
+                        Object::Node(Node::Iri(iri)) => Some(iri.as_str().into()),
+                        _ => None,
+                    })
+                    .collect();
+                propvals.insert(urls::IS_A.into(), Value::ResourceArray(classes));
+                continue;
+            }
+            let property = self.get_property(&predicate)?;
+            let value = self
+                .objects_to_value(objects, &property, stack)
+                .map_err(|e| format!("Unable to convert {} of {:?}: {}", predicate, node, e))?;
+            propvals.insert(predicate, value);
+        }
+        stack.pop();
+        Ok(propvals)
+    }
+
+    /// Whether all objects are Classes, in which case `rdf:type` is converted to `isA`.
+    /// Only Classes in the graph or in the store count, so importing doesn't fetch every type.
+    fn all_classes(&self, objects: &[Object]) -> bool {
+        objects.iter().all(|o| match o {
+            Object::Node(Node::Iri(iri)) => {
+                self.classes.contains(iri)
+                    || self
+                        .store
+                        .get_resource_local(iri)
+                        .and_then(Class::from_resource)
+                        .is_ok()
+            }
+            _ => false,
+        })
+    }
+
+    fn objects_to_value(
+        &mut self,
+        objects: &[Object],
+        property: &Property,
+        stack: &mut Vec<Node>,
+    ) -> AtomicResult<Value> {
+        if property.data_type == DataType::ResourceArray {
+            if let [Object::Literal { value, .. }] = objects {
+                return Value::new(value, &DataType::ResourceArray);
+            }
+            let mut items = Vec::new();
+            for object in flatten(objects) {
+                match object {
+                    Object::Node(node) => items.push(self.node_to_sub_resource(node, stack)?),
+                    _ => return Err("Resource Arrays can only contain resources".into()),
+                }
+            }
+            return Ok(Value::ResourceArray(items));
+        }
+
+        // Every language is a separate object
+        if property.data_type == DataType::LangString {
+            let mut texts = Vec::new();
+            for object in objects {
+                match object {
+                    Object::Literal {
+                        value,
+                        language: Some(language),
+                        ..
+                    } => texts.push((language.clone(), value.clone())),
+                    _ => return Err("Language-tagged strings need a language tag".into()),
+                }
+            }
+            return Ok(Value::LangString(LangString::new(texts)?));
+        }
+
+        if objects.len() > 1 {
+            tracing::warn!(
+                "Property {} accepts one value, but found {}. Using the first one.",
+                property.subject,
+                objects.len()
+            );
+        }
+        match &objects[0] {
+            Object::Node(node) => match self.node_to_sub_resource(node, stack)? {
+                SubResource::Nested(propvals) if property.data_type == DataType::AtomicUrl => {
+                    Ok(Value::NestedResource(SubResource::Nested(propvals)))
+                }
+                SubResource::Subject(subject) => Value::new(&subject, &property.data_type),
+                _ => {
+                    Err(format!("Blank node can not be converted to {}", property.data_type).into())
+                }
+            },
+            Object::Literal { value, .. } => Value::new(value, &property.data_type),
+            Object::List(_) => {
+                Err(format!("A list can not be converted to {}", property.data_type).into())
+            }
+        }
+    }
+
+    fn node_to_sub_resource(
+        &mut self,
+        node: &Node,
+        stack: &mut Vec<Node>,
+    ) -> AtomicResult<SubResource> {
+        if self.nested.contains(node) {
+            Ok(SubResource::Nested(self.node_to_propvals(node, stack)?))
+        } else {
+            Ok(SubResource::Subject(self.node_subject(node)?))
+        }
+    }
+
+    /// Finds a Property in the store, or creates one with a guessed DataType.
+    /// Properties are not fetched, as that would mean a request for every unknown predicate.
+    fn get_property(&mut self, predicate: &str) -> AtomicResult<Property> {
+        if let Some(property) = self.properties.get(predicate) {
+            return Ok(property.clone());
+        }
+        let local = self
+            .store
+            .get_resource_local(predicate)
+            .and_then(Property::from_resource);
+        let property = match local {
+            Ok(property) => property,
+            Err(_) => self.create_property(predicate)?,
+        };
+        self.properties
+            .insert(predicate.to_string(), property.clone());
+        Ok(property)
+    }
+
+    fn create_property(&mut self, predicate: &str) -> AtomicResult<Property> {
+        let property = Property {
+            class_type: None,
+            data_type: self.guess_datatype(predicate),
+            shortname: shortname_from_iri(predicate),
+            description: format!("Imported from the RDF predicate {}", predicate),
+            subject: predicate.into(),
+            allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
+        };
+        self.save_created(predicate, property.to_resource().into_propvals())?;
+        Ok(property)
+    }
+
+    /// Saves a newly created Property or Class below the importer.
+    fn save_created(&mut self, subject: &str, mut propvals: PropVals) -> AtomicResult<()> {
+        if let Some(importer) = &self.parse_opts.importer {
+            propvals.insert(urls::PARENT.into(), Value::AtomicUrl(importer.into()));
+        }
+        let resource = save_parsed_resource(subject.into(), propvals, self.store, self.parse_opts)?;
+        self.created.push(resource);
+        Ok(())
+    }
+
+    /// Guesses the DataType from all objects of the predicate.
+    /// Uses a ResourceArray if a subject has multiple resources for the predicate.
+    fn guess_datatype(&self, predicate: &str) -> DataType {
+        let mut multiple = predicate == RDF_TYPE;
+        let mut objects = Vec::new();
+        for pvs in self.statements.values() {
+            let found: Vec<&Object> = pvs
+                .iter()
+                .filter(|(p, _)| p == predicate)
+                .map(|(_, o)| o)
+                .collect();
+            multiple |= found.len() > 1;
+            objects.extend(found);
+        }
+        if objects.iter().any(|o| matches!(o, Object::List(_))) {
+            return DataType::ResourceArray;
+        }
+        if objects.iter().all(|o| matches!(o, Object::Node(_))) {
+            return if multiple {
+                DataType::ResourceArray
+            } else {
+                DataType::AtomicUrl
+            };
+        }
+        if objects.iter().all(|o| {
+            matches!(
+                o,
+                Object::Literal {
+                    language: Some(_),
+                    ..
+                }
+            )
+        }) {
+            return DataType::LangString;
+        }
+        let mut datatypes = objects.iter().map(|o| match o {
+            Object::Literal {
+                datatype: Some(dt),
+                value,
+                ..
+            } => match literal_datatype(dt) {
+                // XSD allows some values that Atomic Data does not, such as date-times without a time zone
+                dt if Value::new(value, &dt).is_ok() => dt,
+                _ => DataType::String,
+            },
+            _ => DataType::String,
+        });
+        let first = datatypes.next().unwrap_or(DataType::String);
+        datatypes.fold(first, |acc, dt| match (acc, dt) {
+            (a, b) if a == b => a,
+            (DataType::Integer, DataType::Float) | (DataType::Float, DataType::Integer) => {
+                DataType::Float
+            }
+            (DataType::Integer, DataType::Decimal) | (DataType::Decimal, DataType::Integer) => {
+                DataType::Decimal
+            }
+            _ => DataType::String,
+        })
+    }
+}
+
+fn flatten(objects: &[Object]) -> Vec<&Object> {
+    objects
+        .iter()
+        .flat_map(|o| match o {
+            Object::List(items) => flatten(items),
+            other => vec![other],
+        })
+        .collect()
+}
+
+/// Maps an XSD or Atomic datatype of a literal to a [DataType].
+fn literal_datatype(datatype: &str) -> DataType {
+    if let Some(xsd) = datatype.strip_prefix(XSD) {
+        return match xsd {
+            "integer" | "int" | "long" | "short" | "byte" | "nonNegativeInteger"
+            | "positiveInteger" | "negativeInteger" | "nonPositiveInteger" | "unsignedInt"
+            | "unsignedLong" | "unsignedShort" | "unsignedByte" => DataType::Integer,
+            "decimal" => DataType::Decimal,
+            "double" | "float" => DataType::Float,
+            "boolean" => DataType::Boolean,
+            "date" => DataType::Date,
+            "dateTime" | "dateTimeStamp" => DataType::DateTime,
+            "time" => DataType::Time,
+            "duration" => DataType::Duration,
+            _ => DataType::String,
+        };
+    }
+    match match_datatype(datatype) {
+        DataType::Unsupported(_) => DataType::String,
+        dt => dt,
+    }
+}
+
+/// Creates a valid shortname (slug) from the last part of an IRI, e.g. `http://schema.org/givenName` becomes `given-name`.
+fn shortname_from_iri(iri: &str) -> String {
+    let local = iri
+        .trim_end_matches(['/', '#'])
+        .rsplit(['/', '#', ':'])
+        .next()
+        .unwrap_or_default();
+    let mut slug = String::new();
+    let mut previous_lower = false;
+    for c in local.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && previous_lower {
+                slug.push('-');
+            }
+            previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            slug.push(c.to_ascii_lowercase());
+        } else {
+            if !slug.ends_with('-') {
+                slug.push('-');
+            }
+            previous_lower = false;
+        }
+    }
+    let slug = slug.trim_matches('-').to_string();
+    if slug.is_empty() {
+        "property".into()
+    } else {
+        slug
+    }
+}
+
+/// A term definition in a JSON-LD `@context`.
+#[derive(Debug, Clone, Default)]
+struct TermDefinition {
+    id: String,
+    /// `@id` or a datatype IRI
+    kind: Option<String>,
+    list: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct JsonLdContext {
+    terms: HashMap<String, TermDefinition>,
+    vocab: Option<String>,
+    base: Option<String>,
+}
+
+impl JsonLdContext {
+    /// Returns a new context that combines this one with a local `@context` value.
+    fn extend(&self, value: Option<&serde_json::Value>) -> AtomicResult<JsonLdContext> {
+        let mut ctx = self.clone();
+        let contexts = match value {
+            None => return Ok(ctx),
+            Some(serde_json::Value::Array(arr)) => arr.iter().collect(),
+            Some(other) => vec![other],
+        };
+        for context in contexts {
+            let map = match context {
+                serde_json::Value::Object(map) => map,
+                serde_json::Value::Null => {
+                    ctx.terms.clear();
+                    ctx.vocab = None;
+                    continue;
+                }
+                _ => return Err("Remote JSON-LD contexts are not supported, use an object".into()),
+            };
+            if let Some(serde_json::Value::String(vocab)) = map.get("@vocab") {
+                ctx.vocab = Some(vocab.clone());
+            }
+            for (term, def) in map {
+                if term.starts_with('@') {
+                    continue;
+                }
+                let definition = match def {
+                    serde_json::Value::String(id) => TermDefinition {
+                        id: id.clone(),
+                        ..Default::default()
+                    },
+                    serde_json::Value::Object(obj) => TermDefinition {
+                        id: obj
+                            .get("@id")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or(term)
+                            .to_string(),
+                        kind: obj.get("@type").and_then(|v| v.as_str()).map(String::from),
+                        list: obj.get("@container").and_then(|v| v.as_str()) == Some("@list"),
+                    },
+                    _ => continue,
+                };
+                ctx.terms.insert(term.clone(), definition);
+            }
+            // Term definitions can use prefixes defined in the same context
+            let expanded: Vec<(String, String, Option<String>)> = ctx
+                .terms
+                .iter()
+                .map(|(term, def)| {
+                    (
+                        term.clone(),
+                        ctx.expand_prefix(&def.id),
+                        def.kind.as_ref().map(|k| {
+                            if k == "@id" {
+                                k.clone()
+                            } else {
+                                ctx.expand_prefix(k)
+                            }
+                        }),
+                    )
+                })
+                .collect();
+            for (term, id, kind) in expanded {
+                let def = ctx.terms.get_mut(&term).unwrap();
+                def.id = id;
+                def.kind = kind;
+            }
+        }
+        Ok(ctx)
+    }
+
+    /// Expands compact IRIs such as `schema:name`.
+    fn expand_prefix(&self, value: &str) -> String {
+        if let Some((prefix, suffix)) = value.split_once(':') {
+            if !suffix.starts_with("//") {
+                if let Some(def) = self.terms.get(prefix) {
+                    return format!("{}{}", def.id, suffix);
+                }
+            }
+        }
+        value.to_string()
+    }
+
+    /// Expands a key or `@type` value to an IRI. Returns None for terms that are not mapped.
+    fn expand_term(&self, term: &str) -> Option<String> {
+        if let Some(def) = self.terms.get(term) {
+            return Some(def.id.clone());
+        }
+        let expanded = self.expand_prefix(term);
+        if expanded.contains(':') {
+            return Some(expanded);
+        }
+        self.vocab
+            .as_ref()
+            .map(|vocab| format!("{}{}", vocab, term))
+    }
+
+    /// Expands an `@id` value, resolving relative IRIs against the base.
+    fn expand_id(&self, id: &str) -> Node {
+        if let Some(blank) = id.strip_prefix("_:") {
+            return Node::Blank(blank.into());
+        }
+        let expanded = self.expand_prefix(id);
+        if let Some(base) = &self.base {
+            if let Ok(url) = url::Url::parse(base).and_then(|b| b.join(&expanded)) {
+                return Node::Iri(url.to_string());
+            }
+        }
+        Node::Iri(expanded)
+    }
+}
+
+/// Converts JSON-LD to triples.
+/// Supports inline `@context` objects (with prefixes, `@vocab`, typed and `@list` terms), `@graph`, `@value`, `@list` and nested nodes.
+fn json_ld_to_triples(string: &str, base: Option<&str>) -> AtomicResult<Vec<Triple>> {
+    let json: serde_json::Value = serde_json::from_str(string)?;
+    let ctx = JsonLdContext {
+        base: base.map(String::from),
+        ..Default::default()
+    };
+    let mut parser = JsonLdParser {
+        triples: Vec::new(),
+        blank_count: 0,
+    };
+    match json {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                match item {
+                    serde_json::Value::Object(map) => {
+                        parser.node(&map, &ctx)?;
+                    }
+                    _ => return Err("JSON-LD arrays must contain objects".into()),
+                }
+            }
+        }
+        serde_json::Value::Object(map) => {
+            parser.node(&map, &ctx)?;
+        }
+        _ => return Err("Root JSON-LD element must be an object or array".into()),
+    }
+    Ok(parser.triples)
+}
+
+struct JsonLdParser {
+    triples: Vec<Triple>,
+    blank_count: usize,
+}
+
+impl JsonLdParser {
+    fn new_blank(&mut self) -> Node {
+        self.blank_count += 1;
+        Node::Blank(format!("b{}", self.blank_count))
+    }
+
+    /// Adds the triples of a node object, returns its subject.
+    /// Returns None for objects that only contain a `@graph`.
+    fn node(
+        &mut self,
+        map: &Map<String, serde_json::Value>,
+        parent_ctx: &JsonLdContext,
+    ) -> AtomicResult<Option<Node>> {
+        let ctx = parent_ctx.extend(map.get("@context"))?;
+        if let Some(graph) = map.get("@graph") {
+            let items = match graph {
+                serde_json::Value::Array(items) => items.iter().collect(),
+                other => vec![other],
+            };
+            for item in items {
+                if let serde_json::Value::Object(obj) = item {
+                    self.node(obj, &ctx)?;
+                }
+            }
+            if !map.keys().any(|k| k != "@graph" && k != "@context") {
+                return Ok(None);
+            }
+        }
+
+        let subject = match map.get("@id") {
+            Some(serde_json::Value::String(id)) => ctx.expand_id(id),
+            Some(_) => return Err("@id must be a string".into()),
+            None => self.new_blank(),
+        };
+
+        for (key, value) in map {
+            match key.as_str() {
+                "@context" | "@id" | "@graph" => continue,
+                "@type" => {
+                    let types = match value {
+                        serde_json::Value::Array(arr) => arr.iter().collect(),
+                        other => vec![other],
+                    };
+                    for t in types {
+                        let iri = t
+                            .as_str()
+                            .and_then(|t| ctx.expand_term(t))
+                            .ok_or(format!("Invalid @type: {}", t))?;
+                        self.triples.push(Triple {
+                            subject: subject.clone(),
+                            predicate: RDF_TYPE.into(),
+                            object: Object::Node(Node::Iri(iri)),
+                        });
+                    }
+                }
+                _ => {
+                    // Terms that can not be expanded are ignored, as specified by JSON-LD
+                    let Some(predicate) = ctx.expand_term(key) else {
+                        continue;
+                    };
+                    let def = ctx.terms.get(key).cloned().unwrap_or_default();
+                    let items = match value {
+                        serde_json::Value::Array(arr) => arr.iter().collect(),
+                        other => vec![other],
+                    };
+                    let mut objects = Vec::new();
+                    for item in items {
+                        if let Some(object) = self.value(item, &def, &ctx)? {
+                            objects.push(object);
+                        }
+                    }
+                    if def.list {
+                        objects = vec![Object::List(objects)];
+                    }
+                    for object in objects {
+                        self.triples.push(Triple {
+                            subject: subject.clone(),
+                            predicate: predicate.clone(),
+                            object,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(Some(subject))
+    }
+
+    fn value(
+        &mut self,
+        value: &serde_json::Value,
+        def: &TermDefinition,
+        ctx: &JsonLdContext,
+    ) -> AtomicResult<Option<Object>> {
+        let typed = |value: String, default: Option<&str>| Object::Literal {
+            value,
+            datatype: def.kind.clone().or(default.map(String::from)),
+            language: None,
+        };
+        Ok(Some(match value {
+            serde_json::Value::Null => return Ok(None),
+            serde_json::Value::String(s) if def.kind.as_deref() == Some("@id") => {
+                Object::Node(ctx.expand_id(s))
+            }
+            serde_json::Value::String(s) => typed(s.clone(), None),
+            serde_json::Value::Bool(b) => typed(b.to_string(), Some(XSD_BOOLEAN)),
+            serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => {
+                typed(n.to_string(), Some(XSD_INTEGER))
+            }
+            serde_json::Value::Number(n) => typed(n.to_string(), Some(XSD_DOUBLE)),
+            serde_json::Value::Array(items) => {
+                let mut list = Vec::new();
+                for item in items {
+                    if let Some(object) = self.value(item, def, ctx)? {
+                        list.push(object);
+                    }
+                }
+                Object::List(list)
+            }
+            serde_json::Value::Object(obj) => {
+                if let Some(v) = obj.get("@value") {
+                    let value = match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    let datatype = obj
+                        .get("@type")
+                        .and_then(|t| t.as_str())
+                        .map(|t| ctx.expand_prefix(t));
+                    let language = obj
+                        .get("@language")
+                        .and_then(|l| l.as_str())
+                        .map(String::from);
+                    Object::Literal {
+                        value,
+                        datatype,
+                        language,
+                    }
+                } else if let Some(items) = obj.get("@list") {
+                    let list_def = TermDefinition {
+                        list: false,
+                        ..def.clone()
+                    };
+                    match self.value(items, &list_def, ctx)? {
+                        Some(Object::List(list)) => Object::List(list),
+                        Some(single) => Object::List(vec![single]),
+                        None => Object::List(Vec::new()),
+                    }
+                } else {
+                    match self.node(obj, ctx)? {
+                        Some(node) => Object::Node(node),
+                        None => return Ok(None),
+                    }
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse::SaveOpts, Store};
+
+    // The importer only looks up Properties and Classes in the store, and creates the unknown ones, see `Graph::get_property`.
+    // That's why these tests stay offline, even though they use external IRIs.
+
+    fn init_store() -> Store {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        store
+    }
+
+    fn opts() -> ParseOpts {
+        ParseOpts {
+            importer: Some("https://localhost/import".into()),
+            save: SaveOpts::Save,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shortnames() {
+        assert_eq!(
+            shortname_from_iri("http://schema.org/givenName"),
+            "given-name"
+        );
+        assert_eq!(shortname_from_iri("http://xmlns.com/foaf/0.1/"), "0-1");
+        assert_eq!(
+            shortname_from_iri("http://www.w3.org/2000/01/rdf-schema#label"),
+            "label"
+        );
+        assert_eq!(shortname_from_iri("urn:x:Some_Thing"), "some-thing");
+    }
+
+    #[test]
+    fn import_turtle() {
+        let store = init_store();
+        let turtle = r#"
+            @prefix ex: <local:store/ex/> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+            <#alice> <https://atomicdata.dev/properties/name> "Alice" ;
+                ex:age "42"^^xsd:integer ;
+                ex:knows <#bob>, <#carol> ;
+                ex:favourites ( <#bob> <#carol> ) ;
+                ex:address [ ex:city "Amsterdam" ] .
+            _:top ex:city "Utrecht" .
+        "#;
+        let resources = parse_rdf(turtle, RdfFormat::Turtle, &store, &opts()).unwrap();
+
+        let alice = store
+            .get_resource("https://localhost/import/#alice")
+            .unwrap();
+        assert_eq!(
+            alice.get(urls::NAME).unwrap().to_string(),
+            "Alice",
+            "Existing property should be used"
+        );
+        assert!(matches!(
+            alice.get("local:store/ex/age").unwrap(),
+            Value::Integer(42)
+        ));
+        let knows = store.get_property("local:store/ex/knows").unwrap();
+        assert_eq!(knows.data_type, DataType::ResourceArray);
+        assert_eq!(knows.shortname, "knows");
+        assert_eq!(
+            alice
+                .get("local:store/ex/favourites")
+                .unwrap()
+                .to_subjects(None)
+                .unwrap(),
+            vec![
+                "https://localhost/import/#bob".to_string(),
+                "https://localhost/import/#carol".to_string()
+            ]
+        );
+        match alice.get("local:store/ex/address").unwrap() {
+            Value::NestedResource(SubResource::Nested(pv)) => {
+                assert_eq!(
+                    pv.get("local:store/ex/city").unwrap().to_string(),
+                    "Amsterdam"
+                )
+            }
+            other => panic!("Should be nested, got {:?}", other),
+        }
+        assert_eq!(
+            alice.get(urls::PARENT).unwrap().to_string(),
+            "https://localhost/import"
+        );
+        // The blank node that is not referenced gets an @id
+        store.get_resource("https://localhost/import/top").unwrap();
+        // 5 new properties, alice and the top level blank node
+        assert_eq!(resources.len(), 7);
+    }
+
+    #[test]
+    fn import_ntriples_roundtrip() {
+        let store = init_store();
+        // Resource Arrays are not yet serialized as RDF lists
+        let atoms = store
+            .get_resource(urls::CLASS)
+            .unwrap()
+            .to_atoms()
+            .into_iter()
+            .filter(|a| !matches!(a.value, Value::ResourceArray(_)))
+            .collect();
+        let ntriples = crate::serialize::atoms_to_ntriples(atoms, &store).unwrap();
+
+        let store2 = init_store();
+        parse_rdf(&ntriples, RdfFormat::NTriples, &store2, &opts()).unwrap();
+        let class = store2.get_resource(urls::CLASS).unwrap();
+        assert_eq!(class.get(urls::SHORTNAME).unwrap().to_string(), "class");
+    }
+
+    #[test]
+    fn import_language_tagged_strings() {
+        let store = init_store();
+        let turtle = r#"
+            <#greeting> <local:store/ex/title> "Hello"@en, "Hallo"@nl-BE .
+        "#;
+        parse_rdf(turtle, RdfFormat::Turtle, &store, &opts()).unwrap();
+        let title = store.get_property("local:store/ex/title").unwrap();
+        assert_eq!(title.data_type, DataType::LangString);
+        let greeting = store
+            .get_resource("https://localhost/import/#greeting")
+            .unwrap();
+        match greeting.get(&title.subject).unwrap() {
+            Value::LangString(texts) => assert_eq!(texts.get("nl-be"), Some("Hallo")),
+            other => panic!("Should be a language-tagged string, got {:?}", other),
+        }
+
+        let turtle = crate::serialize::atoms_to_turtle(greeting.to_atoms(), &store).unwrap();
+        assert!(
+            turtle.contains(r#""Hello"@en, "Hallo"@nl-be"#),
+            "{}",
+            turtle
+        );
+        let ntriples = crate::serialize::atoms_to_ntriples(greeting.to_atoms(), &store).unwrap();
+        assert!(ntriples.contains(r#""Hallo"@nl-be ."#), "{}", ntriples);
+    }
+
+    #[test]
+    fn import_json_ld() {
+        let store = init_store();
+        let json_ld = r#"{
+            "@context": {
+                "schema": "local:store/schema/",
+                "name": "https://atomicdata.dev/properties/name",
+                "knows": { "@id": "schema:knows", "@type": "@id", "@container": "@list" },
+                "height": { "@id": "schema:height", "@type": "http://www.w3.org/2001/XMLSchema#double" }
+            },
+            "@graph": [
+                {
+                    "@id": "local:store/ex/alice",
+                    "name": "Alice",
+                    "knows": ["local:store/ex/bob"],
+                    "height": 1.8,
+                    "schema:address": { "schema:city": "Amsterdam" },
+                    "unmapped": "ignored"
+                },
+                {
+                    "@id": "local:store/ex/bob",
+                    "@type": "https://atomicdata.dev/classes/Class",
+                    "name": "Bob",
+                    "https://atomicdata.dev/properties/shortname": "bob",
+                    "https://atomicdata.dev/properties/description": "Bob as a Class"
+                }
+            ]
+        }"#;
+        parse_rdf(json_ld, RdfFormat::JsonLd, &store, &opts()).unwrap();
+        let alice = store.get_resource("local:store/ex/alice").unwrap();
+        assert_eq!(alice.get(urls::NAME).unwrap().to_string(), "Alice");
+        assert_eq!(
+            alice.get("local:store/schema/height").unwrap().to_string(),
+            "1.8"
+        );
+        assert_eq!(
+            store
+                .get_property("local:store/schema/height")
+                .unwrap()
+                .data_type,
+            DataType::Float
+        );
+        assert_eq!(
+            store
+                .get_property("local:store/schema/knows")
+                .unwrap()
+                .data_type,
+            DataType::ResourceArray
+        );
+        assert!(matches!(
+            alice.get("local:store/schema/address").unwrap(),
+            Value::NestedResource(SubResource::Nested(_))
+        ));
+        assert_eq!(alice.get_propvals().len(), 5, "unmapped should be ignored");
+        let bob = store.get_resource("local:store/ex/bob").unwrap();
+        assert_eq!(
+            bob.get(urls::IS_A).unwrap().to_subjects(None).unwrap(),
+            vec![urls::CLASS.to_string()],
+            "rdf:type of an existing Class should become isA"
+        );
+    }
+
+    #[test]
+    fn import_ontology() {
+        let store = init_store();
+        let turtle = r#"
+            @prefix ex: <local:store/ex/> .
+            @prefix owl: <http://www.w3.org/2002/07/owl#> .
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+            ex:Person a owl:Class ;
+                rdfs:comment "A human being" ;
+                rdfs:subClassOf [
+                    a owl:Restriction ;
+                    owl:onProperty ex:birthDate ;
+                    owl:minCardinality "1"^^xsd:nonNegativeInteger
+                ], [
+                    a owl:Restriction ;
+                    owl:onProperty ex:employer ;
+                    owl:maxCardinality "1"^^xsd:nonNegativeInteger
+                ] .
+            ex:Organization a rdfs:Class .
+            ex:Employee a owl:Class ;
+                rdfs:subClassOf ex:Person .
+            ex:birthDate a owl:DatatypeProperty ;
+                rdfs:range xsd:date .
+            ex:employer a owl:ObjectProperty ;
+                rdfs:range ex:Organization .
+            ex:friend a owl:ObjectProperty ;
+                rdfs:domain ex:Person ;
+                rdfs:range ex:Person .
+            ex:alice a ex:Person ;
+                ex:birthDate "1990-01-01"^^xsd:date ;
+                ex:friend ex:bob .
+        "#;
+        let resources = parse_rdf(turtle, RdfFormat::Turtle, &store, &opts()).unwrap();
+        // 3 properties, 3 classes and alice
+        assert_eq!(resources.len(), 7);
+
+        let person = store.get_class("local:store/ex/Person").unwrap();
+        assert_eq!(person.shortname, "person");
+        assert_eq!(person.description, "A human being");
+        assert_eq!(person.requires, vec!["local:store/ex/birthDate"]);
+        assert_eq!(
+            person.recommends,
+            vec!["local:store/ex/employer", "local:store/ex/friend"]
+        );
+        let employee = store.get_class("local:store/ex/Employee").unwrap();
+        assert_eq!(employee.extends, vec!["local:store/ex/Person"]);
+
+        let birth_date = store.get_property("local:store/ex/birthDate").unwrap();
+        assert_eq!(birth_date.data_type, DataType::Date);
+        let employer = store.get_property("local:store/ex/employer").unwrap();
+        assert_eq!(employer.data_type, DataType::AtomicUrl);
+        assert_eq!(
+            employer.class_type.as_deref(),
+            Some("local:store/ex/Organization")
+        );
+        let friend = store.get_property("local:store/ex/friend").unwrap();
+        assert_eq!(friend.data_type, DataType::ResourceArray);
+
+        let alice = store.get_resource("local:store/ex/alice").unwrap();
+        assert_eq!(
+            alice.get(urls::IS_A).unwrap().to_subjects(None).unwrap(),
+            vec!["local:store/ex/Person".to_string()]
+        );
+        assert!(matches!(
+            alice.get("local:store/ex/friend").unwrap(),
+            Value::ResourceArray(_)
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+//! Converts OWL and RDFS ontologies into Atomic [Class]es and [Property]s.
+//!
+//! - `owl:Class` and `rdfs:Class` become Classes, `owl:DatatypeProperty`, `owl:ObjectProperty` and `rdf:Property` become Properties.
+//! - `rdfs:comment` becomes the description. `rdfs:range` becomes the [DataType], or the `classtype` if the range is a Class.
+//! - `rdfs:subClassOf` a known Class becomes `extends`.
+//! - Restrictions in `rdfs:subClassOf` become `requires` if they demand at least one value (e.g. `owl:minCardinality 1` or `owl:someValuesFrom`), and `recommends` otherwise.
+//! - Properties that have the Class as `rdfs:domain` are recommended by the Class.
+//! - Object Properties refer to multiple resources, unless they are functional or restricted to a maximum cardinality of one.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{literal_datatype, shortname_from_iri, Graph, Node, Object, RDF_TYPE, XSD};
+use crate::{
+    datatype::{match_datatype, DataType},
+    errors::AtomicResult,
+    schema::{Class, Property, ValueConstraints},
+    Storelike,
+};
+
+const RDF_PROPERTY: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#Property";
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const RDFS_CLASS: &str = "http://www.w3.org/2000/01/rdf-schema#Class";
+const RDFS_COMMENT: &str = "http://www.w3.org/2000/01/rdf-schema#comment";
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const RDFS_DOMAIN: &str = "http://www.w3.org/2000/01/rdf-schema#domain";
+const RDFS_RANGE: &str = "http://www.w3.org/2000/01/rdf-schema#range";
+const RDFS_RESOURCE: &str = "http://www.w3.org/2000/01/rdf-schema#Resource";
+const RDFS_SUB_CLASS_OF: &str = "http://www.w3.org/2000/01/rdf-schema#subClassOf";
+const OWL_CLASS: &str = "http://www.w3.org/2002/07/owl#Class";
+const OWL_THING: &str = "http://www.w3.org/2002/07/owl#Thing";
+const OWL_DATATYPE_PROPERTY: &str = "http://www.w3.org/2002/07/owl#DatatypeProperty";
+const OWL_OBJECT_PROPERTY: &str = "http://www.w3.org/2002/07/owl#ObjectProperty";
+const OWL_FUNCTIONAL_PROPERTY: &str = "http://www.w3.org/2002/07/owl#FunctionalProperty";
+const OWL_ON_PROPERTY: &str = "http://www.w3.org/2002/07/owl#onProperty";
+const OWL_SOME_VALUES_FROM: &str = "http://www.w3.org/2002/07/owl#someValuesFrom";
+const OWL_CARDINALITY: [&str; 2] = [
+    "http://www.w3.org/2002/07/owl#cardinality",
+    "http://www.w3.org/2002/07/owl#qualifiedCardinality",
+];
+const OWL_MIN_CARDINALITY: [&str; 2] = [
+    "http://www.w3.org/2002/07/owl#minCardinality",
+    "http://www.w3.org/2002/07/owl#minQualifiedCardinality",
+];
+const OWL_MAX_CARDINALITY: [&str; 2] = [
+    "http://www.w3.org/2002/07/owl#maxCardinality",
+    "http://www.w3.org/2002/07/owl#maxQualifiedCardinality",
+];
+
+/// An `owl:Restriction` on a Property of a Class.
+struct Restriction {
+    on_property: String,
+    min: u64,
+    max: Option<u64>,
+}
+
+impl<'a, S: Storelike> Graph<'a, S> {
+    /// Creates the Properties and Classes defined in the graph, and removes their statements so they are not converted again.
+    pub(super) fn convert_ontology(&mut self) -> AtomicResult<()> {
+        let classes = self.subjects_of_type(&[OWL_CLASS, RDFS_CLASS]);
+        let properties =
+            self.subjects_of_type(&[OWL_DATATYPE_PROPERTY, OWL_OBJECT_PROPERTY, RDF_PROPERTY]);
+        if classes.is_empty() && properties.is_empty() {
+            return Ok(());
+        }
+        self.classes.extend(classes.iter().cloned());
+
+        let mut converted: HashSet<Node> = HashSet::new();
+        let mut restrictions: HashMap<&str, Vec<Restriction>> = HashMap::new();
+        for class in classes.iter() {
+            for object in self.objects(&Node::Iri(class.clone()), RDFS_SUB_CLASS_OF) {
+                if let Object::Node(node @ Node::Blank(_)) = object {
+                    if let Some(restriction) = self.restriction(node) {
+                        restrictions.entry(class).or_default().push(restriction);
+                        converted.insert(node.clone());
+                    }
+                }
+            }
+        }
+
+        for subject in properties.iter() {
+            let property = self.ontology_property(subject, restrictions.values().flatten());
+            self.properties.insert(subject.clone(), property);
+        }
+        for subject in properties.iter() {
+            let propvals = self.properties[subject].to_resource().into_propvals();
+            self.save_created(subject, propvals)?;
+            converted.insert(Node::Iri(subject.clone()));
+        }
+
+        for subject in classes.iter() {
+            let node = Node::Iri(subject.clone());
+            let mut requires: Vec<String> = Vec::new();
+            let mut recommends: Vec<String> = Vec::new();
+            for restriction in restrictions.get(subject.as_str()).into_iter().flatten() {
+                let list = if restriction.min > 0 {
+                    &mut requires
+                } else {
+                    &mut recommends
+                };
+                if !list.contains(&restriction.on_property) {
+                    list.push(restriction.on_property.clone());
+                }
+            }
+            for property in properties.iter() {
+                if self
+                    .iris(&Node::Iri(property.clone()), RDFS_DOMAIN)
+                    .contains(subject)
+                    && !requires.contains(property)
+                    && !recommends.contains(property)
+                {
+                    recommends.push(property.clone());
+                }
+            }
+            // Restrictions can refer to Properties that are not defined in the ontology
+            for property in requires.iter().chain(recommends.iter()) {
+                self.get_property(property)?;
+            }
+            let extends = self
+                .iris(&node, RDFS_SUB_CLASS_OF)
+                .into_iter()
+                .filter(|iri| self.classes.contains(iri) || self.store.get_class(iri).is_ok())
+                .collect();
+            let class = Class {
+                requires,
+                recommends,
+                extends,
+                unique: vec![],
+                rules: vec![],
+                shortname: shortname_from_iri(subject),
+                description: self.ontology_description(&node),
+                subject: subject.clone(),
+            };
+            self.save_created(subject, class.to_resource().into_propvals())?;
+            converted.insert(node);
+        }
+
+        self.subjects.retain(|node| !converted.contains(node));
+        Ok(())
+    }
+
+    fn ontology_property<'r>(
+        &self,
+        subject: &str,
+        restrictions: impl Iterator<Item = &'r Restriction>,
+    ) -> Property {
+        let node = Node::Iri(subject.into());
+        let types = self.iris(&node, RDF_TYPE);
+        let mut single = types.iter().any(|t| t == OWL_FUNCTIONAL_PROPERTY);
+        for restriction in restrictions {
+            if restriction.on_property == subject && matches!(restriction.max, Some(0..=1)) {
+                single = true;
+            }
+        }
+        let resource_datatype = if single {
+            DataType::AtomicUrl
+        } else {
+            DataType::ResourceArray
+        };
+
+        let (data_type, class_type) = match self.iris(&node, RDFS_RANGE).into_iter().next() {
+            Some(range) if range == OWL_THING || range == RDFS_RESOURCE => {
+                (resource_datatype, None)
+            }
+            Some(range)
+                if range.starts_with(XSD)
+                    || range.starts_with(RDFS)
+                    || types.iter().any(|t| t == OWL_DATATYPE_PROPERTY)
+                    || !matches!(match_datatype(&range), DataType::Unsupported(_)) =>
+            {
+                (literal_datatype(&range), None)
+            }
+            Some(range) => (resource_datatype, Some(range)),
+            None if types.iter().any(|t| t == OWL_OBJECT_PROPERTY) => (resource_datatype, None),
+            None => (DataType::String, None),
+        };
+
+        Property {
+            class_type,
+            data_type,
+            shortname: shortname_from_iri(subject),
+            description: self.ontology_description(&node),
+            subject: subject.into(),
+            allows_only: None,
+            constraints: ValueConstraints::default(),
+            default: None,
+        }
+    }
+
+    /// Uses the `rdfs:comment`, or the `rdfs:label` if there is no comment.
+    fn ontology_description(&self, node: &Node) -> String {
+        [RDFS_COMMENT, RDFS_LABEL]
+            .iter()
+            .flat_map(|predicate| self.objects(node, predicate))
+            .find_map(|object| match object {
+                Object::Literal { value, .. } => Some(value.clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| match node {
+                Node::Iri(iri) => format!("Imported from the ontology definition of {}", iri),
+                Node::Blank(id) => format!("Imported from the ontology definition of _:{}", id),
+            })
+    }
+
+    fn restriction(&self, node: &Node) -> Option<Restriction> {
+        let on_property = self.iris(node, OWL_ON_PROPERTY).into_iter().next()?;
+        let count = |predicates: &[&str]| {
+            predicates
+                .iter()
+                .flat_map(|predicate| self.objects(node, predicate))
+                .find_map(|object| match object {
+                    Object::Literal { value, .. } => value.trim().parse::<u64>().ok(),
+                    _ => None,
+                })
+        };
+        let exact = count(&OWL_CARDINALITY);
+        let some_values = !self.objects(node, OWL_SOME_VALUES_FROM).is_empty();
+        let min = exact
+            .or_else(|| count(&OWL_MIN_CARDINALITY))
+            .unwrap_or(some_values as u64);
+        let max = exact.or_else(|| count(&OWL_MAX_CARDINALITY));
+        Some(Restriction {
+            on_property,
+            min,
+            max,
+        })
+    }
+
+    /// IRI subjects that have one of the `types` as `rdf:type`, in the order in which they were found.
+    fn subjects_of_type(&self, types: &[&str]) -> Vec<String> {
+        self.subjects
+            .iter()
+            .filter_map(|node| match node {
+                Node::Iri(iri)
+                    if self
+                        .iris(node, RDF_TYPE)
+                        .iter()
+                        .any(|t| types.contains(&t.as_str())) =>
+                {
+                    Some(iri.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn objects(&self, node: &Node, predicate: &str) -> Vec<&Object> {
+        self.statements
+            .get(node)
+            .into_iter()
+            .flatten()
+            .filter(|(p, _)| p == predicate)
+            .map(|(_, o)| o)
+            .collect()
+    }
+
+    fn iris(&self, node: &Node, predicate: &str) -> Vec<String> {
+        self.objects(node, predicate)
+            .into_iter()
+            .filter_map(|object| match object {
+                Object::Node(Node::Iri(iri)) => Some(iri.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -6,16 +6,26 @@ use crate::storelike::Query;
 use crate::urls;
 use crate::utils::random_string;
 use crate::values::{SubResource, Value};
-use crate::{commit::CommitBuilder, errors::AtomicResult};
+use crate::{
+    commit::CommitBuilder,
+    errors::{AtomicError, AtomicResult},
+};
 use crate::{
     mapping::is_url,
     schema::{Class, Property},
     Atom, Storelike,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
 use tracing::instrument;
 
+/// Makes sure only one Commit at a time checks and claims the values of a unique Property, see [Resource::lock_unique_values].
+/// Properties share a fixed number of locks, so Commits for unrelated Properties rarely wait for each other.
+static UNIQUE_VALUE_LOCKS: [Mutex<()>; 32] = [const { Mutex::new(()) }; 32];
+
 /// A Resource is a set of Atoms that shares a single Subject.
 /// A Resource only contains valid Values, but it _might_ lack required properties.
 /// All changes to the Resource are applied after committing them (e.g. by using).
@@ -47,6 +57,158 @@ impl Resource {
         Ok(())
     }
 
+    /// Checks whether all values match the constraints of their Properties, such as `min`, `max` and `pattern`.
+    /// Properties that can't be found are skipped.
+    pub fn check_value_constraints(&self, store: &impl Storelike) -> AtomicResult<()> {
+        for (prop, value) in self.propvals.iter() {
+            if let Ok(property) = store.get_property(prop) {
+                property.check_constraints(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the Validation Rules of the Classes of the Resource, see [crate::rules].
+    /// Returns a Validation error that lists the rules that are not met.
+    pub fn check_rules(&self, store: &impl Storelike) -> AtomicResult<()> {
+        crate::rules::check_rules(self, &self.get_classes(store)?, store)
+    }
+
+    /// Checks whether the values of unique Properties are not used by other Resources.
+    /// Properties listed in the `unique` of a Class have to be unique among the instances of that Class,
+    /// Properties with `uniqueInDrive` have to be unique among the Resources in the same Drive.
+    /// Items of Resource Arrays are checked one by one. Nested Resources are skipped.
+    /// Returns a Conflict error that mentions the Resource that already uses the value.
+    pub fn check_unique_values(&self, store: &impl Storelike) -> AtomicResult<()> {
+        let mut drive: Option<Option<String>> = None;
+        for UniqueProp {
+            property: prop,
+            value,
+            classes: declared_in,
+            in_drive,
+        } in self.unique_props(store)?
+        {
+            // Instances of subclasses count as instances of the Class that declares the Property unique
+            let mut unique_in: Vec<(String, &String)> = Vec::new();
+            for declarer in declared_in.iter() {
+                for class in crate::schema::class_and_subclasses(store, declarer)? {
+                    unique_in.push((class, declarer));
+                }
+            }
+            let values = match value {
+                Value::ResourceArray(_) => value
+                    .to_subjects(None)?
+                    .into_iter()
+                    .map(Value::AtomicUrl)
+                    .collect(),
+                Value::NestedResource(_) | Value::Resource(_) => continue,
+                other => vec![other.clone()],
+            };
+            for value in values {
+                let mut q = Query::new();
+                q.property = Some(prop.clone());
+                q.value = Some(value.clone());
+                q.include_external = true;
+                q.include_nested = false;
+                for subject in store.query(&q)?.subjects {
+                    if subject == self.subject {
+                        continue;
+                    }
+                    let Ok(other) = store.get_resource(&subject) else {
+                        continue;
+                    };
+                    let other_classes = match other.get(urls::IS_A) {
+                        Ok(val) => val.to_subjects(None)?,
+                        Err(_) => Vec::new(),
+                    };
+                    if let Some((_, class)) =
+                        unique_in.iter().find(|(c, _)| other_classes.contains(c))
+                    {
+                        return Err(AtomicError::conflict(format!(
+                            "Value '{}' of {} is already used by {}, but has to be unique among instances of {}.",
+                            value, prop, subject, class
+                        ))
+                        .set_subject(&self.subject));
+                    }
+                    if in_drive {
+                        let own_drive = drive.get_or_insert_with(|| self.get_drive(store));
+                        if *own_drive == other.get_drive(store) {
+                            return Err(AtomicError::conflict(format!(
+                                "Value '{}' of {} is already used by {}, but has to be unique within the Drive.",
+                                value, prop, subject
+                            ))
+                            .set_subject(&self.subject));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Locks the unique Properties of the Resource, so no other Commit can check and claim their values until the guards are dropped.
+    /// Returns no guards if the Resource has no values that have to be unique, see [Resource::check_unique_values].
+    pub(crate) fn lock_unique_values(
+        &self,
+        store: &impl Storelike,
+    ) -> AtomicResult<Vec<MutexGuard<'static, ()>>> {
+        let mut locks: Vec<usize> = self
+            .unique_props(store)?
+            .iter()
+            .map(|unique| {
+                let mut hasher = DefaultHasher::new();
+                unique.property.hash(&mut hasher);
+                hasher.finish() as usize % UNIQUE_VALUE_LOCKS.len()
+            })
+            .collect();
+        // Always locked in the same order, to prevent deadlocks
+        locks.sort_unstable();
+        locks.dedup();
+        Ok(locks
+            .into_iter()
+            .map(|i| {
+                UNIQUE_VALUE_LOCKS[i]
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+            })
+            .collect())
+    }
+
+    /// Returns the PropVals that have to be unique.
+    fn unique_props(&self, store: &impl Storelike) -> AtomicResult<Vec<UniqueProp<'_>>> {
+        let classes = self.get_classes(store)?;
+        let mut unique_props = Vec::new();
+        for (prop, value) in self.propvals.iter() {
+            // The Classes that list the Property in their own `unique`, which can be Classes they extend
+            let mut declared_in: Vec<String> = Vec::new();
+            for class in classes.iter().filter(|c| c.unique.contains(prop)) {
+                for subject in std::iter::once(&class.subject).chain(class.extends.iter()) {
+                    if declared_in.contains(subject) {
+                        continue;
+                    }
+                    if let Ok(declarer) = store.get_class(subject) {
+                        if declarer.unique.contains(prop) {
+                            declared_in.push(subject.clone());
+                        }
+                    }
+                }
+            }
+            let in_drive = store
+                .get_property(prop)
+                .map(|p| p.constraints.unique_in_drive)
+                .unwrap_or(false);
+            if !declared_in.is_empty() || in_drive {
+                unique_props.push(UniqueProp {
+                    property: prop,
+                    value,
+                    classes: declared_in,
+                    in_drive,
+                });
+            }
+        }
+        Ok(unique_props)
+    }
+
     /// Removes / deletes the resource from the store by performing a Commit.
     /// Recursively deletes the resource's children.
     #[tracing::instrument(skip(store))]
@@ -94,12 +256,13 @@ impl Resource {
     }
 
     /// Checks if the classes are there, if not, fetches them.
+    /// The classes include the `requires` and `recommends` of the Classes they extend, see [Class::with_inherited].
     /// Returns an empty vector if there are no classes found.
     pub fn get_classes(&self, store: &impl Storelike) -> AtomicResult<Vec<Class>> {
         let mut classes: Vec<Class> = Vec::new();
         if let Ok(val) = self.get(crate::urls::IS_A) {
             for class in val.to_subjects(None)? {
-                classes.push(store.get_class(&class)?)
+                classes.push(store.get_class(&class)?.with_inherited(store)?)
             }
         }
         Ok(classes)
@@ -162,6 +325,28 @@ impl Resource {
         Ok(parents)
     }
 
+    /// Returns the subject of the Drive that the Resource is part of: the closest parent (or the Resource itself) that is a Drive.
+    /// If there is none, the top-most parent is used.
+    pub fn get_drive(&self, store: &impl Storelike) -> Option<String> {
+        let is_drive = |r: &Resource| match r.get(urls::IS_A) {
+            Ok(val) => val
+                .to_subjects(None)
+                .unwrap_or_default()
+                .iter()
+                .any(|c| c == urls::DRIVE),
+            Err(_) => false,
+        };
+        if is_drive(self) {
+            return Some(self.subject.clone());
+        }
+        let parents = self.get_parent_tree(store).ok()?;
+        parents
+            .iter()
+            .find(|p| is_drive(p))
+            .or(parents.last())
+            .map(|p| p.subject.clone())
+    }
+
     /// Returns all PropVals.
     /// Useful if you want to iterate over all Atoms / Properties.
     pub fn get_propvals(&self) -> &PropVals {
@@ -199,6 +384,17 @@ impl Resource {
         self.propvals
     }
 
+    /// Removes all but the first of the preferred languages that is available from the language-tagged strings,
+    /// including those of nested Resources. The values stay language-tagged strings, so they keep matching their Property.
+    /// Values without any of the languages keep all their languages.
+    /// Use this before serializing for a reader, e.g. with the languages of an `Accept-Language` header.
+    pub fn localize(&mut self, languages: &[String]) -> &mut Self {
+        if !languages.is_empty() {
+            localize_propvals(&mut self.propvals, languages);
+        }
+        self
+    }
+
     /// Create a new, empty Resource.
     pub fn new(subject: String) -> Resource {
         let propvals: PropVals = HashMap::new();
@@ -221,13 +417,7 @@ impl Resource {
     /// Does not save the resource to the store.
     pub fn new_instance(class_url: &str, store: &impl Storelike) -> AtomicResult<Resource> {
         let propvals: PropVals = HashMap::new();
-        let class = store.get_class(class_url)?;
-        let subject = format!(
-            "{}/{}/{}",
-            store.get_server_url(),
-            &class.shortname,
-            random_string(10)
-        );
+        let subject = Resource::new_instance_subject(class_url, store)?;
         let mut resource = Resource {
             propvals,
             subject: subject.clone(),
@@ -235,9 +425,49 @@ impl Resource {
         };
         let class_urls = Vec::from([String::from(class_url)]);
         resource.set(crate::urls::IS_A.into(), class_urls.into(), store)?;
+        let agent = store.get_default_agent().ok().map(|a| a.subject);
+        resource.set_defaults(store, agent.as_deref())?;
         Ok(resource)
     }
 
+    /// Generates a Subject for a new instance of some Class, without creating the Resource.
+    /// Unlike [Resource::new_instance], this does not set default values, so it does not advance counters.
+    pub fn new_instance_subject(class_url: &str, store: &impl Storelike) -> AtomicResult<String> {
+        let class = store.get_class(class_url)?;
+        Ok(format!(
+            "{}/{}/{}",
+            store.get_server_url(),
+            &class.shortname,
+            random_string(10)
+        ))
+    }
+
+    /// Sets the default values of the required and recommended Properties of the Classes of this Resource that don't have a value yet.
+    /// See [crate::schema::DefaultValue]. The `agent` is the subject of the Agent that creates the Resource.
+    /// Returns the Properties that have been set.
+    pub fn set_defaults(
+        &mut self,
+        store: &impl Storelike,
+        agent: Option<&str>,
+    ) -> AtomicResult<Vec<String>> {
+        let mut set_props: Vec<String> = Vec::new();
+        for class in self.get_classes(store)? {
+            for prop in class.requires.iter().chain(class.recommends.iter()) {
+                if self.propvals.contains_key(prop) {
+                    continue;
+                }
+                let Ok(property) = store.get_property(prop) else {
+                    continue;
+                };
+                if let Some(value) = property.default_value(store, agent)? {
+                    self.set(prop.clone(), value, store)?;
+                    set_props.push(prop.clone());
+                }
+            }
+        }
+        Ok(set_props)
+    }
+
     /// Appends a Resource to a specific property through the commitbuilder.
     /// Useful if you want to have compact Commits that add things to existing ResourceArrays.
     pub fn push(
@@ -429,7 +659,7 @@ impl Resource {
     }
 
     /// Inserts a Property/Value combination.
-    /// Checks datatype.
+    /// Checks datatype, `allowsOnly` and the other constraints of the Property.
     /// Overwrites existing.
     /// Adds the change to the commit builder's `set` map.
     pub fn set(
@@ -439,7 +669,7 @@ impl Resource {
         store: &impl Storelike,
     ) -> AtomicResult<&mut Self> {
         let full_prop = store.get_property(&property)?;
-        if let Some(allowed) = full_prop.allows_only {
+        if let Some(allowed) = &full_prop.allows_only {
             let error = Err(format!(
                 "Property '{}' does not allow value '{}'. Allowed: {:?}",
                 property, value, allowed
@@ -462,6 +692,7 @@ impl Resource {
             }
         }
         if full_prop.data_type == value.datatype() {
+            full_prop.check_constraints(&value)?;
             self.set_unsafe(property, value);
             Ok(self)
         } else {
@@ -524,6 +755,16 @@ impl Resource {
         serde_json::to_string_pretty(&obj).map_err(|_| "Could not serialize to JSON-AD".into())
     }
 
+    /// Converts Resource to binary JSON-AD (CBOR), see [crate::serialize::cbor].
+    #[instrument(skip_all)]
+    pub fn to_json_ad_cbor(&self) -> AtomicResult<Vec<u8>> {
+        let obj = crate::serialize::propvals_to_json_ad_map(
+            self.get_propvals(),
+            Some(self.get_subject().clone()),
+        )?;
+        crate::serialize::cbor::json_to_cbor_bytes(obj)
+    }
+
     /// Converts Resource to plain JSON string.
     #[instrument(skip_all)]
     pub fn to_json(&self, store: &impl Storelike) -> AtomicResult<String> {
@@ -566,12 +807,174 @@ impl Resource {
     }
 }
 
+/// A PropVal that has to be unique, see [Resource::check_unique_values].
+struct UniqueProp<'a> {
+    property: &'a String,
+    value: &'a Value,
+    /// The Classes that declare the Property unique. The value has to be unique among their instances and those of their subclasses.
+    classes: Vec<String>,
+    /// Whether the value has to be unique in the Drive
+    in_drive: bool,
+}
+
+fn localize_propvals(propvals: &mut PropVals, languages: &[String]) {
+    for value in propvals.values_mut() {
+        localize_value(value, languages);
+    }
+}
+
+fn localize_value(value: &mut Value, languages: &[String]) {
+    match value {
+        Value::LangString(texts) => {
+            if let Some(localized) = texts.localized(languages) {
+                *texts = localized;
+            }
+        }
+        Value::Resource(resource) => {
+            resource.localize(languages);
+        }
+        Value::NestedResource(sub_resource) => localize_sub_resource(sub_resource, languages),
+        Value::ResourceArray(items) => {
+            for item in items {
+                localize_sub_resource(item, languages);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn localize_sub_resource(sub_resource: &mut SubResource, languages: &[String]) {
+    match sub_resource {
+        SubResource::Resource(resource) => {
+            resource.localize(languages);
+        }
+        SubResource::Nested(propvals) => localize_propvals(propvals, languages),
+        SubResource::Subject(_) => {}
+    }
+}
+
 #[cfg(test)]
 mod test {
     use ntest::assert_panics;
 
     use super::*;
-    use crate::{test_utils::init_store, urls};
+    use crate::{datatype::DataType, test_utils::init_store, urls};
+
+    fn add_property(
+        store: &impl Storelike,
+        shortname: &str,
+        data_type: DataType,
+        constraints: crate::schema::ValueConstraints,
+    ) -> String {
+        let property = crate::schema::Property {
+            class_type: None,
+            data_type,
+            shortname: shortname.into(),
+            description: "A constrained property".into(),
+            subject: format!("https://localhost/properties/{}", shortname),
+            allows_only: None,
+            constraints,
+            default: None,
+        };
+        store.add_resource(&property.to_resource()).unwrap();
+        property.subject
+    }
+
+    #[test]
+    fn set_checks_constraints() {
+        let store = init_store();
+        let age = add_property(
+            &store,
+            "age",
+            DataType::Integer,
+            crate::schema::ValueConstraints {
+                min: Some(0),
+                max: Some(150),
+                ..Default::default()
+            },
+        );
+        let code = add_property(
+            &store,
+            "code",
+            DataType::String,
+            crate::schema::ValueConstraints {
+                max_length: Some(5),
+                pattern: Some("^[A-Z]+$".into()),
+                ..Default::default()
+            },
+        );
+        let tags = add_property(
+            &store,
+            "tags",
+            DataType::ResourceArray,
+            crate::schema::ValueConstraints {
+                max: Some(1),
+                ..Default::default()
+            },
+        );
+        // The constraints survive the roundtrip through the store
+        assert_eq!(store.get_property(&age).unwrap().constraints.max, Some(150));
+
+        let mut resource = Resource::new_generate_subject(&store);
+        resource
+            .set(age.clone(), Value::Integer(30), &store)
+            .unwrap();
+        resource.set(age, Value::Integer(-1), &store).unwrap_err();
+        // Integers are compared as integers, even beyond the precision of a float
+        let big = add_property(
+            &store,
+            "big",
+            DataType::Integer,
+            crate::schema::ValueConstraints {
+                max: Some(i64::MAX - 1),
+                ..Default::default()
+            },
+        );
+        resource
+            .set(big.clone(), Value::Integer(i64::MAX - 1), &store)
+            .unwrap();
+        resource
+            .set(big, Value::Integer(i64::MAX), &store)
+            .unwrap_err();
+        let huge = add_property(
+            &store,
+            "huge",
+            DataType::BigInt,
+            crate::schema::ValueConstraints {
+                max: Some(10),
+                ..Default::default()
+            },
+        );
+        resource
+            .set(huge.clone(), Value::BigInt("10".into()), &store)
+            .unwrap();
+        resource
+            .set(huge, Value::BigInt("100000000000000000001".into()), &store)
+            .unwrap_err();
+        resource
+            .set(code.clone(), Value::String("ABC".into()), &store)
+            .unwrap();
+        resource
+            .set(code.clone(), Value::String("abc".into()), &store)
+            .unwrap_err();
+        resource
+            .set(code, Value::String("ABCDEF".into()), &store)
+            .unwrap_err();
+        resource
+            .set(
+                tags.clone(),
+                vec![urls::CLASS, urls::PROPERTY].into(),
+                &store,
+            )
+            .unwrap_err();
+        resource.save_locally(&store).unwrap();
+
+        // Pushing skips `set`, so the Commit checks the constraints of the new Resource
+        resource.push(&tags, urls::CLASS.into(), false).unwrap();
+        resource.push(&tags, urls::PROPERTY.into(), false).unwrap();
+        let err = resource.save_locally(&store).unwrap_err();
+        assert!(err.to_string().contains("maximum is 1"), "{}", err);
+    }
 
     #[test]
     fn get_and_set_resource_props() {
@@ -613,6 +1016,463 @@ mod test {
         new_resource.check_required_props(&store).unwrap();
     }
 
+    #[test]
+    fn check_inherited_required_props() {
+        let store = init_store();
+        let add_class = |shortname: &str, requires: &str, extends: &str| {
+            let class = Class {
+                requires: vec![requires.into()],
+                recommends: vec![urls::NAME.into()],
+                extends: vec![format!("https://localhost/classes/{}", extends)],
+                unique: vec![],
+                rules: vec![],
+                shortname: shortname.into(),
+                description: "A class that extends another".into(),
+                subject: format!("https://localhost/classes/{}", shortname),
+            };
+            store.add_resource(&class.to_resource()).unwrap();
+            class.subject
+        };
+        // The classes extend each other, which should not cause an infinite loop
+        let creature = add_class("creature", urls::DESCRIPTION, "animal");
+        let animal = add_class("animal", urls::NAME, "creature");
+
+        let class = &Resource::new_instance(&animal, &store)
+            .unwrap()
+            .get_classes(&store)
+            .unwrap()[0];
+        assert_eq!(class.requires, vec![urls::NAME, urls::DESCRIPTION]);
+        assert!(
+            class.recommends.is_empty(),
+            "required wins over recommended"
+        );
+        assert_eq!(class.extends, vec![creature]);
+
+        let mut resource = Resource::new("https://localhost/dog".into());
+        resource.set_class(&animal);
+        resource
+            .set(urls::NAME.into(), Value::String("Dog".into()), &store)
+            .unwrap();
+        resource.check_required_props(&store).unwrap_err();
+        resource
+            .set(
+                urls::DESCRIPTION.into(),
+                Value::Markdown("Woof".into()),
+                &store,
+            )
+            .unwrap();
+        resource.check_required_props(&store).unwrap();
+    }
+
+    #[test]
+    fn check_unique_values() {
+        let store = init_store();
+        let email = add_property(
+            &store,
+            "email",
+            DataType::String,
+            crate::schema::ValueConstraints::default(),
+        );
+        let slug = add_property(
+            &store,
+            "page-slug",
+            DataType::Slug,
+            crate::schema::ValueConstraints {
+                unique_in_drive: true,
+                ..Default::default()
+            },
+        );
+        let person = Class {
+            requires: vec![],
+            recommends: vec![email.clone()],
+            extends: vec![],
+            unique: vec![email.clone()],
+            rules: vec![],
+            shortname: "person".into(),
+            description: "A person with a unique email".into(),
+            subject: "https://localhost/classes/person".into(),
+        };
+        store.add_resource(&person.to_resource()).unwrap();
+
+        let save = |subject: &str, class: Option<&str>, prop: &str, value: Value| {
+            let mut resource = Resource::new(format!("https://localhost/{}", subject));
+            if let Some(class) = class {
+                resource.set_class(class);
+            }
+            resource.set(prop.into(), value, &store).unwrap();
+            resource.save_locally(&store).map(|_| resource)
+        };
+        let address = || Value::String("alice@example.com".into());
+        let mut alice = save("alice", Some(&person.subject), &email, address()).unwrap();
+        let err = save("bob", Some(&person.subject), &email, address()).unwrap_err();
+        assert!(matches!(
+            err.error_type,
+            crate::AtomicErrorType::ConflictError
+        ));
+        assert!(err.message.contains("alice"), "{}", err.message);
+        // Only instances of the Class are compared
+        save("mailbox", None, &email, address()).unwrap();
+        // Instances of subclasses are compared with instances of the Class that declares the Property unique
+        let employee = Class {
+            extends: vec![person.subject.clone()],
+            unique: vec![],
+            shortname: "employee".into(),
+            subject: "https://localhost/classes/employee".into(),
+            ..person.clone()
+        };
+        store.add_resource(&employee.to_resource()).unwrap();
+        let err = save("bob", Some(&employee.subject), &email, address()).unwrap_err();
+        assert!(err.message.contains(&person.subject), "{}", err.message);
+        let other_address = || Value::String("carol@example.com".into());
+        save("carol", Some(&employee.subject), &email, other_address()).unwrap();
+        save("dave", Some(&person.subject), &email, other_address()).unwrap_err();
+        // A Resource does not conflict with itself
+        alice
+            .set(urls::NAME.into(), Value::String("Alice".into()), &store)
+            .unwrap();
+        alice.save_locally(&store).unwrap();
+
+        for drive in ["drive1", "drive2"] {
+            let mut resource = Resource::new(format!("https://localhost/{}", drive));
+            resource.set_class(urls::DRIVE);
+            store.add_resource(&resource).unwrap();
+        }
+        let save_in_drive = |subject: &str, drive: &str| {
+            let mut resource = Resource::new(format!("https://localhost/{}", subject));
+            resource.set_unsafe(
+                urls::PARENT.into(),
+                Value::AtomicUrl(format!("https://localhost/{}", drive)),
+            );
+            resource
+                .set(slug.clone(), Value::Slug("home".into()), &store)
+                .unwrap();
+            resource.save_locally(&store)
+        };
+        save_in_drive("home1", "drive1").unwrap();
+        save_in_drive("home2", "drive2").unwrap();
+        let err = save_in_drive("home3", "drive1").unwrap_err();
+        assert!(matches!(
+            err.error_type,
+            crate::AtomicErrorType::ConflictError
+        ));
+    }
+
+    #[test]
+    fn check_rules() {
+        use crate::rules::ValidationRule;
+        let store = init_store();
+        let constraints = crate::schema::ValueConstraints::default;
+        let start = add_property(&store, "start-date", DataType::Date, constraints());
+        let end = add_property(&store, "end-date", DataType::Date, constraints());
+        let status = add_property(&store, "status", DataType::String, constraints());
+        let completed = add_property(&store, "completed-at", DataType::DateTime, constraints());
+        let rules = [
+            ("end-after-start", "end-date >= start-date", None),
+            (
+                "done-needs-completion",
+                r#"if status = "done" then exists(completed-at)"#,
+                Some("Tasks that are done need a completion time"),
+            ),
+        ]
+        .map(|(shortname, expression, description)| {
+            let rule = ValidationRule {
+                subject: format!("https://localhost/rules/{}", shortname),
+                shortname: shortname.into(),
+                description: description.map(String::from),
+                expression: expression.into(),
+            };
+            store.add_resource(&rule.to_resource()).unwrap();
+            rule.subject
+        });
+        let task = Class {
+            requires: vec![],
+            recommends: vec![
+                start.clone(),
+                end.clone(),
+                status.clone(),
+                completed.clone(),
+            ],
+            extends: vec![],
+            unique: vec![],
+            rules: rules.to_vec(),
+            shortname: "task".into(),
+            description: "A task with a planning".into(),
+            subject: "https://localhost/classes/task".into(),
+        };
+        store.add_resource(&task.to_resource()).unwrap();
+
+        let save = |subject: &str, propvals: Vec<(&String, &str)>| {
+            let mut resource = Resource::new(format!("https://localhost/{}", subject));
+            resource.set_class(&task.subject);
+            for (prop, value) in propvals {
+                resource.set_string(prop.into(), value, &store).unwrap();
+            }
+            resource.save_locally(&store)
+        };
+        save(
+            "planned",
+            vec![(&start, "2024-05-01"), (&end, "2024-05-03")],
+        )
+        .unwrap();
+        // Rules with missing values are not broken
+        save("open", vec![(&end, "2024-05-03"), (&status, "open")]).unwrap();
+
+        let err = save(
+            "broken",
+            vec![
+                (&start, "2024-05-03"),
+                (&end, "2024-05-01"),
+                (&status, "done"),
+            ],
+        )
+        .unwrap_err();
+        let crate::AtomicErrorType::ValidationError(violations) = &err.error_type else {
+            panic!("Expected a validation error, got {:?}", err);
+        };
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].rule, rules[0]);
+        assert_eq!(violations[0].properties, vec![end.clone(), start.clone()]);
+        assert_eq!(
+            violations[1].properties,
+            vec![status.clone(), completed.clone()]
+        );
+        assert_eq!(
+            violations[1].message,
+            "Tasks that are done need a completion time"
+        );
+        let error_resource = err.into_resource("https://localhost/broken".into());
+        assert_eq!(
+            error_resource
+                .get(urls::VIOLATIONS)
+                .unwrap()
+                .to_subjects(None)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Imported data is checked too
+        let json = format!(
+            r#"{{"@id": "https://localhost/imported", "{}": ["{}"], "{}": "2024-05-03", "{}": "2024-05-01"}}"#,
+            urls::IS_A,
+            task.subject,
+            start,
+            end
+        );
+        let err = store
+            .import(&json, &crate::parse::ParseOpts::default())
+            .unwrap_err();
+        assert!(
+            matches!(err.error_type, crate::AtomicErrorType::ValidationError(_)),
+            "{}",
+            err
+        );
+        assert!(err.message.contains("end-after-start"), "{}", err.message);
+    }
+
+    #[test]
+    fn set_defaults() {
+        use crate::schema::{DefaultValue, Generator};
+        let store = init_store();
+        let add_default = |shortname: &str, data_type: DataType, default: DefaultValue| {
+            let property = crate::schema::Property {
+                class_type: None,
+                data_type,
+                shortname: shortname.into(),
+                description: "A property with a default".into(),
+                subject: format!("https://localhost/properties/{}", shortname),
+                allows_only: None,
+                constraints: Default::default(),
+                default: Some(default),
+            };
+            store.add_resource(&property.to_resource()).unwrap();
+            property.subject
+        };
+        let status = add_default(
+            "status",
+            DataType::String,
+            DefaultValue::Static(Value::String("open".into())),
+        );
+        let number = add_default(
+            "number",
+            DataType::Integer,
+            DefaultValue::Generated(Generator::Counter),
+        );
+        let created = add_default(
+            "created",
+            DataType::Timestamp,
+            DefaultValue::Generated(Generator::Now),
+        );
+        let creator = add_default(
+            "creator",
+            DataType::AtomicUrl,
+            DefaultValue::Generated(Generator::Agent),
+        );
+        let key = add_default(
+            "key",
+            DataType::Slug,
+            DefaultValue::Generated(Generator::Uuid),
+        );
+        assert!(matches!(
+            store.get_property(&status).unwrap().default,
+            Some(DefaultValue::Static(Value::String(s))) if s == "open"
+        ));
+        let issue = Class {
+            requires: vec![status.clone()],
+            recommends: vec![
+                number.clone(),
+                created.clone(),
+                creator.clone(),
+                key.clone(),
+            ],
+            extends: vec![],
+            unique: vec![],
+            rules: vec![],
+            shortname: "issue".into(),
+            description: "An issue with defaults".into(),
+            subject: "https://localhost/classes/issue".into(),
+        };
+        store.add_resource(&issue.to_resource()).unwrap();
+
+        let mut first = Resource::new_instance(&issue.subject, &store).unwrap();
+        assert_eq!(first.get(&status).unwrap().to_string(), "open");
+        assert_eq!(first.get(&number).unwrap().to_int().unwrap(), 1);
+        assert!(first.get(&created).unwrap().to_int().unwrap() > 0);
+        assert_eq!(
+            first.get(&creator).unwrap().to_string(),
+            store.get_default_agent().unwrap().subject
+        );
+        assert_eq!(first.get(&key).unwrap().to_string().len(), 36);
+        first.save_locally(&store).unwrap();
+
+        // Values that are set are not overwritten
+        let mut second = Resource::new("https://localhost/issue2".into());
+        second.set_class(&issue.subject);
+        second
+            .set(status.clone(), Value::String("closed".into()), &store)
+            .unwrap();
+        second.save_locally(&store).unwrap();
+        // A create Commit gets the defaults that it omits
+        let second = store.get_resource("https://localhost/issue2").unwrap();
+        assert_eq!(second.get(&status).unwrap().to_string(), "closed");
+        assert_eq!(second.get(&number).unwrap().to_int().unwrap(), 2);
+        assert!(second.get(&key).is_ok());
+        // These are recorded in the Commit, outside of the signed `set`
+        let commit_subject = second.get(urls::LAST_COMMIT).unwrap().to_string();
+        let commit =
+            crate::Commit::from_resource(store.get_resource(&commit_subject).unwrap()).unwrap();
+        let defaults = commit.defaults.as_ref().unwrap();
+        assert_eq!(defaults.get(&number).unwrap().to_int().unwrap(), 2);
+        assert!(defaults.contains_key(&key));
+        assert!(!defaults.contains_key(&status));
+        assert!(!commit.set.as_ref().unwrap().contains_key(&number));
+        // Replaying the Commit gives the same Resource
+        let replayed = commit
+            .apply_changes(Resource::new(second.get_subject().clone()), &store, false)
+            .unwrap();
+        assert_eq!(
+            replayed.get(&key).unwrap().to_string(),
+            second.get(&key).unwrap().to_string()
+        );
+
+        // A Commit that fails the rights checks does not use up a number
+        let stranger = store.create_agent(Some("stranger")).unwrap();
+        let mut forbidden = Resource::new("https://localhost/issue3".into());
+        forbidden.set_class(&issue.subject);
+        forbidden
+            .set(
+                urls::PARENT.into(),
+                Value::AtomicUrl(store.get_server_url().into()),
+                &store,
+            )
+            .unwrap();
+        let commit = forbidden
+            .get_commit_builder()
+            .clone()
+            .sign(&stranger, &store, &forbidden)
+            .unwrap();
+        commit
+            .apply_opts(
+                &store,
+                &crate::commit::CommitOpts {
+                    validate_schema: true,
+                    validate_signature: true,
+                    validate_timestamp: true,
+                    validate_rights: true,
+                    validate_previous_commit: true,
+                    update_index: true,
+                    validate_for_agent: None,
+                },
+            )
+            .unwrap_err();
+        // Neither does generating the subject of an imported instance
+        let parse_opts = crate::parse::ParseOpts {
+            importer: Some(store.get_server_url().into()),
+            signer: Some(store.get_default_agent().unwrap()),
+            save: crate::parse::SaveOpts::Commit,
+            ..Default::default()
+        };
+        let imported = crate::parse::parse_json_with_class(
+            r#"{"status": "new"}"#,
+            &issue.subject,
+            &store,
+            &parse_opts,
+        )
+        .unwrap();
+        let imported = store.get_resource(imported[0].get_subject()).unwrap();
+        assert_eq!(imported.get(&number).unwrap().to_int().unwrap(), 3);
+    }
+
+    #[test]
+    fn localize() {
+        let store = init_store();
+        let title = add_property(
+            &store,
+            "title",
+            DataType::LangString,
+            crate::schema::ValueConstraints {
+                max_length: Some(5),
+                ..Default::default()
+            },
+        );
+        let mut resource = Resource::new("https://localhost/greeting".into());
+        resource
+            .set_string(title.clone(), r#"{"en": "Hello", "nl": "Hallo"}"#, &store)
+            .unwrap();
+        // Constraints apply to every language
+        let too_long = Value::new(
+            r#"{"en": "Hello", "de": "Guten Tag"}"#,
+            &DataType::LangString,
+        )
+        .unwrap();
+        assert!(resource.set(title.clone(), too_long, &store).is_err());
+        let mut nested = PropVals::new();
+        nested.insert(title.clone(), resource.get(&title).unwrap().clone());
+        resource.set_unsafe(
+            urls::CHILDREN.into(),
+            vec![SubResource::Nested(nested)].into(),
+        );
+
+        let mut unknown = resource.clone();
+        unknown.localize(&["fr".into()]);
+        assert!(matches!(unknown.get(&title).unwrap(), Value::LangString(_)));
+
+        resource.localize(&["nl-BE".into(), "en".into()]);
+        assert_eq!(
+            resource.get(&title).unwrap().to_string(),
+            r#"{"nl":"Hallo"}"#
+        );
+        let Value::ResourceArray(children) = resource.get(urls::CHILDREN).unwrap() else {
+            panic!("Children should be a Resource Array")
+        };
+        let SubResource::Nested(nested) = &children[0] else {
+            panic!("Child should be nested")
+        };
+        assert!(
+            matches!(nested.get(&title), Some(Value::LangString(s)) if s.iter().eq([("nl", "Hallo")]))
+        );
+    }
+
     #[test]
     fn new_instance() {
         let store = init_store();
@@ -0,0 +1,787 @@
+//! Validation Rules describe constraints that involve several Properties of a Resource,
+//! such as `endDate >= startDate` or `if status = "done" then exists(completedAt)`.
+//! Classes list their [ValidationRule]s in `rules`.
+//! These are checked when Commits are applied and when data is imported, see [check_rules].
+//!
+//! Rules use three-valued logic, like `CHECK` constraints in SQL:
+//! a comparison with a missing value is unknown, and a rule only fails if it is false.
+//! Use `exists(shortname)` to require a value.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{
+    datatype::DataType,
+    errors::{AtomicError, AtomicErrorType, AtomicResult},
+    mapping::is_url,
+    schema::Class,
+    urls, Resource, Storelike, Value,
+};
+
+/// A rule that the instances of a Class have to follow.
+/// https://atomicdata.dev/classes/ValidationRule
+#[derive(Clone, Debug)]
+pub struct ValidationRule {
+    /// URL of the rule
+    pub subject: String,
+    pub shortname: String,
+    /// Explains the rule. Used as the error message if the rule is not met.
+    pub description: Option<String>,
+    /// https://atomicdata.dev/properties/expression
+    pub expression: String,
+}
+
+impl ValidationRule {
+    pub fn from_resource(resource: Resource) -> AtomicResult<ValidationRule> {
+        Ok(ValidationRule {
+            subject: resource.get_subject().into(),
+            shortname: resource.get(urls::SHORTNAME)?.to_string(),
+            description: resource.get(urls::DESCRIPTION).ok().map(|v| v.to_string()),
+            expression: resource.get(urls::EXPRESSION)?.to_string(),
+        })
+    }
+
+    pub fn to_resource(&self) -> Resource {
+        let mut resource = Resource::new(self.subject.clone());
+        resource.set_class(urls::VALIDATION_RULE);
+        resource.set_unsafe(urls::SHORTNAME.into(), Value::Slug(self.shortname.clone()));
+        resource.set_unsafe(
+            urls::EXPRESSION.into(),
+            Value::String(self.expression.clone()),
+        );
+        if let Some(description) = &self.description {
+            resource.set_unsafe(urls::DESCRIPTION.into(), Value::String(description.clone()));
+        }
+        resource
+    }
+
+    pub fn parse(&self) -> AtomicResult<Expr> {
+        parse_expression(&self.expression).map_err(|mut e| {
+            e.message = format!(
+                "Invalid expression in Validation Rule {}: {}",
+                self.subject, e.message
+            );
+            e
+        })
+    }
+}
+
+/// A Validation Rule that a Resource does not follow.
+/// Returned in [crate::errors::AtomicErrorType::ValidationError].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleViolation {
+    /// Subject of the [ValidationRule]
+    pub rule: String,
+    /// Shortname of the [ValidationRule]
+    pub shortname: String,
+    /// Subject of the Class that has the rule
+    pub class: String,
+    /// Subjects of the Properties in the expression of the rule
+    pub properties: Vec<String>,
+    /// The description of the rule, or its expression if it has none
+    pub message: String,
+}
+
+impl std::fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rule '{}' for {} is not met: {}",
+            self.shortname,
+            self.properties.join(", "),
+            self.message
+        )
+    }
+}
+
+/// A parsed [ValidationRule] expression, see [parse_expression].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// `if a then b`, which is the same as `not a or b`
+    If(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// `exists(shortname)` is true if the Resource has a value for the Property
+    Exists(String),
+    Compare(Operand, Comparison, Operand),
+    /// A Boolean Property, or `true` or `false`
+    Operand(Operand),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    /// The value of a Property, referred to by its shortname or its URL
+    Property(String),
+    /// A quoted text, e.g. `"done"`
+    Text(String),
+    Number(String),
+    Boolean(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// The same comparison with the operands swapped, so `a < b` becomes `b > a`.
+    fn flip(self) -> Comparison {
+        match self {
+            Comparison::Less => Comparison::Greater,
+            Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+            Comparison::Greater => Comparison::Less,
+            Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+            other => other,
+        }
+    }
+
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl Expr {
+    /// The shortnames and URLs of the Properties used in the expression, without duplicates.
+    pub fn properties(&self) -> Vec<String> {
+        let mut properties = Vec::new();
+        self.collect_properties(&mut properties);
+        properties
+    }
+
+    fn collect_properties(&self, properties: &mut Vec<String>) {
+        let operands = match self {
+            Expr::If(a, b) | Expr::Or(a, b) | Expr::And(a, b) => {
+                a.collect_properties(properties);
+                b.collect_properties(properties);
+                return;
+            }
+            Expr::Not(a) => return a.collect_properties(properties),
+            Expr::Exists(name) => vec![name],
+            Expr::Compare(a, _, b) => [a, b]
+                .into_iter()
+                .filter_map(|o| match o {
+                    Operand::Property(name) => Some(name),
+                    _ => None,
+                })
+                .collect(),
+            Expr::Operand(Operand::Property(name)) => vec![name],
+            Expr::Operand(_) => vec![],
+        };
+        for name in operands {
+            if !properties.contains(name) {
+                properties.push(name.clone());
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Compare(Comparison),
+    /// Keywords and shortnames
+    Word(String),
+    /// A URL between `<` and `>`
+    Url(String),
+    Text(String),
+    Number(String),
+}
+
+fn tokenize(expression: &str) -> AtomicResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let token = match c {
+            '(' => {
+                chars.next();
+                Token::Open
+            }
+            ')' => {
+                chars.next();
+                Token::Close
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => text.push(escaped),
+                            None => return Err("Unterminated text, missing a closing \"".into()),
+                        },
+                        Some(other) => text.push(other),
+                        None => return Err("Unterminated text, missing a closing \"".into()),
+                    }
+                }
+                Token::Text(text)
+            }
+            '<' | '>' | '=' | '!' => {
+                chars.next();
+                let followed_by_equals = chars.next_if_eq(&'=').is_some();
+                let starts_url = || chars.clone().take(4).collect::<String>() == "http";
+                if c == '<' && !followed_by_equals && starts_url() {
+                    let url: String = chars.by_ref().take_while(|n| *n != '>').collect();
+                    Token::Url(url)
+                } else {
+                    Token::Compare(match (c, followed_by_equals) {
+                        ('<', false) => Comparison::Less,
+                        ('<', true) => Comparison::LessOrEqual,
+                        ('>', false) => Comparison::Greater,
+                        ('>', true) => Comparison::GreaterOrEqual,
+                        ('=', _) => Comparison::Equal,
+                        ('!', true) => Comparison::NotEqual,
+                        _ => return Err("Unexpected '!', use `not` or `!=`".into()),
+                    })
+                }
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut number = String::new();
+                number.push(c);
+                chars.next();
+                while let Some(next) = chars.next_if(|n| n.is_ascii_digit() || *n == '.') {
+                    number.push(next);
+                }
+                if number.parse::<f64>().is_err() {
+                    return Err(format!("Not a valid number: {}", number).into());
+                }
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = String::new();
+                while let Some(next) =
+                    chars.next_if(|n| n.is_alphanumeric() || *n == '-' || *n == '_')
+                {
+                    word.push(next);
+                }
+                Token::Word(word)
+            }
+            other => return Err(format!("Unexpected character '{}'", other).into()),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Longer expressions are rejected, so that parsing and checking rules stays cheap.
+pub const MAX_EXPRESSION_LENGTH: usize = 4096;
+/// Parentheses, `not` and `if` can be nested this deep.
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+const KEYWORDS: [&str; 8] = ["if", "then", "and", "or", "not", "exists", "true", "false"];
+
+/// Parses the expression of a [ValidationRule].
+///
+/// - Properties are referred to by their shortname (e.g. `endDate`), or by their URL between `<` and `>`.
+/// - Values are quoted texts (`"done"`), numbers (`-1.5`), `true` or `false`.
+///   They are converted to the datatype of the Property they are compared with.
+/// - Comparisons: `=`, `!=`, `<`, `<=`, `>` and `>=`.
+///   For Resource Arrays, `=` checks whether the array contains the value.
+/// - `exists(shortname)` checks whether the Resource has a value for the Property.
+/// - Combine these with `and`, `or`, `not`, parentheses, and `if ... then ...`.
+///
+/// Expressions are limited to [MAX_EXPRESSION_LENGTH] bytes and [MAX_NESTING_DEPTH] levels of nesting.
+/// Returns a Parse error if the expression is invalid.
+pub fn parse_expression(expression: &str) -> AtomicResult<Expr> {
+    parse_tokens(expression).map_err(|mut e| {
+        e.error_type = AtomicErrorType::ParseError;
+        e
+    })
+}
+
+fn parse_tokens(expression: &str) -> AtomicResult<Expr> {
+    if expression.len() > MAX_EXPRESSION_LENGTH {
+        return Err(format!(
+            "The expression is {} bytes long, the maximum is {}",
+            expression.len(),
+            MAX_EXPRESSION_LENGTH
+        )
+        .into());
+    }
+    let tokens = tokenize(expression)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        depth: 0,
+    };
+    let expr = parser.expression()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("Unexpected {:?} after the end of the expression", token).into());
+    }
+    Ok(expr)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// How deeply the current token is nested, see [MAX_NESTING_DEPTH]
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consumes the keyword if it is the next token.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(w)) if w == keyword) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, expected: Token) -> AtomicResult<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("Expected {:?}, found {:?}", expected, token).into()),
+            None => Err(format!("Expected {:?} at the end of the expression", expected).into()),
+        }
+    }
+
+    /// Calls `parse` one level deeper, or fails if that exceeds [MAX_NESTING_DEPTH].
+    fn nested(&mut self, parse: fn(&mut Self) -> AtomicResult<Expr>) -> AtomicResult<Expr> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(format!(
+                "The expression is nested too deeply, the maximum is {} levels",
+                MAX_NESTING_DEPTH
+            )
+            .into());
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn expression(&mut self) -> AtomicResult<Expr> {
+        self.nested(Self::if_or)
+    }
+
+    fn if_or(&mut self) -> AtomicResult<Expr> {
+        if self.keyword("if") {
+            let condition = self.expression()?;
+            if !self.keyword("then") {
+                return Err("Expected `then` after the condition of `if`".into());
+            }
+            let consequence = self.expression()?;
+            return Ok(Expr::If(Box::new(condition), Box::new(consequence)));
+        }
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> AtomicResult<Expr> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> AtomicResult<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.nested(Self::not)?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> AtomicResult<Expr> {
+        if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let expr = self.expression()?;
+            self.expect(Token::Close)?;
+            return Ok(expr);
+        }
+        if self.keyword("exists") {
+            self.expect(Token::Open)?;
+            let Operand::Property(name) = self.operand()? else {
+                return Err("`exists` needs the shortname or URL of a Property".into());
+            };
+            self.expect(Token::Close)?;
+            return Ok(Expr::Exists(name));
+        }
+        let left = self.operand()?;
+        if let Some(Token::Compare(comparison)) = self.peek().cloned() {
+            self.position += 1;
+            return Ok(Expr::Compare(left, comparison, self.operand()?));
+        }
+        match left {
+            Operand::Property(_) | Operand::Boolean(_) => Ok(Expr::Operand(left)),
+            other => Err(format!("Expected a comparison after {:?}", other).into()),
+        }
+    }
+
+    fn operand(&mut self) -> AtomicResult<Operand> {
+        match self.next() {
+            Some(Token::Word(w)) if w == "true" => Ok(Operand::Boolean(true)),
+            Some(Token::Word(w)) if w == "false" => Ok(Operand::Boolean(false)),
+            Some(Token::Word(w)) if KEYWORDS.contains(&w.as_str()) => {
+                Err(format!("Unexpected `{}`", w).into())
+            }
+            Some(Token::Word(w)) | Some(Token::Url(w)) => Ok(Operand::Property(w)),
+            Some(Token::Text(text)) => Ok(Operand::Text(text)),
+            Some(Token::Number(number)) => Ok(Operand::Number(number)),
+            Some(other) => Err(format!("Unexpected {:?}", other).into()),
+            None => Err("Unexpected end of the expression".into()),
+        }
+    }
+}
+
+/// A Value of a Property, or a literal from the expression that still has to be converted.
+enum Resolved<'a> {
+    Value(&'a Value),
+    Literal(String),
+}
+
+/// Evaluates expressions for a Resource.
+struct Evaluator<'a> {
+    resource: &'a Resource,
+    /// Shortnames of the Properties of the Class, and their subjects
+    shortnames: &'a HashMap<String, String>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn property(&self, name: &str) -> AtomicResult<String> {
+        if is_url(name) {
+            return Ok(name.into());
+        }
+        self.shortnames.get(name).cloned().ok_or_else(|| {
+            format!(
+                "Unknown Property '{}'. Use the shortname of a Property that the Class requires or recommends, or a URL between < and >",
+                name
+            )
+            .into()
+        })
+    }
+
+    fn resolve(&self, operand: &Operand) -> AtomicResult<Option<Resolved<'a>>> {
+        Ok(match operand {
+            Operand::Property(name) => self
+                .resource
+                .get(&self.property(name)?)
+                .ok()
+                .map(Resolved::Value),
+            Operand::Text(text) | Operand::Number(text) => Some(Resolved::Literal(text.clone())),
+            Operand::Boolean(b) => Some(Resolved::Literal(b.to_string())),
+        })
+    }
+
+    /// Returns `None` if the outcome is unknown, because a value is missing.
+    fn eval(&self, expr: &Expr) -> AtomicResult<Option<bool>> {
+        Ok(match expr {
+            Expr::If(condition, consequence) => {
+                or(self.eval(condition)?.map(|c| !c), self.eval(consequence)?)
+            }
+            Expr::Or(a, b) => or(self.eval(a)?, self.eval(b)?),
+            Expr::And(a, b) => {
+                let (a, b) = (self.eval(a)?, self.eval(b)?);
+                or(a.map(|a| !a), b.map(|b| !b)).map(|either_false| !either_false)
+            }
+            Expr::Not(a) => self.eval(a)?.map(|a| !a),
+            Expr::Exists(name) => Some(self.resource.get(&self.property(name)?).is_ok()),
+            Expr::Operand(Operand::Boolean(b)) => Some(*b),
+            Expr::Operand(operand) => match self.resolve(operand)? {
+                Some(Resolved::Value(value)) => Some(value.to_bool()?),
+                _ => None,
+            },
+            Expr::Compare(a, comparison, b) => match (self.resolve(a)?, self.resolve(b)?) {
+                (Some(a), Some(b)) => Some(compare(a, *comparison, b)?),
+                _ => None,
+            },
+        })
+    }
+}
+
+/// Kleene's `or`: true if either is true, unknown if either is unknown.
+fn or(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+fn compare(a: Resolved, comparison: Comparison, b: Resolved) -> AtomicResult<bool> {
+    let (value, literal, comparison) = match (a, b) {
+        (Resolved::Value(a), Resolved::Value(b)) => return compare_values(a, comparison, b),
+        (Resolved::Value(value), Resolved::Literal(literal)) => (value, literal, comparison),
+        (Resolved::Literal(literal), Resolved::Value(value)) => (value, literal, comparison.flip()),
+        (Resolved::Literal(a), Resolved::Literal(b)) => {
+            let ordering = match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                _ => a.cmp(&b),
+            };
+            return Ok(comparison.matches(ordering));
+        }
+    };
+    if let Value::ResourceArray(_) = value {
+        return match comparison {
+            Comparison::Equal => Ok(value.contains_value(&Value::AtomicUrl(literal))),
+            Comparison::NotEqual => Ok(!value.contains_value(&Value::AtomicUrl(literal))),
+            _ => Err(format!("Can't use {:?} on a Resource Array", comparison).into()),
+        };
+    }
+    // Numbers are compared by their value, so `9.5` can be compared with an Integer
+    let datatype = match value {
+        Value::Integer(_) | Value::Float(_) | Value::Timestamp(_) => DataType::Float,
+        Value::Decimal(_) | Value::BigInt(_) => DataType::Decimal,
+        other => other.datatype(),
+    };
+    let literal = Value::new(&literal, &datatype).map_err(|e| {
+        format!(
+            "Can't compare '{}' with a value of datatype {}. {}",
+            literal,
+            value.datatype(),
+            e
+        )
+    })?;
+    compare_values(value, comparison, &literal)
+}
+
+fn compare_values(a: &Value, comparison: Comparison, b: &Value) -> AtomicResult<bool> {
+    match order(a, b) {
+        Some(ordering) => Ok(comparison.matches(ordering)),
+        None => match comparison {
+            Comparison::Equal => Ok(a.to_string() == b.to_string()),
+            Comparison::NotEqual => Ok(a.to_string() != b.to_string()),
+            _ => Err(format!(
+                "Can't compare values of datatypes {} and {} with {:?}",
+                a.datatype(),
+                b.datatype(),
+                comparison
+            )
+            .into()),
+        },
+    }
+}
+
+/// Orders two values, if they have a meaningful order.
+fn order(a: &Value, b: &Value) -> Option<Ordering> {
+    use Value::*;
+    let is_number = |v: &Value| {
+        matches!(
+            v,
+            Integer(_) | Float(_) | Timestamp(_) | Decimal(_) | BigInt(_)
+        )
+    };
+    match (a, b) {
+        // Compared exactly, other numbers are compared as floats
+        (Decimal(_) | BigInt(_), Decimal(_) | BigInt(_)) => {
+            Some(a.to_sortable_string().cmp(&b.to_sortable_string()))
+        }
+        (Integer(a) | Timestamp(a), Integer(b) | Timestamp(b)) => Some(a.cmp(b)),
+        _ if is_number(a) && is_number(b) => a.to_float().ok()?.partial_cmp(&b.to_float().ok()?),
+        (
+            ResourceArray(_) | NestedResource(_) | Resource(_) | GeoPoint(_) | LangString(_)
+            | Unsupported(_),
+            _,
+        ) => None,
+        _ if a.datatype() == b.datatype() => {
+            Some(a.to_sortable_string().cmp(&b.to_sortable_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Checks the [ValidationRule]s of the Classes on the Resource.
+/// Returns a [crate::errors::AtomicErrorType::ValidationError] that lists all rules that are not met.
+/// Rules that can't be evaluated, for example because they refer to unknown Properties, return a regular error.
+pub fn check_rules(
+    resource: &Resource,
+    classes: &[Class],
+    store: &impl Storelike,
+) -> AtomicResult<()> {
+    let mut violations = Vec::new();
+    for class in classes.iter().filter(|c| !c.rules.is_empty()) {
+        let mut shortnames = HashMap::new();
+        for prop in class.requires.iter().chain(class.recommends.iter()) {
+            let property = store
+                .get_resource_local(prop)
+                .and_then(crate::schema::Property::from_resource);
+            if let Ok(property) = property {
+                shortnames.insert(property.shortname, property.subject);
+            }
+        }
+        let evaluator = Evaluator {
+            resource,
+            shortnames: &shortnames,
+        };
+        for subject in &class.rules {
+            let rule = ValidationRule::from_resource(store.get_resource_local(subject)?)
+                .map_err(|e| format!("Validation Rule {} of {}: {}", subject, class.subject, e))?;
+            let expr = rule.parse()?;
+            let outcome = evaluator.eval(&expr).map_err(|e| {
+                format!(
+                    "Validation Rule '{}' of {} can't be checked: {}",
+                    rule.shortname, class.subject, e.message
+                )
+            })?;
+            if outcome == Some(false) {
+                let properties = expr
+                    .properties()
+                    .iter()
+                    .map(|name| evaluator.property(name))
+                    .collect::<AtomicResult<Vec<String>>>()?;
+                violations.push(RuleViolation {
+                    message: rule.description.unwrap_or(rule.expression),
+                    rule: rule.subject,
+                    shortname: rule.shortname,
+                    class: class.subject.clone(),
+                    properties,
+                });
+            }
+        }
+    }
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(AtomicError::validation(violations).set_subject(resource.get_subject()))
+}
+
+/// Checks whether the expression of a new or changed Validation Rule can be parsed.
+#[cfg(feature = "db")]
+pub(crate) fn before_apply_commit_rule(resource_new: &Resource) -> AtomicResult<()> {
+    ValidationRule::from_resource(resource_new.clone())?.parse()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_expressions() {
+        assert_eq!(
+            parse_expression("endDate >= startDate").unwrap(),
+            Expr::Compare(
+                Operand::Property("endDate".into()),
+                Comparison::GreaterOrEqual,
+                Operand::Property("startDate".into())
+            )
+        );
+        assert_eq!(
+            parse_expression(r#"if status = "done" then exists(completed-at)"#).unwrap(),
+            Expr::If(
+                Box::new(Expr::Compare(
+                    Operand::Property("status".into()),
+                    Comparison::Equal,
+                    Operand::Text("done".into())
+                )),
+                Box::new(Expr::Exists("completed-at".into()))
+            )
+        );
+        let expr =
+            parse_expression("not (a < -1.5 or <https://example.com/b> != true) and c").unwrap();
+        assert_eq!(expr.properties(), vec!["a", "https://example.com/b", "c"]);
+
+        for invalid in [
+            "",
+            "a >",
+            "a = 1 b",
+            "(a = 1",
+            "\"done\"",
+            "if a then",
+            "exists(\"a\")",
+            "a = \"open",
+            "a ! b",
+            "a = 1.2.3",
+            "and = 1",
+        ] {
+            parse_expression(invalid).expect_err(invalid);
+        }
+
+        // Deeply nested expressions are rejected instead of overflowing the stack
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        parse_expression(&nested(MAX_NESTING_DEPTH - 1)).unwrap();
+        let err = parse_expression(&nested(10_000)).unwrap_err();
+        assert!(matches!(err.error_type, AtomicErrorType::ParseError));
+        let err = parse_expression(&nested(MAX_NESTING_DEPTH + 1)).unwrap_err();
+        assert!(err.message.contains("nested too deeply"), "{}", err);
+        for repeated in ["not ", "if a then "] {
+            let expression = format!("{}a", repeated.repeat(MAX_NESTING_DEPTH + 1));
+            let err = parse_expression(&expression).unwrap_err();
+            assert!(err.message.contains("nested too deeply"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn evaluate_expressions() {
+        let mut resource = Resource::new("https://localhost/task".into());
+        resource.set_unsafe("https://localhost/start".into(), Value::Integer(10));
+        resource.set_unsafe("https://localhost/end".into(), Value::Float(12.5));
+        resource.set_unsafe(
+            "https://localhost/status".into(),
+            Value::String("done".into()),
+        );
+        resource.set_unsafe("https://localhost/done".into(), Value::Boolean(true));
+        resource.set_unsafe(
+            "https://localhost/tags".into(),
+            vec!["https://localhost/red"].into(),
+        );
+        let shortnames: HashMap<String, String> =
+            ["start", "end", "status", "done", "tags", "missing"]
+                .iter()
+                .map(|s| (s.to_string(), format!("https://localhost/{}", s)))
+                .collect();
+        let evaluator = Evaluator {
+            resource: &resource,
+            shortnames: &shortnames,
+        };
+        let eval = |expression: &str| evaluator.eval(&parse_expression(expression).unwrap());
+
+        assert_eq!(eval("end >= start").unwrap(), Some(true));
+        assert_eq!(eval("start > 9.5 and start < 100").unwrap(), Some(true));
+        assert_eq!(eval("-1 < start").unwrap(), Some(true));
+        assert_eq!(eval("status = \"done\" and done").unwrap(), Some(true));
+        assert_eq!(
+            eval("tags = \"https://localhost/red\"").unwrap(),
+            Some(true)
+        );
+        assert_eq!(eval("not done or start = 11").unwrap(), Some(false));
+        // Missing values make comparisons unknown, `exists` is always known
+        assert_eq!(eval("missing > start").unwrap(), None);
+        assert_eq!(eval("missing > start or done").unwrap(), Some(true));
+        assert_eq!(eval("missing > start and not done").unwrap(), Some(false));
+        assert_eq!(
+            eval("if status = \"done\" then exists(missing)").unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            eval("if status = \"open\" then exists(missing)").unwrap(),
+            Some(true)
+        );
+
+        eval("unknown = 1").unwrap_err();
+        eval("start = \"ten\"").unwrap_err();
+        eval("status").unwrap_err();
+        eval("tags > 1").unwrap_err();
+    }
+}
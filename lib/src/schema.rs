@@ -1,7 +1,10 @@
 //! Structs and models at the core of Atomic Schema (Class, Property, Datatype).
 
-use crate::{datatype::DataType, errors::AtomicResult, urls, Resource, Value};
+use crate::{
+    datatype::DataType, errors::AtomicResult, storelike::Query, urls, Resource, Storelike, Value,
+};
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Mutex};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Property {
@@ -18,6 +21,269 @@ pub struct Property {
     /// Restricts values to be only one of these Subjects.
     /// https://atomicdata.dev/properties/allowsOnly
     pub allows_only: Option<Vec<String>>,
+    /// Restricts the size, length and shape of values.
+    pub constraints: ValueConstraints,
+    /// The value that is set when a new instance of a Class that uses this Property is created.
+    pub default: Option<DefaultValue>,
+}
+
+/// How the value of a Property is filled in for new instances, see [Resource::set_defaults].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DefaultValue {
+    /// A fixed value.
+    /// https://atomicdata.dev/properties/defaultValue
+    Static(Value),
+    /// A value that is generated when the instance is created.
+    /// https://atomicdata.dev/properties/generator
+    Generated(Generator),
+}
+
+/// Generates values for new instances.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Generator {
+    /// The current time, for Timestamps and Integers.
+    Now,
+    /// The Agent that creates the instance.
+    Agent,
+    /// An Integer that is one higher for every new instance, starting after the highest existing value of the Property.
+    /// Numbers of instances that fail to be created are skipped.
+    Counter,
+    /// A random UUID (version 4).
+    Uuid,
+}
+
+impl std::str::FromStr for Generator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "now" => Ok(Generator::Now),
+            "agent" => Ok(Generator::Agent),
+            "counter" => Ok(Generator::Counter),
+            "uuid" => Ok(Generator::Uuid),
+            other => Err(format!(
+                "Unknown generator '{}'. Use one of now, agent, counter or uuid",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Generator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Generator::Now => "now",
+            Generator::Agent => "agent",
+            Generator::Counter => "counter",
+            Generator::Uuid => "uuid",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Constraints on the values of a Property. Numbers are limited by `min` and `max` (or `minFloat` and `maxFloat`),
+/// texts by `minLength` and `maxLength`, and the amount of items in a Resource Array by `min` and `max`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ValueConstraints {
+    /// The lowest number, or the smallest amount of items in a Resource Array.
+    /// https://atomicdata.dev/properties/min
+    pub min: Option<i64>,
+    /// The highest number, or the largest amount of items in a Resource Array.
+    /// https://atomicdata.dev/properties/max
+    pub max: Option<i64>,
+    /// The lowest number, for Floats and Integers.
+    /// https://atomicdata.dev/properties/minFloat
+    pub min_float: Option<f64>,
+    /// The highest number, for Floats and Integers.
+    /// https://atomicdata.dev/properties/maxFloat
+    pub max_float: Option<f64>,
+    /// The shortest text, in characters.
+    /// https://atomicdata.dev/properties/minLength
+    #[serde(default)]
+    pub min_length: Option<u64>,
+    /// The longest text, in characters.
+    /// https://atomicdata.dev/properties/maxLength
+    #[serde(default)]
+    pub max_length: Option<u64>,
+    /// A regular expression that text values have to match. Not anchored, so use `^` and `$` to match the whole value.
+    /// https://atomicdata.dev/properties/pattern
+    pub pattern: Option<String>,
+    /// Values have to be unique among the Resources in a Drive. Checked when applying Commits, see [Resource::check_unique_values].
+    /// https://atomicdata.dev/properties/uniqueInDrive
+    #[serde(default)]
+    pub unique_in_drive: bool,
+}
+
+/// Compiled `pattern` constraints. Properties are parsed again on every lookup, so the cache is shared.
+/// Also used for the patterns in JSON Schemas, see [crate::json_schema].
+static PATTERNS: Mutex<Option<HashMap<String, regex::Regex>>> = Mutex::new(None);
+
+/// Returns the compiled regex for a `pattern` constraint, compiling it only the first time.
+pub(crate) fn compiled_pattern(pattern: &str) -> Result<regex::Regex, String> {
+    let mut cache = PATTERNS.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if let Some(re) = cache.get(pattern) {
+        return Ok(re.clone());
+    }
+    let re =
+        regex::Regex::new(pattern).map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+    // Patterns come from (possibly remote) Properties, so keep the cache from growing without bounds
+    if cache.len() >= 1000 {
+        cache.clear();
+    }
+    cache.insert(pattern.into(), re.clone());
+    Ok(re)
+}
+
+impl ValueConstraints {
+    fn from_resource(resource: &Resource) -> AtomicResult<ValueConstraints> {
+        let int = |prop: &str| resource.get(prop).ok().map(|v| v.to_int()).transpose();
+        let float = |prop: &str| resource.get(prop).ok().map(|v| v.to_float()).transpose();
+        let length = |prop: &str| -> AtomicResult<Option<u64>> {
+            int(prop)?
+                .map(|l| {
+                    u64::try_from(l).map_err(|_| format!("{} can not be negative, got {}", prop, l))
+                })
+                .transpose()
+                .map_err(|e| e.into())
+        };
+        Ok(ValueConstraints {
+            min: int(urls::MIN)?,
+            max: int(urls::MAX)?,
+            min_float: float(urls::MIN_FLOAT)?,
+            max_float: float(urls::MAX_FLOAT)?,
+            min_length: length(urls::MIN_LENGTH)?,
+            max_length: length(urls::MAX_LENGTH)?,
+            pattern: resource.get(urls::PATTERN).ok().map(|v| v.to_string()),
+            unique_in_drive: match resource.get(urls::UNIQUE_IN_DRIVE) {
+                Ok(val) => val.to_bool()?,
+                Err(_) => false,
+            },
+        })
+    }
+
+    fn add_to_resource(&self, resource: &mut Resource) {
+        if let Some(min) = self.min {
+            resource.set_unsafe(urls::MIN.into(), Value::Integer(min));
+        }
+        if let Some(max) = self.max {
+            resource.set_unsafe(urls::MAX.into(), Value::Integer(max));
+        }
+        if let Some(min_float) = self.min_float {
+            resource.set_unsafe(urls::MIN_FLOAT.into(), Value::Float(min_float));
+        }
+        if let Some(max_float) = self.max_float {
+            resource.set_unsafe(urls::MAX_FLOAT.into(), Value::Float(max_float));
+        }
+        if let Some(min_length) = self.min_length {
+            resource.set_unsafe(urls::MIN_LENGTH.into(), Value::Integer(min_length as i64));
+        }
+        if let Some(max_length) = self.max_length {
+            resource.set_unsafe(urls::MAX_LENGTH.into(), Value::Integer(max_length as i64));
+        }
+        if let Some(pattern) = &self.pattern {
+            resource.set_unsafe(urls::PATTERN.into(), Value::String(pattern.clone()));
+        }
+        if self.unique_in_drive {
+            resource.set_unsafe(urls::UNIQUE_IN_DRIVE.into(), Value::Boolean(true));
+        }
+    }
+
+    fn check_range<T: PartialOrd + std::fmt::Display>(
+        value: T,
+        min: Option<T>,
+        max: Option<T>,
+        what: &str,
+    ) -> Result<(), String> {
+        if let Some(min) = min {
+            if value < min {
+                return Err(format!("{} is {}, but the minimum is {}", what, value, min));
+            }
+        }
+        if let Some(max) = max {
+            if value > max {
+                return Err(format!("{} is {}, but the maximum is {}", what, value, max));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares Decimals and BigInts to `min` and `max` without rounding them to a float.
+    fn check_exact_range(&self, value: &Value) -> Result<(), String> {
+        let sortable = |bound: i64| Value::BigInt(bound.to_string()).to_sortable_string();
+        let value_sortable = value.to_sortable_string();
+        if let Some(min) = self.min {
+            if value_sortable < sortable(min) {
+                return Err(format!(
+                    "The value is {}, but the minimum is {}",
+                    value, min
+                ));
+            }
+        }
+        if let Some(max) = self.max {
+            if value_sortable > sortable(max) {
+                return Err(format!(
+                    "The value is {}, but the maximum is {}",
+                    value, max
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the value against the constraints. Returns an explanation if it does not match.
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::Integer(int) => {
+                Self::check_range(*int, self.min, self.max, "The value")?;
+                Self::check_range(*int as f64, self.min_float, self.max_float, "The value")?;
+            }
+            Value::Float(float) => {
+                let min = self.min.map(|m| m as f64);
+                let max = self.max.map(|m| m as f64);
+                Self::check_range(*float, min, max, "The value")?;
+                Self::check_range(*float, self.min_float, self.max_float, "The value")?;
+            }
+            Value::Decimal(_) | Value::BigInt(_) => {
+                self.check_exact_range(value)?;
+                if self.min_float.is_some() || self.max_float.is_some() {
+                    let number = value.to_float().map_err(|e| e.to_string())?;
+                    Self::check_range(number, self.min_float, self.max_float, "The value")?;
+                }
+            }
+            Value::String(text)
+            | Value::Markdown(text)
+            | Value::Slug(text)
+            | Value::Date(text)
+            | Value::Duration(text)
+            | Value::Time(text)
+            | Value::DateTime(text) => {
+                let length = text.chars().count() as u64;
+                Self::check_range(length, self.min_length, self.max_length, "The length")?;
+                if let Some(pattern) = &self.pattern {
+                    if !compiled_pattern(pattern)?.is_match(text) {
+                        return Err(format!("'{}' does not match the pattern {}", text, pattern));
+                    }
+                }
+            }
+            Value::ResourceArray(items) => {
+                Self::check_range(
+                    items.len() as i64,
+                    self.min,
+                    self.max,
+                    "The amount of items",
+                )?;
+            }
+            Value::LangString(texts) => {
+                for (language, text) in texts.iter() {
+                    self.check(&Value::String(text.into()))
+                        .map_err(|e| format!("{} (language '{}')", e, language))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
 }
 
 impl PartialEq for Property {
@@ -41,12 +307,27 @@ impl Property {
             Err(_) => None,
         };
 
+        let constraints = ValueConstraints::from_resource(&resource)?;
+
+        let default = if let Ok(generator) = resource.get(urls::GENERATOR) {
+            Some(DefaultValue::Generated(generator.to_string().parse()?))
+        } else if let Ok(value) = resource.get(urls::DEFAULT_VALUE) {
+            Some(DefaultValue::Static(Value::new(
+                &value.to_string(),
+                &data_type,
+            )?))
+        } else {
+            None
+        };
+
         Ok(Property {
             class_type,
             data_type,
             shortname,
             description,
             allows_only,
+            constraints,
+            default,
             subject: resource.get_subject().into(),
         })
     }
@@ -73,15 +354,97 @@ impl Property {
                 Value::AtomicUrl(classtype.clone()),
             );
         }
+        self.constraints.add_to_resource(&mut resource);
+        match &self.default {
+            Some(DefaultValue::Static(value)) => {
+                resource.set_unsafe(urls::DEFAULT_VALUE.into(), Value::String(value.to_string()));
+            }
+            Some(DefaultValue::Generated(generator)) => {
+                resource.set_unsafe(urls::GENERATOR.into(), Value::String(generator.to_string()));
+            }
+            None => {}
+        }
 
         resource
     }
+
+    /// Returns the default value for a new instance, if the Property has one.
+    /// The `agent` is the subject of the Agent that creates the instance, used by [Generator::Agent].
+    pub fn default_value(
+        &self,
+        store: &impl Storelike,
+        agent: Option<&str>,
+    ) -> AtomicResult<Option<Value>> {
+        let generator = match &self.default {
+            None => return Ok(None),
+            Some(DefaultValue::Static(value)) => return Ok(Some(value.clone())),
+            Some(DefaultValue::Generated(generator)) => generator,
+        };
+        let value = match (generator, &self.data_type) {
+            (Generator::Now, DataType::Timestamp) => Value::Timestamp(crate::utils::now()),
+            (Generator::Now, DataType::Integer) => Value::Integer(crate::utils::now()),
+            (Generator::Agent, DataType::AtomicUrl) => match agent {
+                Some(agent) => Value::AtomicUrl(agent.into()),
+                None => return Ok(None),
+            },
+            (Generator::Agent, DataType::ResourceArray) => match agent {
+                Some(agent) => Value::ResourceArray(vec![agent.into()]),
+                None => return Ok(None),
+            },
+            (Generator::Counter, DataType::Integer) => {
+                // Only the first value needs a Query, after that the counter is used
+                let highest = || -> AtomicResult<i64> {
+                    let mut q = Query::new();
+                    q.property = Some(self.subject.clone());
+                    Ok(store
+                        .query(&q)?
+                        .resources
+                        .iter()
+                        .filter_map(|r| r.get(&self.subject).ok()?.to_int().ok())
+                        .max()
+                        .unwrap_or(0))
+                };
+                Value::Integer(store.next_counter(&self.subject, &highest)?)
+            }
+            (Generator::Uuid, DataType::String | DataType::Slug) => {
+                Value::new(&crate::utils::random_uuid(), &self.data_type)?
+            }
+            (generator, data_type) => {
+                return Err(format!(
+                    "Generator '{}' of Property {} does not support Datatype {}",
+                    generator, self.subject, data_type
+                )
+                .into())
+            }
+        };
+        Ok(Some(value))
+    }
+
+    /// Checks whether the value matches the [ValueConstraints] of this Property.
+    pub fn check_constraints(&self, value: &Value) -> AtomicResult<()> {
+        self.constraints.check(value).map_err(|e| {
+            format!(
+                "Value '{}' is not valid for Property '{}': {}",
+                value, self.subject, e
+            )
+            .into()
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Class {
     pub requires: Vec<String>,
     pub recommends: Vec<String>,
+    /// The parent Classes, of which this Class inherits the `requires` and `recommends`.
+    /// https://atomicdata.dev/properties/extends
+    pub extends: Vec<String>,
+    /// Properties of which the values have to be unique among the instances of this Class.
+    /// https://atomicdata.dev/properties/unique
+    pub unique: Vec<String>,
+    /// Validation Rules that the instances of this Class have to follow, see [crate::rules].
+    /// https://atomicdata.dev/properties/rules
+    pub rules: Vec<String>,
     pub shortname: String,
     pub description: String,
     /// URL
@@ -105,12 +468,30 @@ impl Class {
             }
         }
 
+        let mut extends = Vec::new();
+        if let Ok(parents) = resource.get(urls::EXTENDS) {
+            extends = parents.to_subjects(None)?;
+        }
+
+        let mut unique = Vec::new();
+        if let Ok(props) = resource.get(urls::UNIQUE) {
+            unique = props.to_subjects(None)?;
+        }
+
+        let mut rules = Vec::new();
+        if let Ok(val) = resource.get(urls::RULES) {
+            rules = val.to_subjects(None)?;
+        }
+
         let shortname = resource.get(urls::SHORTNAME)?.to_string();
         let description = resource.get(urls::DESCRIPTION)?.to_string();
 
         Ok(Class {
             requires,
             recommends,
+            extends,
+            unique,
+            rules,
             shortname,
             subject: resource.get_subject().into(),
             description,
@@ -132,12 +513,85 @@ impl Class {
         if !self.requires.is_empty() {
             resource.set_unsafe(urls::REQUIRES.into(), Value::from(self.requires.clone()));
         }
-        if !self.requires.is_empty() {
+        if !self.recommends.is_empty() {
             resource.set_unsafe(
                 urls::RECOMMENDS.into(),
                 Value::from(self.recommends.clone()),
             );
         }
+        if !self.extends.is_empty() {
+            resource.set_unsafe(urls::EXTENDS.into(), Value::from(self.extends.clone()));
+        }
+        if !self.unique.is_empty() {
+            resource.set_unsafe(urls::UNIQUE.into(), Value::from(self.unique.clone()));
+        }
+        if !self.rules.is_empty() {
+            resource.set_unsafe(urls::RULES.into(), Value::from(self.rules.clone()));
+        }
         resource
     }
+
+    /// Adds the `requires`, `recommends`, `unique` and `rules` of all the Classes this Class (indirectly) extends.
+    /// A Property that is required by any of the Classes is required, and is not listed as recommended.
+    /// `extends` is replaced by all the ancestors of the Class, closest first.
+    pub fn with_inherited(mut self, store: &impl Storelike) -> AtomicResult<Class> {
+        let mut ancestors: Vec<String> = Vec::new();
+        let mut queue: std::collections::VecDeque<String> = self.extends.drain(..).collect();
+        while let Some(subject) = queue.pop_front() {
+            // Cycles are ignored
+            if subject == self.subject || ancestors.contains(&subject) {
+                continue;
+            }
+            let parent = store
+                .get_class(&subject)
+                .map_err(|e| format!("Class {} extends {}. {}", self.subject, subject, e))?;
+            for prop in parent.requires {
+                if !self.requires.contains(&prop) {
+                    self.requires.push(prop);
+                }
+            }
+            for prop in parent.recommends {
+                if !self.recommends.contains(&prop) {
+                    self.recommends.push(prop);
+                }
+            }
+            for prop in parent.unique {
+                if !self.unique.contains(&prop) {
+                    self.unique.push(prop);
+                }
+            }
+            for rule in parent.rules {
+                if !self.rules.contains(&rule) {
+                    self.rules.push(rule);
+                }
+            }
+            queue.extend(parent.extends);
+            ancestors.push(subject);
+        }
+        let requires = &self.requires;
+        self.recommends.retain(|prop| !requires.contains(prop));
+        self.extends = ancestors;
+        Ok(self)
+    }
+}
+
+/// Returns the subject of the Class, followed by the subjects of all Classes that (indirectly) extend it.
+/// Cycles are ignored.
+pub fn class_and_subclasses(store: &impl Storelike, class: &str) -> AtomicResult<Vec<String>> {
+    let mut classes = vec![class.to_string()];
+    let mut i = 0;
+    while i < classes.len() {
+        let mut q = Query::new();
+        q.property = Some(urls::EXTENDS.into());
+        q.value = Some(Value::AtomicUrl(classes[i].clone()));
+        q.include_external = true;
+        q.include_nested = false;
+        for subclass in store.query(&q)?.subjects {
+            if !classes.contains(&subclass) {
+                classes.push(subclass);
+            }
+        }
+        i += 1;
+    }
+    Ok(classes)
 }
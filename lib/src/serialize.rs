@@ -1,9 +1,17 @@
-//! Serialization / formatting / encoding (JSON, RDF, N-Triples)
+//! Serialization / formatting / encoding (JSON, binary JSON-AD, RDF: N-Triples, Turtle, TriG, RDF/XML)
+
+pub(crate) mod cbor;
+#[cfg(feature = "rdf")]
+mod rdf;
 
 use serde_json::Map;
 use serde_json::Value as SerdeValue;
 use tracing::instrument;
 
+pub use cbor::resources_to_json_ad_cbor;
+#[cfg(feature = "rdf")]
+pub use rdf::{atoms_to_rdf_xml, atoms_to_trig, atoms_to_turtle, shacl, write_turtle, Prefixes};
+
 use crate::{
     datatype::DataType, errors::AtomicResult, resources::PropVals, Resource, Storelike, Value,
 };
@@ -21,16 +29,37 @@ pub fn resources_to_json_ad(resources: &[Resource]) -> AtomicResult<String> {
     serde_json::to_string_pretty(&serde_array).map_err(|_| "Could not serialize to JSON-AD".into())
 }
 
+/// Writes a single Resource as one line of Newline Delimited JSON-AD (NDJSON).
+/// Use this for streaming large amounts of Resources, as it does not require holding all of them in memory.
+/// http://ndjson.org/
+pub fn resource_to_ndjson_line(
+    resource: &Resource,
+    writer: &mut impl std::io::Write,
+) -> AtomicResult<()> {
+    let obj = propvals_to_json_ad_map(
+        resource.get_propvals(),
+        Some(resource.get_subject().clone()),
+    )?;
+    serde_json::to_writer(&mut *writer, &obj)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
 /// Converts an Atomic Value to a Serde Value.
 // TODO: Accept JSON-LD / JSON as options
 // https://github.com/atomicdata-dev/atomic-server/issues/315
-fn val_to_serde(value: Value) -> AtomicResult<SerdeValue> {
+pub(crate) fn val_to_serde(value: Value) -> AtomicResult<SerdeValue> {
     let json_val: SerdeValue = match value {
         Value::AtomicUrl(val) => SerdeValue::String(val),
         Value::Date(val) => SerdeValue::String(val),
         // TODO: Handle big numbers
         Value::Integer(val) => serde_json::from_str(&val.to_string()).unwrap_or_default(),
         Value::Float(val) => serde_json::from_str(&val.to_string()).unwrap_or_default(),
+        // JSON numbers lose precision in many parsers, so these are strings
+        Value::Decimal(val) | Value::BigInt(val) => SerdeValue::String(val),
+        Value::GeoPoint(point) => point.to_geo_json(),
+        Value::LangString(texts) => texts.to_json(),
+        Value::Duration(val) | Value::Time(val) | Value::DateTime(val) => SerdeValue::String(val),
         Value::Markdown(val) => SerdeValue::String(val),
         Value::ResourceArray(val) => {
             let mut vec: Vec<SerdeValue> = Vec::new();
@@ -138,6 +167,30 @@ pub fn propvals_to_json_ld(
                     );
                     obj.into()
                 }
+                DataType::Decimal
+                | DataType::BigInt
+                | DataType::Duration
+                | DataType::Time
+                | DataType::DateTime => {
+                    let mut obj = Map::new();
+                    obj.insert("@id".into(), prop_url.as_str().into());
+                    obj.insert("@type".into(), value.datatype().rdf_datatype().into());
+                    obj.into()
+                }
+                DataType::GeoPoint => {
+                    let mut obj = Map::new();
+                    obj.insert("@id".into(), prop_url.as_str().into());
+                    // GeoJSON objects are JSON literals, not nodes
+                    obj.insert("@type".into(), "@json".into());
+                    obj.into()
+                }
+                DataType::LangString => {
+                    let mut obj = Map::new();
+                    obj.insert("@id".into(), prop_url.as_str().into());
+                    // Language maps use the keys as language tags
+                    obj.insert("@container".into(), "@language".into());
+                    obj.into()
+                }
                 DataType::Markdown => prop_url.as_str().into(),
                 DataType::ResourceArray => {
                     let mut obj = Map::new();
@@ -186,8 +239,19 @@ pub fn atoms_to_ntriples(atoms: Vec<crate::Atom>, store: &impl Storelike) -> Ato
             iri: &atom.property,
         };
         let datatype = store.get_property(&atom.property)?.data_type;
+        // Every language becomes a separate triple
+        if let Value::LangString(texts) = &atom.value {
+            for (language, value) in texts.iter() {
+                formatter.format(&Triple {
+                    subject,
+                    predicate,
+                    object: Literal::LanguageTaggedString { value, language }.into(),
+                })?
+            }
+            continue;
+        }
         let value = &atom.value.to_string();
-        let datatype_url = datatype.to_string();
+        let datatype_url = datatype.rdf_datatype();
         let object: Term = match &datatype {
             DataType::AtomicUrl => NamedNode { iri: value }.into(),
             // Maybe these should be converted to RDF collections / lists?
@@ -211,50 +275,69 @@ pub fn atoms_to_ntriples(atoms: Vec<crate::Atom>, store: &impl Storelike) -> Ato
 }
 
 #[cfg(feature = "rdf")]
-/// Serializes Atoms to Ntriples (which is also valid Turtle / Notation3).
-pub fn atoms_to_turtle(atoms: Vec<crate::Atom>, store: &impl Storelike) -> AtomicResult<String> {
-    use rio_api::formatter::TriplesFormatter;
-    use rio_api::model::{Literal, NamedNode, Term, Triple};
-    use rio_turtle::TurtleFormatter;
-
-    let mut formatter = TurtleFormatter::new(Vec::default());
+/// Serializes Atoms to one of the RDF formats.
+pub fn atoms_to_rdf(
+    atoms: Vec<crate::Atom>,
+    format: &Format,
+    store: &impl Storelike,
+) -> AtomicResult<String> {
+    match format {
+        Format::NTriples => atoms_to_ntriples(atoms, store),
+        Format::Turtle => atoms_to_turtle(atoms, store),
+        Format::TriG => atoms_to_trig(atoms, store),
+        Format::RdfXml => atoms_to_rdf_xml(atoms, store),
+        Format::Json | Format::JsonAd | Format::JsonAdCbor | Format::JsonLd | Format::Pretty => {
+            Err("Not an RDF serialization format".into())
+        }
+    }
+}
 
-    for atom in atoms {
-        let subject = NamedNode { iri: &atom.subject }.into();
-        let predicate = NamedNode {
-            iri: &atom.property,
-        };
-        let datatype = store.get_property(&atom.property)?.data_type;
-        let value = &atom.value.to_string();
-        let datatype_url = datatype.to_string();
-        let object: Term = match &datatype {
-            DataType::AtomicUrl => NamedNode { iri: value }.into(),
-            // Maybe these should be converted to RDF collections / lists?
-            // DataType::ResourceArray => {}
-            DataType::String => Literal::Simple { value }.into(),
-            _dt => Literal::Typed {
-                value,
-                datatype: NamedNode { iri: &datatype_url },
+#[cfg(feature = "rdf")]
+/// Writes Resources to one of the RDF formats, and returns the amount of Resources.
+/// N-Triples, Turtle and TriG are written one Resource at a time, so large stores don't have to fit in memory.
+/// RDF/XML declares all namespaces in its root element, so it is built in memory first.
+pub fn write_rdf(
+    resources: impl Iterator<Item = Resource>,
+    format: &Format,
+    store: &impl Storelike,
+    writer: &mut impl std::io::Write,
+) -> AtomicResult<usize> {
+    match format {
+        Format::NTriples => {
+            let mut count = 0;
+            for resource in resources {
+                writer.write_all(atoms_to_ntriples(resource.to_atoms(), store)?.as_bytes())?;
+                count += 1;
             }
-            .into(),
-        };
-
-        formatter.format(&Triple {
-            subject,
-            predicate,
-            object,
-        })?
+            writer.flush()?;
+            Ok(count)
+        }
+        Format::Turtle => write_turtle(resources, store, false, writer),
+        Format::TriG => write_turtle(resources, store, true, writer),
+        Format::RdfXml => {
+            let resources: Vec<Resource> = resources.collect();
+            let atoms = resources.iter().flat_map(|r| r.to_atoms()).collect();
+            writer.write_all(atoms_to_rdf_xml(atoms, store)?.as_bytes())?;
+            writer.flush()?;
+            Ok(resources.len())
+        }
+        Format::Json | Format::JsonAd | Format::JsonAdCbor | Format::JsonLd | Format::Pretty => {
+            Err("Not an RDF serialization format".into())
+        }
     }
-    let out = String::from_utf8(formatter.finish()?)?;
-    Ok(out)
 }
 
 /// Should list all the supported serialization formats
 pub enum Format {
     Json,
     JsonAd,
+    /// Binary JSON-AD, see [cbor]
+    JsonAdCbor,
     JsonLd,
     NTriples,
+    Turtle,
+    TriG,
+    RdfXml,
     Pretty,
 }
 
@@ -0,0 +1,150 @@
+//! Binary JSON-AD, encoded as [CBOR](https://www.rfc-editor.org/rfc/rfc8949).
+//! The structure is the same as JSON-AD, but the payload is wrapped in a [stringref](http://cbor.schmorp.de/stringref) namespace (tag 256).
+//! Strings that occur more than once, such as Property URLs, are written only once and referred to by their index (tag 25) afterwards.
+//! See [crate::parse::parse_json_ad_cbor] for the parser.
+
+use std::collections::HashMap;
+
+use ciborium::value::{Integer, Value as CborValue};
+use serde_json::Value as SerdeValue;
+
+use crate::{errors::AtomicResult, Resource};
+
+/// Marks a value in which strings can be referenced.
+pub(crate) const STRINGREF_NAMESPACE_TAG: u64 = 256;
+/// Refers to a string that appeared earlier in the namespace.
+pub(crate) const STRINGREF_TAG: u64 = 25;
+
+/// Strings shorter than this are not added to the table, because a reference would not be smaller.
+/// The minimum length depends on the amount of strings that are already in the table.
+pub(crate) fn stringref_min_length(table_len: usize) -> usize {
+    match table_len {
+        0..=23 => 3,
+        24..=255 => 4,
+        256..=65535 => 5,
+        65536..=4294967295 => 7,
+        _ => 11,
+    }
+}
+
+/// Encodes a JSON-AD value, with repeated strings replaced by references.
+pub(crate) fn json_to_cbor_bytes(value: SerdeValue) -> AtomicResult<Vec<u8>> {
+    let mut table = HashMap::new();
+    let cbor = CborValue::Tag(
+        STRINGREF_NAMESPACE_TAG,
+        Box::new(json_to_cbor(value, &mut table)),
+    );
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(&cbor, &mut bytes)
+        .map_err(|e| format!("Could not serialize to binary JSON-AD: {}", e))?;
+    Ok(bytes)
+}
+
+/// Converts the value in the order in which it is written, because the decoder builds its table in that order too.
+fn json_to_cbor(value: SerdeValue, table: &mut HashMap<String, u64>) -> CborValue {
+    match value {
+        SerdeValue::Null => CborValue::Null,
+        SerdeValue::Bool(b) => CborValue::Bool(b),
+        SerdeValue::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                CborValue::Integer(i.into())
+            } else if let Some(u) = n.as_u64() {
+                CborValue::Integer(u.into())
+            } else {
+                CborValue::Float(n.as_f64().unwrap_or_default())
+            }
+        }
+        SerdeValue::String(s) => string_to_cbor(s, table),
+        SerdeValue::Array(items) => {
+            CborValue::Array(items.into_iter().map(|v| json_to_cbor(v, table)).collect())
+        }
+        SerdeValue::Object(map) => CborValue::Map(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let key = string_to_cbor(k, table);
+                    (key, json_to_cbor(v, table))
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn string_to_cbor(s: String, table: &mut HashMap<String, u64>) -> CborValue {
+    if let Some(index) = table.get(&s) {
+        return CborValue::Tag(
+            STRINGREF_TAG,
+            Box::new(CborValue::Integer(Integer::from(*index))),
+        );
+    }
+    if s.len() >= stringref_min_length(table.len()) {
+        table.insert(s.clone(), table.len() as u64);
+    }
+    CborValue::Text(s)
+}
+
+/// Serializes a vector of Resources to binary JSON-AD.
+pub fn resources_to_json_ad_cbor(resources: &[Resource]) -> AtomicResult<Vec<u8>> {
+    let mut vec: Vec<SerdeValue> = Vec::new();
+    for r in resources {
+        vec.push(super::propvals_to_json_ad_map(
+            r.get_propvals(),
+            Some(r.get_subject().clone()),
+        )?)
+    }
+    json_to_cbor_bytes(SerdeValue::Array(vec))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse::ParseOpts, urls, Storelike};
+
+    #[test]
+    fn cbor_roundtrip() {
+        let store = crate::Store::init().unwrap();
+        store.populate().unwrap();
+        let resources = vec![
+            store.get_resource(urls::DESCRIPTION).unwrap(),
+            store.get_resource(urls::SHORTNAME).unwrap(),
+            store.get_resource(urls::CLASS).unwrap(),
+        ];
+        let bytes = resources_to_json_ad_cbor(&resources).unwrap();
+        let json: Vec<SerdeValue> = resources
+            .iter()
+            .map(|r| serde_json::from_str(&r.to_json_ad().unwrap()).unwrap())
+            .collect();
+        let json = serde_json::to_string(&json).unwrap();
+        assert!(
+            bytes.len() * 4 < json.len() * 3,
+            "Repeated URLs should be interned: {} vs {} bytes",
+            bytes.len(),
+            json.len()
+        );
+
+        let opts = ParseOpts {
+            save: crate::parse::SaveOpts::DontSave,
+            ..Default::default()
+        };
+        let parsed = crate::parse::parse_json_ad_cbor(&bytes, &store, &opts).unwrap();
+        assert_eq!(parsed.len(), 3);
+        for (original, parsed) in resources.iter().zip(parsed.iter()) {
+            assert_eq!(original.to_json_ad().unwrap(), parsed.to_json_ad().unwrap());
+        }
+    }
+
+    #[test]
+    fn short_strings_are_not_interned() {
+        let value = serde_json::json!(["ab", "ab", "abc", "abc"]);
+        let bytes = json_to_cbor_bytes(value).unwrap();
+        let cbor: CborValue = ciborium::de::from_reader(&bytes[..]).unwrap();
+        let CborValue::Tag(STRINGREF_NAMESPACE_TAG, inner) = cbor else {
+            panic!("Not a stringref namespace")
+        };
+        let items = inner.as_array().unwrap();
+        assert_eq!(items[1], CborValue::Text("ab".into()));
+        assert_eq!(
+            items[3],
+            CborValue::Tag(STRINGREF_TAG, Box::new(CborValue::Integer(0.into())))
+        );
+    }
+}
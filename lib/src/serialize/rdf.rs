@@ -0,0 +1,757 @@
+//! Serializes Atoms to the RDF formats Turtle, TriG and RDF/XML.
+//! Turtle and TriG can also be written one Resource at a time, see [write_turtle].
+//! Resource Arrays become RDF Lists and Nested Resources become blank nodes.
+//! IRIs are shortened using [Prefixes], which are derived from the Ontologies in the store.
+
+pub mod shacl;
+
+use std::collections::HashMap;
+
+use crate::{
+    errors::AtomicResult, resources::PropVals, storelike::Query, urls, values::SubResource, Atom,
+    Resource, Storelike, Value,
+};
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+/// Vocabularies that always have a prefix.
+const COMMON_PREFIXES: [(&str, &str); 7] = [
+    ("rdf", RDF),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("atomic", "https://atomicdata.dev/properties/"),
+    ("atomic-classes", "https://atomicdata.dev/classes/"),
+    ("atomic-datatypes", "https://atomicdata.dev/datatypes/"),
+];
+
+/// Maps namespaces to prefixes, e.g. `xsd` to `http://www.w3.org/2001/XMLSchema#`.
+#[derive(Debug, Clone)]
+pub struct Prefixes {
+    /// Pairs of prefix and namespace
+    prefixes: Vec<(String, String)>,
+}
+
+impl Prefixes {
+    /// The prefixes of common RDF vocabularies and the Atomic Data core.
+    pub fn common() -> Prefixes {
+        Prefixes {
+            prefixes: COMMON_PREFIXES
+                .iter()
+                .map(|(prefix, namespace)| (prefix.to_string(), namespace.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Adds a prefix for every Ontology in the store, named after its shortname.
+    /// The namespace is derived from the Classes, Properties and instances of the Ontology.
+    /// If these have different namespaces, the last path segment of the namespace is appended to the shortname.
+    pub fn from_store(store: &impl Storelike) -> Prefixes {
+        let mut prefixes = Prefixes::common();
+        let ontologies = match store.query(&Query::new_class(urls::ONTOLOGY)) {
+            Ok(result) => result.subjects,
+            Err(e) => {
+                tracing::warn!("Could not find ontologies for RDF prefixes: {}", e);
+                return prefixes;
+            }
+        };
+        for subject in ontologies {
+            let Ok(ontology) = store.get_resource(&subject) else {
+                continue;
+            };
+            let Ok(shortname) = ontology.get(urls::SHORTNAME).map(|s| s.to_string()) else {
+                continue;
+            };
+            let mut namespaces: Vec<String> = Vec::new();
+            for property in [urls::CLASSES, urls::PROPERTIES, urls::INSTANCES] {
+                let Ok(items) = ontology.get(property).and_then(|v| v.to_subjects(None)) else {
+                    continue;
+                };
+                for item in items {
+                    let namespace = namespace_of(&item).to_string();
+                    if !namespace.is_empty() && !namespaces.contains(&namespace) {
+                        namespaces.push(namespace);
+                    }
+                }
+            }
+            let single = namespaces.len() == 1;
+            for namespace in namespaces {
+                let prefix = if single {
+                    shortname.clone()
+                } else {
+                    let segment = namespace
+                        .trim_end_matches(['/', '#'])
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or_default();
+                    format!("{}-{}", shortname, segment)
+                };
+                prefixes.add(&prefix, &namespace);
+            }
+        }
+        prefixes
+    }
+
+    /// Adds a prefix, unless the prefix or the namespace is already used, or the prefix is invalid.
+    /// Returns whether it was added.
+    pub fn add(&mut self, prefix: &str, namespace: &str) -> bool {
+        if !is_prefix(prefix)
+            || self
+                .prefixes
+                .iter()
+                .any(|(p, ns)| p == prefix || ns == namespace)
+        {
+            return false;
+        }
+        self.prefixes.push((prefix.into(), namespace.into()));
+        true
+    }
+
+    /// Splits the IRI in a prefix and a local name, using the longest matching namespace.
+    fn shorten<'a>(&'a self, iri: &'a str) -> Option<(&'a str, &'a str)> {
+        self.prefixes
+            .iter()
+            .filter_map(|(prefix, namespace)| {
+                iri.strip_prefix(namespace.as_str())
+                    .filter(|local| is_local_name(local))
+                    .map(|local| (prefix.as_str(), namespace.len(), local))
+            })
+            .max_by_key(|(_, len, _)| *len)
+            .map(|(prefix, _, local)| (prefix, local))
+    }
+
+    fn namespace(&self, prefix: &str) -> Option<&str> {
+        self.prefixes
+            .iter()
+            .find(|(p, _)| p == prefix)
+            .map(|(_, ns)| ns.as_str())
+    }
+}
+
+/// Everything up to and including the last `#` or `/`.
+fn namespace_of(iri: &str) -> &str {
+    match iri.rfind(['#', '/']) {
+        Some(i) => &iri[..=i],
+        None => "",
+    }
+}
+
+/// Valid as a local name in both Turtle and XML.
+fn is_local_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn is_prefix(prefix: &str) -> bool {
+    prefix.starts_with(|c: char| c.is_ascii_alphabetic()) && is_local_name(prefix)
+}
+
+/// An RDF object.
+enum Term {
+    Iri(String),
+    Literal {
+        value: String,
+        datatype: Option<String>,
+    },
+    LangLiteral {
+        value: String,
+        language: String,
+    },
+    List(Vec<Term>),
+    Blank(Vec<(String, Term)>),
+}
+
+/// All statements about a single subject, sorted by predicate.
+struct Description {
+    subject: String,
+    statements: Vec<(String, Term)>,
+}
+
+/// Groups the Atoms by subject, in the order in which the subjects are found.
+fn atoms_to_descriptions(atoms: Vec<Atom>) -> Vec<Description> {
+    let mut descriptions: Vec<Description> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for atom in atoms {
+        let statements = value_to_terms(&atom.value)
+            .into_iter()
+            .map(|term| (atom.property.clone(), term));
+        match index.get(&atom.subject) {
+            Some(i) => descriptions[*i].statements.extend(statements),
+            None => {
+                index.insert(atom.subject.clone(), descriptions.len());
+                descriptions.push(Description {
+                    subject: atom.subject,
+                    statements: statements.collect(),
+                })
+            }
+        }
+    }
+    for description in descriptions.iter_mut() {
+        description.statements.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    descriptions
+}
+
+fn propvals_to_statements(propvals: &PropVals) -> Vec<(String, Term)> {
+    let mut statements: Vec<(String, Term)> = propvals
+        .iter()
+        .flat_map(|(property, value)| {
+            value_to_terms(value)
+                .into_iter()
+                .map(|term| (property.clone(), term))
+        })
+        .collect();
+    statements.sort_by(|a, b| a.0.cmp(&b.0));
+    statements
+}
+
+fn sub_resource_to_term(sub_resource: &SubResource) -> Term {
+    match sub_resource {
+        SubResource::Subject(subject) => Term::Iri(subject.clone()),
+        SubResource::Resource(resource) => Term::Iri(resource.get_subject().clone()),
+        SubResource::Nested(propvals) => Term::Blank(propvals_to_statements(propvals)),
+    }
+}
+
+/// Language-tagged strings become a literal for every language, other values a single term.
+fn value_to_terms(value: &Value) -> Vec<Term> {
+    match value {
+        Value::LangString(texts) => texts
+            .iter()
+            .map(|(language, text)| Term::LangLiteral {
+                value: text.into(),
+                language: language.into(),
+            })
+            .collect(),
+        other => vec![value_to_term(other)],
+    }
+}
+
+fn value_to_term(value: &Value) -> Term {
+    match value {
+        Value::AtomicUrl(url) => Term::Iri(url.clone()),
+        Value::ResourceArray(items) => Term::List(items.iter().map(sub_resource_to_term).collect()),
+        Value::NestedResource(sub_resource) => sub_resource_to_term(sub_resource),
+        Value::Resource(resource) => Term::Iri(resource.get_subject().clone()),
+        Value::String(string) => Term::Literal {
+            value: string.clone(),
+            datatype: None,
+        },
+        other => Term::Literal {
+            value: other.to_string(),
+            datatype: Some(other.datatype().rdf_datatype()),
+        },
+    }
+}
+
+/// Writes a string literal with Turtle escapes, including the quotes.
+fn quote_literal(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Writes Turtle and TriG. Keeps track of the prefixes that are used.
+struct TurtleWriter<'a> {
+    prefixes: &'a Prefixes,
+    used: Vec<&'a str>,
+}
+
+impl<'a> TurtleWriter<'a> {
+    fn iri(&mut self, iri: &'a str) -> String {
+        if let Some((prefix, local)) = self.prefixes.shorten(iri) {
+            if !self.used.contains(&prefix) {
+                self.used.push(prefix);
+            }
+            return format!("{}:{}", prefix, local);
+        }
+        let mut out = String::from("<");
+        for c in iri.chars() {
+            match c {
+                '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' | '\u{0}'..=' ' => {
+                    out.push_str(&format!("\\u{:04X}", c as u32))
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('>');
+        out
+    }
+
+    fn term(&mut self, term: &'a Term, indent: usize) -> String {
+        match term {
+            Term::Iri(iri) => self.iri(iri),
+            Term::Literal { value, datatype } => {
+                let mut out = quote_literal(value);
+                if let Some(datatype) = datatype.as_deref().filter(|dt| *dt != XSD_STRING) {
+                    out.push_str("^^");
+                    out.push_str(&self.iri(datatype));
+                }
+                out
+            }
+            Term::LangLiteral { value, language } => {
+                format!("{}@{}", quote_literal(value), language)
+            }
+            Term::List(items) => {
+                let items: Vec<String> = items.iter().map(|i| self.term(i, indent)).collect();
+                if items.is_empty() {
+                    "()".into()
+                } else {
+                    format!("( {} )", items.join(" "))
+                }
+            }
+            Term::Blank(statements) if statements.is_empty() => "[]".into(),
+            Term::Blank(statements) => format!(
+                "[\n{}\n{}]",
+                self.predicate_objects(statements, indent + 4),
+                " ".repeat(indent)
+            ),
+        }
+    }
+
+    /// Writes the statements, repeating objects of the same predicate with a comma.
+    fn predicate_objects(&mut self, statements: &'a [(String, Term)], indent: usize) -> String {
+        let pad = " ".repeat(indent);
+        let mut lines: Vec<String> = Vec::new();
+        let mut previous: Option<&str> = None;
+        for (predicate, object) in statements {
+            let object = self.term(object, indent);
+            match (previous, lines.last_mut()) {
+                (Some(p), Some(line)) if p == predicate => {
+                    line.push_str(", ");
+                    line.push_str(&object);
+                }
+                _ => {
+                    let predicate_str = self.iri(predicate);
+                    lines.push(format!("{}{} {}", pad, predicate_str, object));
+                }
+            }
+            previous = Some(predicate);
+        }
+        lines.join(" ;\n")
+    }
+
+    fn description(&mut self, description: &'a Description, indent: usize) -> String {
+        format!(
+            "{}{}\n{} .\n",
+            " ".repeat(indent),
+            self.iri(&description.subject),
+            self.predicate_objects(&description.statements, indent + 4)
+        )
+    }
+
+    /// Adds the `@prefix` declarations of the used prefixes to the body.
+    fn finish(self, body: String) -> String {
+        let mut out = String::new();
+        for (prefix, namespace) in self.prefixes.prefixes.iter() {
+            if self.used.contains(&prefix.as_str()) {
+                out.push_str(&format!("@prefix {}: <{}> .\n", prefix, namespace));
+            }
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&body);
+        out
+    }
+}
+
+/// Serializes Atoms to Turtle, using the prefixes of the Ontologies in the store.
+pub fn atoms_to_turtle(atoms: Vec<Atom>, store: &impl Storelike) -> AtomicResult<String> {
+    let prefixes = Prefixes::from_store(store);
+    let descriptions = atoms_to_descriptions(atoms);
+    let mut writer = TurtleWriter {
+        prefixes: &prefixes,
+        used: Vec::new(),
+    };
+    let body: Vec<String> = descriptions
+        .iter()
+        .map(|d| writer.description(d, 0))
+        .collect();
+    Ok(writer.finish(body.join("\n")))
+}
+
+/// Serializes Atoms to TriG. Every Resource is placed in a named graph, which is named after its subject.
+pub fn atoms_to_trig(atoms: Vec<Atom>, store: &impl Storelike) -> AtomicResult<String> {
+    let prefixes = Prefixes::from_store(store);
+    let descriptions = atoms_to_descriptions(atoms);
+    let mut writer = TurtleWriter {
+        prefixes: &prefixes,
+        used: Vec::new(),
+    };
+    let body: Vec<String> = descriptions
+        .iter()
+        .map(|d| trig_graph(&mut writer, d))
+        .collect();
+    Ok(writer.finish(body.join("\n")))
+}
+
+/// A named graph with the statements of a single subject.
+fn trig_graph<'a>(writer: &mut TurtleWriter<'a>, description: &'a Description) -> String {
+    let graph = writer.iri(&description.subject);
+    format!("{} {{\n{}}}\n", graph, writer.description(description, 4))
+}
+
+/// Writes Resources as Turtle, or as TriG if `trig` is set, one Resource at a time, so they don't have to fit in memory.
+/// The prefixes come first, before it is known which are used, so all prefixes of the store are declared.
+/// Returns the amount of Resources.
+pub fn write_turtle(
+    resources: impl Iterator<Item = Resource>,
+    store: &impl Storelike,
+    trig: bool,
+    writer: &mut impl std::io::Write,
+) -> AtomicResult<usize> {
+    let prefixes = Prefixes::from_store(store);
+    for (prefix, namespace) in prefixes.prefixes.iter() {
+        writeln!(writer, "@prefix {}: <{}> .", prefix, namespace)?;
+    }
+    let mut count = 0;
+    for resource in resources {
+        for description in atoms_to_descriptions(resource.to_atoms()).iter() {
+            let mut turtle = TurtleWriter {
+                prefixes: &prefixes,
+                used: Vec::new(),
+            };
+            let block = if trig {
+                trig_graph(&mut turtle, description)
+            } else {
+                turtle.description(description, 0)
+            };
+            write!(writer, "\n{}", block)?;
+        }
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Writes RDF/XML. Predicates need a prefix, so one is generated for unknown namespaces.
+struct XmlWriter {
+    prefixes: Prefixes,
+    used: Vec<String>,
+}
+
+impl XmlWriter {
+    /// Returns the qualified name of a predicate, e.g. `atomic:name`.
+    fn qname(&mut self, iri: &str) -> AtomicResult<String> {
+        if self.prefixes.shorten(iri).is_none() {
+            let namespace = namespace_of(iri);
+            let mut i = self.prefixes.prefixes.len();
+            while !self.prefixes.add(&format!("ns{}", i), namespace) {
+                if self.prefixes.prefixes.iter().any(|(_, ns)| ns == namespace) {
+                    break;
+                }
+                i += 1;
+            }
+        }
+        let (prefix, local) = self.prefixes.shorten(iri).ok_or(format!(
+            "Predicate {} can not be serialized as RDF/XML, as it does not end with a valid name",
+            iri
+        ))?;
+        let qname = format!("{}:{}", prefix, local);
+        if !self.used.iter().any(|p| p == prefix) {
+            self.used.push(prefix.to_string());
+        }
+        Ok(qname)
+    }
+
+    fn statements(
+        &mut self,
+        statements: &[(String, Term)],
+        indent: usize,
+        out: &mut String,
+    ) -> AtomicResult<()> {
+        let pad = " ".repeat(indent);
+        for (predicate, object) in statements {
+            let qname = self.qname(predicate)?;
+            match object {
+                Term::Iri(iri) => out.push_str(&format!(
+                    "{}<{} rdf:resource=\"{}\"/>\n",
+                    pad,
+                    qname,
+                    escape_xml(iri)
+                )),
+                Term::Literal { value, datatype } => {
+                    let datatype = match datatype {
+                        Some(dt) => format!(" rdf:datatype=\"{}\"", escape_xml(dt)),
+                        None => String::new(),
+                    };
+                    out.push_str(&format!(
+                        "{}<{}{}>{}</{}>\n",
+                        pad,
+                        qname,
+                        datatype,
+                        escape_xml(value),
+                        qname
+                    ))
+                }
+                Term::LangLiteral { value, language } => out.push_str(&format!(
+                    "{}<{} xml:lang=\"{}\">{}</{}>\n",
+                    pad,
+                    qname,
+                    escape_xml(language),
+                    escape_xml(value),
+                    qname
+                )),
+                Term::List(items) => {
+                    out.push_str(&format!(
+                        "{}<{} rdf:parseType=\"Collection\">\n",
+                        pad, qname
+                    ));
+                    for item in items {
+                        self.node(item, indent + 2, out)?;
+                    }
+                    out.push_str(&format!("{}</{}>\n", pad, qname));
+                }
+                Term::Blank(statements) => {
+                    out.push_str(&format!("{}<{} rdf:parseType=\"Resource\">\n", pad, qname));
+                    self.statements(statements, indent + 2, out)?;
+                    out.push_str(&format!("{}</{}>\n", pad, qname));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes an item of a Collection.
+    fn node(&mut self, term: &Term, indent: usize, out: &mut String) -> AtomicResult<()> {
+        let pad = " ".repeat(indent);
+        match term {
+            Term::Iri(iri) => out.push_str(&format!(
+                "{}<rdf:Description rdf:about=\"{}\"/>\n",
+                pad,
+                escape_xml(iri)
+            )),
+            Term::Blank(statements) => {
+                out.push_str(&format!("{}<rdf:Description>\n", pad));
+                self.statements(statements, indent + 2, out)?;
+                out.push_str(&format!("{}</rdf:Description>\n", pad));
+            }
+            _ => return Err("RDF/XML Collections can only contain resources".into()),
+        }
+        Ok(())
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Serializes Atoms to RDF/XML, using the prefixes of the Ontologies in the store.
+pub fn atoms_to_rdf_xml(atoms: Vec<Atom>, store: &impl Storelike) -> AtomicResult<String> {
+    let descriptions = atoms_to_descriptions(atoms);
+    let mut writer = XmlWriter {
+        prefixes: Prefixes::from_store(store),
+        used: vec!["rdf".into()],
+    };
+    let mut body = String::new();
+    for description in descriptions.iter() {
+        body.push_str(&format!(
+            "  <rdf:Description rdf:about=\"{}\">\n",
+            escape_xml(&description.subject)
+        ));
+        writer.statements(&description.statements, 4, &mut body)?;
+        body.push_str("  </rdf:Description>\n");
+    }
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rdf:RDF");
+    for prefix in writer.used.iter() {
+        let namespace = writer.prefixes.namespace(prefix).unwrap_or_default();
+        out.push_str(&format!(
+            "\n    xmlns:{}=\"{}\"",
+            prefix,
+            escape_xml(namespace)
+        ));
+    }
+    out.push_str(">\n");
+    out.push_str(&body);
+    out.push_str("</rdf:RDF>\n");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{datatype::DataType, Resource, Store};
+
+    fn init() -> (Store, Vec<Atom>) {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        let mut resource = Resource::new("https://example.com/thing".into());
+        resource.set_unsafe(urls::NAME.into(), Value::String("A \"thing\"".into()));
+        resource.set_unsafe(urls::IS_A.into(), vec![urls::CLASS, urls::PROPERTY].into());
+        resource.set_unsafe(urls::SHORTNAME.into(), Value::Slug("thing".into()));
+        resource.set_unsafe(urls::DESCRIPTION.into(), Value::Markdown("A thing".into()));
+        resource.set_unsafe(
+            urls::DATATYPE_PROP.into(),
+            Value::AtomicUrl(urls::STRING.into()),
+        );
+        let mut nested = PropVals::new();
+        nested.insert(urls::DESCRIPTION.into(), Value::Markdown("Nested".into()));
+        resource.set_unsafe(
+            "https://example.com/vocab/nested".into(),
+            Value::NestedResource(SubResource::Nested(nested)),
+        );
+        (store, resource.to_atoms())
+    }
+
+    #[test]
+    fn turtle_roundtrip() {
+        let (store, atoms) = init();
+        let turtle = atoms_to_turtle(atoms, &store).unwrap();
+        assert!(turtle.contains("@prefix atomic: <https://atomicdata.dev/properties/> ."));
+        assert!(turtle.contains("atomic:isA ( atomic-classes:Class atomic-classes:Property )"));
+        assert!(turtle.contains(r#"atomic:name "A \"thing\"""#));
+        assert!(
+            !turtle.contains("@prefix owl:"),
+            "Unused prefixes are left out"
+        );
+
+        let store2 = Store::init().unwrap();
+        store2.populate().unwrap();
+        let opts = crate::parse::ParseOpts {
+            importer: Some("https://example.com/import".into()),
+            save: crate::parse::SaveOpts::Save,
+            ..Default::default()
+        };
+        crate::rdf::parse_rdf(&turtle, crate::rdf::RdfFormat::Turtle, &store2, &opts).unwrap();
+        let thing = store2.get_resource("https://example.com/thing").unwrap();
+        assert_eq!(
+            thing.get(urls::IS_A).unwrap().to_subjects(None).unwrap(),
+            vec![urls::CLASS.to_string(), urls::PROPERTY.to_string()]
+        );
+        assert_eq!(thing.get(urls::NAME).unwrap().to_string(), "A \"thing\"");
+    }
+
+    #[test]
+    fn xsd_datatypes() {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        let starts_at = "https://example.com/vocab/startsAt";
+        let length = "https://example.com/vocab/length";
+        for (subject, data_type) in [
+            (starts_at, DataType::DateTime),
+            (length, DataType::Duration),
+        ] {
+            let property = crate::schema::Property {
+                class_type: None,
+                data_type,
+                shortname: "property".into(),
+                description: "A property".into(),
+                subject: subject.into(),
+                allows_only: None,
+                constraints: Default::default(),
+                default: None,
+            };
+            store.add_resource(&property.to_resource()).unwrap();
+        }
+        let mut resource = Resource::new("https://example.com/meeting".into());
+        resource.set_unsafe(
+            starts_at.into(),
+            Value::DateTime("2024-05-01T09:30:00+02:00".into()),
+        );
+        resource.set_unsafe(length.into(), Value::Duration("PT1H30M".into()));
+        let atoms = resource.to_atoms();
+
+        let ntriples = crate::serialize::atoms_to_ntriples(atoms.clone(), &store).unwrap();
+        assert!(ntriples.contains(
+            r#""2024-05-01T09:30:00+02:00"^^<http://www.w3.org/2001/XMLSchema#dateTime>"#
+        ));
+        let turtle = atoms_to_turtle(atoms, &store).unwrap();
+        assert!(turtle.contains(r#""PT1H30M"^^xsd:duration"#));
+
+        let opts = crate::parse::ParseOpts {
+            importer: Some("https://example.com/import".into()),
+            save: crate::parse::SaveOpts::Save,
+            ..Default::default()
+        };
+        // The importer does not know these properties, so it picks the datatypes from the literals
+        let store2 = Store::init().unwrap();
+        store2.populate().unwrap();
+        crate::rdf::parse_rdf(&turtle, crate::rdf::RdfFormat::Turtle, &store2, &opts).unwrap();
+        let meeting = store2.get_resource("https://example.com/meeting").unwrap();
+        assert!(matches!(
+            meeting.get(length).unwrap(),
+            Value::Duration(d) if d == "PT1H30M"
+        ));
+    }
+
+    #[test]
+    fn ontology_prefixes() {
+        let (store, atoms) = init();
+        let mut ontology = Resource::new("https://example.com/vocab".into());
+        ontology.set_unsafe(urls::IS_A.into(), vec![urls::ONTOLOGY].into());
+        ontology.set_unsafe(urls::SHORTNAME.into(), Value::Slug("vocab".into()));
+        ontology.set_unsafe(urls::DESCRIPTION.into(), Value::Markdown("Vocab".into()));
+        ontology.set_unsafe(
+            urls::PROPERTIES.into(),
+            vec!["https://example.com/vocab/nested"].into(),
+        );
+        store.add_resource(&ontology).unwrap();
+
+        let trig = atoms_to_trig(atoms.clone(), &store).unwrap();
+        assert!(trig.contains("@prefix vocab: <https://example.com/vocab/> ."));
+        assert!(trig.contains("<https://example.com/thing> {\n    <https://example.com/thing>\n"));
+        assert!(trig.contains("vocab:nested [\n"));
+
+        let xml = atoms_to_rdf_xml(atoms, &store).unwrap();
+        assert!(xml.contains("xmlns:vocab=\"https://example.com/vocab/\""));
+        assert!(xml.contains("<atomic:name>A &quot;thing&quot;</atomic:name>"));
+        assert!(xml.contains("<vocab:nested rdf:parseType=\"Resource\">"));
+        assert!(xml.contains(
+            "<atomic:isA rdf:parseType=\"Collection\">\n      <rdf:Description rdf:about=\"https://atomicdata.dev/classes/Class\"/>"
+        ));
+    }
+
+    #[test]
+    fn write_turtle_per_resource() {
+        let (store, _atoms) = init();
+        let resources = vec![
+            store.get_resource(urls::CLASS).unwrap(),
+            store.get_resource(urls::PROPERTY).unwrap(),
+        ];
+        let mut out = Vec::new();
+        let count = write_turtle(resources.clone().into_iter(), &store, false, &mut out).unwrap();
+        assert_eq!(count, 2);
+        let streamed = String::from_utf8(out).unwrap();
+        // Same statements as when all Atoms are serialized at once
+        let atoms = resources.iter().flat_map(|r| r.to_atoms()).collect();
+        let buffered = atoms_to_turtle(atoms, &store).unwrap();
+        let body = |turtle: &str| -> Vec<String> {
+            turtle
+                .lines()
+                .filter(|l| !l.starts_with("@prefix") && !l.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        assert_eq!(body(&streamed), body(&buffered));
+        assert!(streamed.contains("@prefix owl: <http://www.w3.org/2002/07/owl#> ."));
+
+        let mut out = Vec::new();
+        write_turtle(resources.into_iter(), &store, true, &mut out).unwrap();
+        let trig = String::from_utf8(out).unwrap();
+        assert!(trig.contains("atomic-classes:Class {\n    atomic-classes:Class\n"));
+    }
+}
@@ -0,0 +1,372 @@
+//! Exports Classes as [SHACL](https://www.w3.org/TR/shacl/) shapes in Turtle, so RDF validators can check Atomic Data.
+//!
+//! - Every Class becomes a `sh:NodeShape`. Properties in `requires` get `sh:minCount 1`, those in `recommends` are optional.
+//! - Every Property becomes a `sh:PropertyShape` with `sh:maxCount 1`, since Atomic Data has one value per Property.
+//!   LangStrings are the exception: they are written as one `rdf:langString` literal per language, so they get `sh:uniqueLang` instead.
+//! - The `sh:datatype` matches the literals of the RDF serializers, see [DataType::rdf_datatype]. Strings use `xsd:string`.
+//! - `allowsOnly` becomes `sh:in`. Resource Arrays are RDF Lists, so their items are checked using a path through `rdf:rest*/rdf:first`.
+//! - For text, `minLength`, `maxLength` and `pattern` become `sh:minLength`, `sh:maxLength` and `sh:pattern`.
+//!   Number ranges are not exported.
+//!
+//! Atomic Data uses `isA` instead of `rdf:type`, so `sh:targetClass` only matches data that also uses `rdf:type`.
+//! That's why the shapes also have a SPARQL-based target (SHACL Advanced Features) for instances that use `isA`.
+//! For the same reason, `classtype` is not exported as `sh:class`, which would require an `rdf:type` on every linked resource.
+
+use super::{Description, Prefixes, Term, TurtleWriter, RDF};
+use crate::{
+    datatype::DataType,
+    errors::AtomicResult,
+    schema::{Class, Property},
+    urls,
+    values::DATE_REGEX,
+    Storelike,
+};
+
+pub const SHACL: &str = "http://www.w3.org/ns/shacl#";
+/// Path of the endpoint that serves the shapes. Used as the IRI of the NodeShapes.
+pub const SHACL_PATH: &str = "/shacl";
+
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const RDFS_COMMENT: &str = "http://www.w3.org/2000/01/rdf-schema#comment";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+/// Same as [crate::values::SLUG_REGEX], but without the non-capturing group, which XPath regexes don't support.
+const SLUG_PATTERN: &str = "^[a-z0-9]+(-[a-z0-9]+)*$";
+
+/// The IRI of the NodeShape of a Class.
+pub fn shape_url(store: &impl Storelike, class_subject: &str) -> String {
+    format!(
+        "{}{}?class={}",
+        store.get_server_url(),
+        SHACL_PATH,
+        urlencoding::encode(class_subject)
+    )
+}
+
+/// Serializes the Classes and their Properties as SHACL shapes in Turtle.
+pub fn classes_to_shacl(class_subjects: &[String], store: &impl Storelike) -> AtomicResult<String> {
+    let mut prefixes = Prefixes::from_store(store);
+    prefixes.add("sh", SHACL);
+    let mut shapes = Vec::new();
+    for subject in class_subjects {
+        let class = store.get_class(subject)?.with_inherited(store)?;
+        shapes.push(class_to_node_shape(store, &class)?);
+    }
+    let mut writer = TurtleWriter {
+        prefixes: &prefixes,
+        used: Vec::new(),
+    };
+    let body: Vec<String> = shapes.iter().map(|d| writer.description(d, 0)).collect();
+    Ok(writer.finish(body.join("\n")))
+}
+
+fn sh(name: &str) -> String {
+    format!("{}{}", SHACL, name)
+}
+
+fn iri(iri: &str) -> Term {
+    Term::Iri(iri.into())
+}
+
+fn string(value: &str) -> Term {
+    Term::Literal {
+        value: value.into(),
+        datatype: None,
+    }
+}
+
+fn integer(value: i64) -> Term {
+    Term::Literal {
+        value: value.to_string(),
+        datatype: Some(XSD_INTEGER.into()),
+    }
+}
+
+fn boolean(value: bool) -> Term {
+    Term::Literal {
+        value: value.to_string(),
+        datatype: Some(XSD_BOOLEAN.into()),
+    }
+}
+
+fn class_to_node_shape(store: &impl Storelike, class: &Class) -> AtomicResult<Description> {
+    let mut statements = vec![
+        (format!("{}type", RDF), iri(&sh("NodeShape"))),
+        (RDFS_LABEL.into(), string(&class.shortname)),
+        (RDFS_COMMENT.into(), string(&class.description)),
+        (sh("targetClass"), iri(&class.subject)),
+        (
+            sh("target"),
+            Term::Blank(vec![
+                (format!("{}type", RDF), iri(&sh("SPARQLTarget"))),
+                (
+                    sh("select"),
+                    string(&format!(
+                        "SELECT ?this WHERE {{ ?this <{}>/<{}rest>*/<{}first> <{}> . }}",
+                        urls::IS_A,
+                        RDF,
+                        RDF,
+                        class.subject
+                    )),
+                ),
+            ]),
+        ),
+    ];
+    let mut seen = Vec::new();
+    for subject in class.requires.iter().chain(class.recommends.iter()) {
+        if seen.contains(&subject) {
+            continue;
+        }
+        seen.push(subject);
+        let property = store.get_property(subject)?;
+        let required = class.requires.contains(subject);
+        for shape in property_shapes(&property, required) {
+            statements.push((sh("property"), shape));
+        }
+    }
+    Ok(Description {
+        subject: shape_url(store, &class.subject),
+        statements,
+    })
+}
+
+/// The PropertyShapes of a Property. Resource Arrays get a second shape for the items of the list.
+fn property_shapes(property: &Property, required: bool) -> Vec<Term> {
+    let mut shape = vec![
+        (sh("path"), iri(&property.subject)),
+        (sh("name"), string(&property.shortname)),
+        (sh("description"), string(&property.description)),
+    ];
+    if required {
+        shape.push((sh("minCount"), integer(1)));
+    }
+    if property.data_type == DataType::LangString {
+        shape.push((sh("uniqueLang"), boolean(true)));
+    } else {
+        shape.push((sh("maxCount"), integer(1)));
+    }
+
+    match &property.data_type {
+        DataType::AtomicUrl => {
+            shape.extend(reference_constraints(property));
+            vec![Term::Blank(shape)]
+        }
+        DataType::ResourceArray => {
+            shape.push((sh("nodeKind"), iri(&sh("BlankNodeOrIRI"))));
+            let mut items = vec![
+                (
+                    sh("path"),
+                    Term::List(vec![
+                        iri(&property.subject),
+                        Term::Blank(vec![(sh("zeroOrMorePath"), iri(&format!("{}rest", RDF)))]),
+                        iri(&format!("{}first", RDF)),
+                    ]),
+                ),
+                (sh("name"), string(&format!("{} item", property.shortname))),
+            ];
+            items.extend(reference_constraints(property));
+            vec![Term::Blank(shape), Term::Blank(items)]
+        }
+        DataType::String => {
+            shape.push((sh("datatype"), iri(super::XSD_STRING)));
+            shape.extend(text_constraints(property));
+            vec![Term::Blank(shape)]
+        }
+        other => {
+            shape.push((sh("datatype"), iri(&other.rdf_datatype())));
+            match other {
+                DataType::Slug => shape.push((sh("pattern"), string(SLUG_PATTERN))),
+                DataType::Date => shape.push((sh("pattern"), string(DATE_REGEX))),
+                _ => {}
+            }
+            if matches!(
+                other,
+                DataType::Markdown | DataType::Slug | DataType::Date | DataType::LangString
+            ) {
+                shape.extend(text_constraints(property));
+            }
+            vec![Term::Blank(shape)]
+        }
+    }
+}
+
+/// Length and pattern constraints for text values.
+fn text_constraints(property: &Property) -> Vec<(String, Term)> {
+    let constraints = &property.constraints;
+    let mut statements = Vec::new();
+    if let Some(min_length) = constraints.min_length {
+        statements.push((sh("minLength"), integer(min_length as i64)));
+    }
+    if let Some(max_length) = constraints.max_length {
+        statements.push((sh("maxLength"), integer(max_length as i64)));
+    }
+    if let Some(pattern) = &constraints.pattern {
+        statements.push((sh("pattern"), string(pattern)));
+    }
+    statements
+}
+
+/// Constraints for values that link to Resources, which can be nested (blank nodes).
+fn reference_constraints(property: &Property) -> Vec<(String, Term)> {
+    let mut constraints = vec![(sh("nodeKind"), iri(&sh("BlankNodeOrIRI")))];
+    if let Some(allowed) = &property.allows_only {
+        constraints.push((
+            sh("in"),
+            Term::List(allowed.iter().map(|a| iri(a)).collect()),
+        ));
+    }
+    constraints
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Store;
+
+    #[test]
+    fn property_class_to_shacl() {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        let turtle = classes_to_shacl(&[urls::PROPERTY.into()], &store).unwrap();
+        assert!(turtle.contains("@prefix sh: <http://www.w3.org/ns/shacl#> ."));
+        assert!(turtle.contains("sh:targetClass atomic-classes:Property"));
+        assert!(turtle.contains("rdf:type sh:NodeShape"));
+        // Shortname is required, and a slug
+        assert!(turtle.contains(
+            "sh:path atomic:shortname ;\n        sh:name \"shortname\" ;\n        sh:description"
+        ));
+        assert!(turtle.contains("sh:pattern \"^[a-z0-9]+(-[a-z0-9]+)*$\""));
+        assert!(turtle.contains("sh:minCount \"1\"^^xsd:integer"));
+        // The datatype links to a Resource, and Resource Arrays are checked per item
+        assert!(turtle.contains("sh:path atomic:datatype ;"));
+        assert!(turtle.contains("sh:path ( atomic:allowsOnly [\n"));
+
+        // The output is valid Turtle
+        let mut parser = rio_turtle::TurtleParser::new(turtle.as_bytes(), None);
+        let mut count = 0;
+        rio_api::parser::TriplesParser::parse_all(&mut parser, &mut |_| {
+            count += 1;
+            Ok(()) as Result<(), rio_turtle::TurtleError>
+        })
+        .unwrap();
+        assert!(count > 20);
+    }
+
+    /// Parses Turtle to (subject, predicate, object) triples. Objects are IRIs, blank node ids or `value^^datatype`.
+    fn parse_turtle(turtle: &str) -> Vec<(String, String, String)> {
+        use rio_api::model::{Literal, Subject, Term};
+        let mut triples = Vec::new();
+        let mut parser = rio_turtle::TurtleParser::new(turtle.as_bytes(), None);
+        rio_api::parser::TriplesParser::parse_all(&mut parser, &mut |t| {
+            let subject = match t.subject {
+                Subject::NamedNode(n) => n.iri.to_string(),
+                Subject::BlankNode(b) => b.id.to_string(),
+                Subject::Triple(_) => unreachable!(),
+            };
+            let object = match t.object {
+                Term::NamedNode(n) => n.iri.to_string(),
+                Term::BlankNode(b) => b.id.to_string(),
+                Term::Literal(Literal::Typed { value, datatype }) => {
+                    format!("{}^^{}", value, datatype.iri)
+                }
+                Term::Literal(Literal::LanguageTaggedString { value, .. })
+                | Term::Literal(Literal::Simple { value }) => value.to_string(),
+                Term::Triple(_) => unreachable!(),
+            };
+            triples.push((subject, t.predicate.iri.to_string(), object));
+            Ok(()) as Result<(), rio_turtle::TurtleError>
+        })
+        .unwrap();
+        triples
+    }
+
+    #[test]
+    fn shapes_match_serialized_literals() {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        let mut properties = Vec::new();
+        for (shortname, data_type) in [
+            ("price", DataType::Decimal),
+            ("published", DataType::DateTime),
+            ("title", DataType::LangString),
+        ] {
+            let property = Property {
+                class_type: None,
+                data_type,
+                shortname: shortname.into(),
+                description: "A typed literal".into(),
+                subject: format!("https://localhost/properties/{}", shortname),
+                allows_only: None,
+                constraints: Default::default(),
+                default: None,
+            };
+            store.add_resource(&property.to_resource()).unwrap();
+            properties.push(property);
+        }
+        let class = Class {
+            requires: properties.iter().map(|p| p.subject.clone()).collect(),
+            recommends: Vec::new(),
+            extends: Vec::new(),
+            unique: Vec::new(),
+            rules: Vec::new(),
+            shortname: "offer".into(),
+            description: "An offer".into(),
+            subject: "https://localhost/classes/Offer".into(),
+        };
+        store.add_resource(&class.to_resource()).unwrap();
+        let shapes =
+            parse_turtle(&classes_to_shacl(std::slice::from_ref(&class.subject), &store).unwrap());
+
+        let mut resource = crate::Resource::new("https://localhost/offer".into());
+        resource.set_unsafe(
+            properties[0].subject.clone(),
+            crate::Value::new("1.50", &DataType::Decimal).unwrap(),
+        );
+        resource.set_unsafe(
+            properties[1].subject.clone(),
+            crate::Value::new("2024-05-01T09:30:00+02:00", &DataType::DateTime).unwrap(),
+        );
+        resource.set_unsafe(
+            properties[2].subject.clone(),
+            crate::Value::new(r#"{"en": "Offer", "nl": "Aanbod"}"#, &DataType::LangString).unwrap(),
+        );
+        let data =
+            parse_turtle(&super::super::atoms_to_turtle(resource.to_atoms(), &store).unwrap());
+
+        for property in properties.iter() {
+            let shape = shapes
+                .iter()
+                .find(|t| t.1 == sh("path") && t.2 == property.subject)
+                .map(|t| &t.0)
+                .unwrap();
+            let has = |predicate: &str, object: &str| {
+                shapes
+                    .iter()
+                    .any(|t| &t.0 == shape && t.1 == sh(predicate) && t.2 == object)
+            };
+            let literals: Vec<&String> = data
+                .iter()
+                .filter(|t| t.1 == property.subject)
+                .map(|t| &t.2)
+                .collect();
+            if property.data_type == DataType::LangString {
+                // One literal per language, which is allowed as long as the languages differ
+                assert_eq!(literals.len(), 2);
+                assert!(has("datatype", &DataType::LangString.rdf_datatype()));
+                assert!(has("uniqueLang", &format!("true^^{}", XSD_BOOLEAN)));
+                assert!(!shapes
+                    .iter()
+                    .any(|t| &t.0 == shape && t.1 == sh("maxCount")));
+                continue;
+            }
+            let (_, datatype) = literals[0].rsplit_once("^^").unwrap();
+            // The datatype of the literal is the one that the shape of its Property requires
+            assert!(
+                has("datatype", datatype),
+                "{} has no shape with datatype {}",
+                property.shortname,
+                datatype
+            );
+        }
+    }
+}
@@ -5,7 +5,10 @@ use crate::agents::Agent;
 use crate::storelike::QueryResult;
 use crate::Value;
 use crate::{atoms::Atom, storelike::Storelike};
-use crate::{errors::AtomicResult, Resource};
+use crate::{
+    errors::{AtomicError, AtomicResult},
+    Resource,
+};
 use std::{collections::HashMap, sync::Arc, sync::Mutex};
 
 /// The in-memory store of data, containing the Resources, Properties and Classes
@@ -15,6 +18,8 @@ pub struct Store {
     // The store currently holds two stores - that is not ideal
     hashmap: Arc<Mutex<HashMap<String, Resource>>>,
     default_agent: Arc<Mutex<Option<crate::agents::Agent>>>,
+    /// See [Storelike::next_counter]
+    counters: Arc<Mutex<HashMap<String, i64>>>,
 }
 
 impl Store {
@@ -24,6 +29,7 @@ impl Store {
         let store = Store {
             hashmap: Arc::new(Mutex::new(HashMap::new())),
             default_agent: Arc::new(Mutex::new(None)),
+            counters: Arc::new(Mutex::new(HashMap::new())),
         };
         crate::populate::populate_base_models(&store)?;
         Ok(store)
@@ -186,6 +192,25 @@ impl Storelike for Store {
         )
     }
 
+    fn get_resource_local(&self, subject: &str) -> AtomicResult<Resource> {
+        self.hashmap
+            .lock()
+            .unwrap()
+            .get(subject)
+            .cloned()
+            .ok_or_else(|| AtomicError::not_found(format!("{} is not in the store", subject)))
+    }
+
+    fn next_counter(&self, key: &str, start: &dyn Fn() -> AtomicResult<i64>) -> AtomicResult<i64> {
+        let mut counters = self.counters.lock().unwrap();
+        let current = match counters.get(key) {
+            Some(current) => *current,
+            None => start()?,
+        };
+        counters.insert(key.into(), current + 1);
+        Ok(current + 1)
+    }
+
     fn remove_resource(&self, subject: &str) -> AtomicResult<()> {
         self.hashmap
             .lock()
@@ -203,6 +228,9 @@ impl Storelike for Store {
     }
 
     fn query(&self, q: &crate::storelike::Query) -> AtomicResult<crate::storelike::QueryResult> {
+        if let Some(result) = crate::storelike::query_subclasses(self, q)? {
+            return Ok(result);
+        }
         let atoms = self.tpf(
             None,
             q.property.as_deref(),
@@ -243,6 +271,12 @@ impl Storelike for Store {
             }
         }
 
+        let mut count = atoms.len();
+        if let Some(geo) = &q.geo {
+            resources.retain(|r| geo.matches(r));
+            count = resources.len();
+        }
+
         if let Some(sort) = &q.sort_by {
             resources = crate::collections::sort_resources(resources, sort, q.sort_desc);
         }
@@ -252,7 +286,7 @@ impl Storelike for Store {
         }
 
         Ok(QueryResult {
-            count: atoms.len(),
+            count,
             subjects,
             resources,
         })
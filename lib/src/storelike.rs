@@ -9,7 +9,12 @@ use crate::{
     urls,
 };
 use crate::{errors::AtomicResult, parse::parse_json_ad_string};
-use crate::{mapping::Mapping, values::Value, Atom, Resource};
+use crate::{
+    mapping::Mapping,
+    values::{SortableValue, Value},
+    Atom, Resource,
+};
+use std::collections::HashSet;
 
 // A path can return one of many things
 pub enum PathReturn {
@@ -59,6 +64,13 @@ pub trait Storelike: Sized {
         overwrite_existing: bool,
     ) -> AtomicResult<()>;
 
+    /// Runs `write`, which changes several Resources or indexes, while snapshots of the store wait.
+    /// That way, a snapshot contains all of these changes or none of them, see [crate::Db::snapshot].
+    /// The default implementation just runs `write`, so overwrite it if your store can create snapshots.
+    fn hold_snapshots<T>(&self, write: impl FnOnce() -> AtomicResult<T>) -> AtomicResult<T> {
+        write()
+    }
+
     /// Returns an iterator that iterates over all resources in the store.
     /// If Include_external is false, this is filtered by selecting only resoureces that match the `self` URL of the store.
     fn all_resources(&self, include_external: bool) -> Box<dyn Iterator<Item = Resource>>;
@@ -130,6 +142,39 @@ pub trait Storelike: Sized {
         crate::serialize::resources_to_json_ad(&properties)
     }
 
+    /// Streams the store as Newline Delimited JSON-AD (one Resource per line) to the `writer`.
+    /// Unlike [Storelike::export], this does not hold the entire store in memory.
+    /// Property Resources are written first (which requires iterating over the store twice), so the output can be imported in order.
+    /// Returns the amount of exported resources.
+    fn export_ndjson(
+        &self,
+        include_external: bool,
+        writer: &mut impl std::io::Write,
+    ) -> AtomicResult<usize> {
+        let is_property = |r: &Resource| {
+            r.get_main_class()
+                .map(|class| class == crate::urls::PROPERTY)
+                .unwrap_or(false)
+        };
+        let mut count = 0;
+        for r in self
+            .all_resources(include_external)
+            .filter(|r| is_property(r))
+        {
+            crate::serialize::resource_to_ndjson_line(&r, writer)?;
+            count += 1;
+        }
+        for r in self
+            .all_resources(include_external)
+            .filter(|r| !is_property(r))
+        {
+            crate::serialize::resource_to_ndjson_line(&r, writer)?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
     /// Fetches a resource, makes sure its subject matches.
     /// Save to the store.
     /// Uses `client_agent` for Authentication.
@@ -148,6 +193,15 @@ pub trait Storelike: Sized {
     /// If you're not sure what to use, use `get_resource_extended`.
     fn get_resource(&self, subject: &str) -> AtomicResult<Resource>;
 
+    /// Returns a Resource that is in this store. Unlike [Storelike::get_resource], never fetches it from elsewhere.
+    /// Use this when the subject comes from untrusted input, so that it can't make the server send requests.
+    fn get_resource_local(&self, subject: &str) -> AtomicResult<Resource>;
+
+    /// Increments the counter with this `key`, and returns the new value. Used by [crate::schema::Generator::Counter].
+    /// A new counter continues after the value returned by `start`.
+    /// Concurrent calls never return the same value.
+    fn next_counter(&self, key: &str, start: &dyn Fn() -> AtomicResult<i64>) -> AtomicResult<i64>;
+
     /// Returns an existing resource, or creates a new one with the given Subject
     fn get_resource_new(&self, subject: &str) -> Resource {
         match self.get_resource(subject) {
@@ -225,6 +279,44 @@ pub trait Storelike: Sized {
         Ok(len)
     }
 
+    /// Imports plain JSON with shortnames as keys as instances of the Class, returns the amount of imported resources.
+    /// See [crate::parse::parse_json_with_class].
+    fn import_json(
+        &self,
+        string: &str,
+        class: &str,
+        parse_opts: &crate::parse::ParseOpts,
+    ) -> AtomicResult<usize> {
+        let vec = crate::parse::parse_json_with_class(string, class, self, parse_opts)?;
+        Ok(vec.len())
+    }
+
+    /// Imports an RDF string (Turtle, N-Triples or JSON-LD), returns the amount of imported resources.
+    /// Unknown predicates are added as new Properties.
+    #[cfg(feature = "rdf")]
+    fn import_rdf(
+        &self,
+        string: &str,
+        format: crate::rdf::RdfFormat,
+        parse_opts: &crate::parse::ParseOpts,
+    ) -> AtomicResult<usize> {
+        let vec = crate::rdf::parse_rdf(string, format, self, parse_opts)?;
+        Ok(vec.len())
+    }
+
+    /// Imports a Newline Delimited JSON-AD stream (one Resource per line), returns the amount of imported resources.
+    /// Reads and saves `batch_size` lines at a time, so the full input never has to be kept in memory.
+    /// `on_progress` is called after every batch with the total amount of imported resources so far.
+    fn import_ndjson(
+        &self,
+        reader: impl std::io::BufRead,
+        parse_opts: &crate::parse::ParseOpts,
+        batch_size: usize,
+        on_progress: &mut dyn FnMut(usize),
+    ) -> AtomicResult<usize> {
+        crate::parse::parse_ndjson_ad(reader, self, parse_opts, batch_size, on_progress)
+    }
+
     /// Removes a resource from the store. Errors if not present.
     fn remove_resource(&self, subject: &str) -> AtomicResult<()>;
 
@@ -326,6 +418,7 @@ pub trait Storelike: Sized {
         &self,
         _subject: &str,
         _body: Vec<u8>,
+        _content_type: Option<&str>,
         _for_agent: &ForAgent,
     ) -> AtomicResult<Resource> {
         Err("`post_resource` not implemented for StoreLike. Implement it in your trait.".into())
@@ -354,7 +447,8 @@ pub trait Storelike: Sized {
     }
 }
 
-/// Use this to construct a list of Resources
+/// Use this to construct a list of Resources.
+/// Create it with [Query::new], or end a struct literal with `..Default::default()`, so fields that are added later get their defaults.
 #[derive(Debug)]
 pub struct Query {
     /// Filter by Property
@@ -377,6 +471,10 @@ pub struct Query {
     pub include_external: bool,
     /// Whether to include full Resources in the result, if not, will add empty vector here.
     pub include_nested: bool,
+    /// If the Query filters by `isA`, also include instances of Classes that (indirectly) extend the Class.
+    pub include_subclasses: bool,
+    /// Only include Resources with a [crate::geo::GeoPoint] in some area.
+    pub geo: Option<crate::geo::GeoFilter>,
     /// For which Agent the query is executed. Pass `None` if you want to skip permission checks.
     pub for_agent: ForAgent,
 }
@@ -394,6 +492,8 @@ impl Query {
             sort_desc: false,
             include_external: false,
             include_nested: true,
+            include_subclasses: false,
+            geo: None,
             for_agent: ForAgent::Sudo,
         }
     }
@@ -406,7 +506,8 @@ impl Query {
         q
     }
 
-    /// Search for instances of some Class
+    /// Search for instances of some Class.
+    /// Set `include_subclasses` to also find instances of Classes that extend it.
     pub fn new_class(class: &str) -> Self {
         let mut q = Self::new();
         q.property = Some(urls::IS_A.into());
@@ -427,3 +528,105 @@ pub struct QueryResult {
     /// The amount of hits that were found, including the ones that were out of bounds or not authorized.
     pub count: usize,
 }
+
+/// Performs a Query with `include_subclasses` by querying the Class and each of its subclasses, and merging the results.
+/// Returns `None` if the Query does not filter by a Class that has subclasses, in which case it can be performed as usual.
+pub(crate) fn query_subclasses(
+    store: &impl Storelike,
+    q: &Query,
+) -> AtomicResult<Option<QueryResult>> {
+    if !q.include_subclasses || q.property.as_deref() != Some(urls::IS_A) {
+        return Ok(None);
+    }
+    let Some(class) = &q.value else {
+        return Ok(None);
+    };
+    let classes = crate::schema::class_and_subclasses(store, &class.to_string())?;
+    if classes.len() == 1 {
+        return Ok(None);
+    }
+
+    // Only the subjects are queried, so Resources are only loaded for the requested page
+    let limit = q.limit.unwrap_or(usize::MAX);
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut per_class: Vec<Vec<String>> = Vec::new();
+    for class in classes {
+        let class_query = Query {
+            property: q.property.clone(),
+            value: Some(Value::AtomicUrl(class)),
+            limit: None,
+            start_val: q.start_val.clone(),
+            end_val: q.end_val.clone(),
+            offset: 0,
+            sort_by: q.sort_by.clone(),
+            sort_desc: q.sort_desc,
+            include_external: q.include_external,
+            include_nested: false,
+            include_subclasses: false,
+            geo: q.geo.clone(),
+            for_agent: ForAgent::Sudo,
+        };
+        // Resources can be instances of multiple of the Classes
+        let subjects = store
+            .query(&class_query)?
+            .subjects
+            .into_iter()
+            .filter(|subject| seen.insert(subject.clone()))
+            .collect();
+        per_class.push(subjects);
+    }
+    let count = seen.len();
+
+    let page: Vec<String> = match &q.sort_by {
+        None => {
+            let mut subjects: Vec<String> = per_class.into_iter().flatten().collect();
+            subjects.sort();
+            subjects.into_iter().skip(q.offset).take(limit).collect()
+        }
+        Some(sort_by) => {
+            // The results of every Class are sorted already, so the page is among the first results of each Class
+            let mut candidates: Vec<(SortableValue, String)> = Vec::new();
+            for subjects in per_class {
+                for subject in subjects.into_iter().take(q.offset.saturating_add(limit)) {
+                    let value = match store.get_resource(&subject) {
+                        Ok(resource) => match resource.get(sort_by) {
+                            Ok(val) => val.to_sortable_string(),
+                            Err(_e) => SortableValue::new(),
+                        },
+                        Err(_e) => continue,
+                    };
+                    candidates.push((value, subject));
+                }
+            }
+            candidates.sort();
+            if q.sort_desc {
+                candidates.reverse();
+            }
+            candidates
+                .into_iter()
+                .skip(q.offset)
+                .take(limit)
+                .map(|(_, subject)| subject)
+                .collect()
+        }
+    };
+
+    let include_resources = q.include_nested || q.for_agent != ForAgent::Sudo;
+    let mut subjects = Vec::new();
+    let mut resources = Vec::new();
+    for subject in page {
+        if !include_resources {
+            subjects.push(subject);
+            continue;
+        }
+        if let Ok(resource) = store.get_resource_extended(&subject, true, &q.for_agent) {
+            subjects.push(subject);
+            resources.push(resource);
+        }
+    }
+    Ok(Some(QueryResult {
+        subjects,
+        resources,
+        count,
+    }))
+}
@@ -16,8 +16,10 @@ pub const FILE: &str = "https://atomicdata.dev/classes/File";
 pub const CHATROOM: &str = "https://atomicdata.dev/classes/ChatRoom";
 pub const PARAGRAPH: &str = "https://atomicdata.dev/classes/elements/Paragraph";
 pub const MESSAGE: &str = "https://atomicdata.dev/classes/Message";
+pub const ARTICLE: &str = "https://atomicdata.dev/classes/Article";
 pub const IMPORTER: &str = "https://atomicdata.dev/classes/Importer";
 pub const ERROR: &str = "https://atomicdata.dev/classes/Error";
+pub const VALIDATION_RULE: &str = "https://atomicdata.dev/classes/ValidationRule";
 pub const BOOKMARK: &str = "https://atomicdata.dev/class/Bookmark";
 pub const ONTOLOGY: &str = "https://atomicdata.dev/class/ontology";
 pub const ENDPOINT_RESPONSE: &str =
@@ -34,15 +36,35 @@ pub const IS_LOCKED: &str = "https://atomicdata.dev/properties/isLocked";
 pub const DATATYPE_PROP: &str = "https://atomicdata.dev/properties/datatype";
 pub const CLASSTYPE_PROP: &str = "https://atomicdata.dev/properties/classtype";
 pub const ALLOWS_ONLY: &str = "https://atomicdata.dev/properties/allowsOnly";
+// ... for constraints on the values of Properties
+pub const MIN: &str = "https://atomicdata.dev/properties/min";
+pub const MAX: &str = "https://atomicdata.dev/properties/max";
+pub const MIN_FLOAT: &str = "https://atomicdata.dev/properties/minFloat";
+pub const MAX_FLOAT: &str = "https://atomicdata.dev/properties/maxFloat";
+pub const MIN_LENGTH: &str = "https://atomicdata.dev/properties/minLength";
+pub const MAX_LENGTH: &str = "https://atomicdata.dev/properties/maxLength";
+pub const PATTERN: &str = "https://atomicdata.dev/properties/pattern";
+pub const UNIQUE_IN_DRIVE: &str = "https://atomicdata.dev/properties/uniqueInDrive";
+// ... for default values of Properties
+pub const DEFAULT_VALUE: &str = "https://atomicdata.dev/properties/defaultValue";
+pub const GENERATOR: &str = "https://atomicdata.dev/properties/generator";
 // ... for Classes
 pub const REQUIRES: &str = "https://atomicdata.dev/properties/requires";
 pub const RECOMMENDS: &str = "https://atomicdata.dev/properties/recommends";
+pub const EXTENDS: &str = "https://atomicdata.dev/properties/extends";
+pub const UNIQUE: &str = "https://atomicdata.dev/properties/unique";
+pub const RULES: &str = "https://atomicdata.dev/properties/rules";
+// ... for Validation Rules
+pub const EXPRESSION: &str = "https://atomicdata.dev/properties/expression";
+pub const RULE: &str = "https://atomicdata.dev/properties/rule";
+pub const VIOLATIONS: &str = "https://atomicdata.dev/properties/violations";
 // ... for Commits
 pub const SUBJECT: &str = "https://atomicdata.dev/properties/subject";
 pub const SET: &str = "https://atomicdata.dev/properties/set";
 pub const PUSH: &str = "https://atomicdata.dev/properties/push";
 pub const REMOVE: &str = "https://atomicdata.dev/properties/remove";
 pub const DESTROY: &str = "https://atomicdata.dev/properties/destroy";
+pub const DEFAULTS: &str = "https://atomicdata.dev/properties/defaults";
 pub const SIGNER: &str = "https://atomicdata.dev/properties/signer";
 pub const CREATED_AT: &str = "https://atomicdata.dev/properties/createdAt";
 pub const SIGNATURE: &str = "https://atomicdata.dev/properties/signature";
@@ -65,6 +87,13 @@ pub const COLLECTION_INCLUDE_NESTED: &str =
     "https://atomicdata.dev/properties/collection/includeNested";
 pub const COLLECTION_INCLUDE_EXTERNAL: &str =
     "https://atomicdata.dev/properties/collection/includeExternal";
+pub const COLLECTION_INCLUDE_SUBCLASSES: &str =
+    "https://atomicdata.dev/properties/collection/includeSubclasses";
+pub const COLLECTION_GEO_PROPERTY: &str =
+    "https://atomicdata.dev/properties/collection/geoProperty";
+pub const COLLECTION_BOUNDING_BOX: &str =
+    "https://atomicdata.dev/properties/collection/boundingBox";
+pub const COLLECTION_NEAR: &str = "https://atomicdata.dev/properties/collection/near";
 pub const COLLECTION_PAGE_SIZE: &str = "https://atomicdata.dev/properties/collection/pageSize";
 pub const COLLECTION_SORT_BY: &str = "https://atomicdata.dev/properties/collection/sortBy";
 pub const COLLECTION_SORT_DESC: &str = "https://atomicdata.dev/properties/collection/sortDesc";
@@ -111,6 +140,9 @@ pub const ATTACHMENTS: &str = "https://atomicdata.dev/properties/attachments";
 // ... for ChatRooms and Messages
 pub const MESSAGES: &str = "https://atomicdata.dev/properties/messages";
 pub const NEXT_PAGE: &str = "https://atomicdata.dev/properties/nextPage";
+// ... for Articles
+pub const PUBLISHED_AT: &str = "https://atomicdata.dev/properties/published-at";
+pub const TAGS: &str = "https://atomicdata.dev/properties/tags";
 // ... for Importers
 pub const IMPORTER_URL: &str = "https://atomicdata.dev/properties/importer/url";
 pub const IMPORTER_JSON: &str = "https://atomicdata.dev/properties/importer/json";
@@ -136,6 +168,13 @@ pub const RESOURCE_ARRAY: &str = "https://atomicdata.dev/datatypes/resourceArray
 pub const BOOLEAN: &str = "https://atomicdata.dev/datatypes/boolean";
 pub const DATE: &str = "https://atomicdata.dev/datatypes/date";
 pub const TIMESTAMP: &str = "https://atomicdata.dev/datatypes/timestamp";
+pub const DECIMAL: &str = "https://atomicdata.dev/datatypes/decimal";
+pub const BIG_INT: &str = "https://atomicdata.dev/datatypes/bigInt";
+pub const LANG_STRING: &str = "https://atomicdata.dev/datatypes/langString";
+pub const DURATION: &str = "https://atomicdata.dev/datatypes/duration";
+pub const TIME: &str = "https://atomicdata.dev/datatypes/time";
+pub const DATE_TIME: &str = "https://atomicdata.dev/datatypes/dateTime";
+pub const GEO_POINT: &str = "https://atomicdata.dev/datatypes/geoPoint";
 
 // Methods
 pub const INSERT: &str = "https://atomicdata.dev/methods/insert";
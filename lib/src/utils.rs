@@ -45,3 +45,19 @@ pub fn random_string(n: usize) -> String {
         .collect();
     random_string.to_lowercase()
 }
+
+/// Returns a random (version 4) UUID, such as `9f6e3d0c-1f5a-4c1e-8a0b-3c2d1e0f9a8b`
+pub fn random_uuid() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
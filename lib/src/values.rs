@@ -1,8 +1,15 @@
 //! A value is the part of an Atom that contains the actual information.
 
 use crate::{
-    datatype::match_datatype, datatype::DataType, errors::AtomicResult, resources::PropVals,
-    utils::check_valid_url, Resource,
+    datatype::match_datatype,
+    datatype::DataType,
+    datetime::{parse_date_time, parse_duration, parse_time},
+    errors::AtomicResult,
+    geo::GeoPoint,
+    lang::LangString,
+    resources::PropVals,
+    utils::check_valid_url,
+    Resource,
 };
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -26,6 +33,20 @@ pub enum Value {
     Resource(Box<Resource>),
     Boolean(bool),
     Unsupported(UnsupportedValue),
+    // New variants are added at the end, because the order is part of the stored (bincode) format.
+    /// Decimal number with arbitrary precision, without leading or trailing zeros
+    Decimal(String),
+    /// Integer of any size, without leading zeros
+    BigInt(String),
+    GeoPoint(GeoPoint),
+    /// Text in one or more languages
+    LangString(LangString),
+    /// ISO 8601 duration, as it was written
+    Duration(String),
+    /// Local time of day, as it was written
+    Time(String),
+    /// ISO 8601 date and time with a time zone, as it was written
+    DateTime(String),
 }
 
 /// A resource in a JSON-AD body can be any of these
@@ -51,6 +72,10 @@ pub struct UnsupportedValue {
 pub const SLUG_REGEX: &str = r"^[a-z0-9]+(?:-[a-z0-9]+)*$";
 /// YYYY-MM-DD
 pub const DATE_REGEX: &str = r"^\d{4}\-(0[1-9]|1[012])\-(0[1-9]|[12][0-9]|3[01])$";
+/// Decimal notation with an optional sign and fraction, no exponent
+pub const DECIMAL_REGEX: &str = r"^[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)$";
+/// Digits with an optional sign
+pub const BIG_INT_REGEX: &str = r"^[+-]?[0-9]+$";
 
 impl Value {
     /// Check if the value `q_val` is present in `val`
@@ -72,6 +97,13 @@ impl Value {
             Value::Date(_) => DataType::Date,
             Value::Integer(_) => DataType::Integer,
             Value::Float(_) => DataType::Float,
+            Value::Decimal(_) => DataType::Decimal,
+            Value::BigInt(_) => DataType::BigInt,
+            Value::GeoPoint(_) => DataType::GeoPoint,
+            Value::LangString(_) => DataType::LangString,
+            Value::Duration(_) => DataType::Duration,
+            Value::Time(_) => DataType::Time,
+            Value::DateTime(_) => DataType::DateTime,
             Value::Markdown(_) => DataType::Markdown,
             Value::ResourceArray(_) => DataType::ResourceArray,
             Value::Slug(_) => DataType::Slug,
@@ -97,6 +129,42 @@ impl Value {
                 let val: f64 = value.parse()?;
                 Ok(Value::Float(val))
             }
+            DataType::Decimal => {
+                let re = Regex::new(DECIMAL_REGEX).unwrap();
+                if re.is_match(value) {
+                    return Ok(Value::Decimal(normalize_decimal(value)));
+                }
+                Err(format!(
+                    "Not a valid decimal: {}. Needs to be a number like -12.50, without exponent.",
+                    value
+                )
+                .into())
+            }
+            DataType::BigInt => {
+                let re = Regex::new(BIG_INT_REGEX).unwrap();
+                if re.is_match(value) {
+                    return Ok(Value::BigInt(normalize_decimal(value)));
+                }
+                Err(format!(
+                    "Not a valid big integer: {}. Only digits are allowed.",
+                    value
+                )
+                .into())
+            }
+            DataType::GeoPoint => Ok(Value::GeoPoint(GeoPoint::parse(value)?)),
+            DataType::LangString => Ok(Value::LangString(LangString::parse(value)?)),
+            DataType::Duration => {
+                parse_duration(value)?;
+                Ok(Value::Duration(value.into()))
+            }
+            DataType::Time => {
+                parse_time(value)?;
+                Ok(Value::Time(value.into()))
+            }
+            DataType::DateTime => {
+                parse_date_time(value)?;
+                Ok(Value::DateTime(value.into()))
+            }
             DataType::String => Ok(Value::String(value.into())),
             DataType::Markdown => Ok(Value::Markdown(value.into())),
             DataType::Slug => {
@@ -219,6 +287,17 @@ impl Value {
         }
     }
 
+    /// Returns a Float, if the Atom is a number.
+    pub fn to_float(&self) -> AtomicResult<f64> {
+        match self {
+            Value::Float(float) => Ok(float.to_owned()),
+            Value::Timestamp(int) | Value::Integer(int) => Ok(*int as f64),
+            _ => self.to_string().parse::<f64>().map_err(|e| {
+                format!("Value {} cannot be converted into float. {}", self, e).into()
+            }),
+        }
+    }
+
     /// Returns a PropVals Hashmap, if the Atom is a NestedResource
     pub fn to_nested(&self) -> AtomicResult<&PropVals> {
         if let Value::NestedResource(SubResource::Nested(nested)) = self {
@@ -231,6 +310,12 @@ impl Value {
     pub fn to_sortable_string(&self) -> SortableValue {
         match self {
             Value::ResourceArray(arr) => arr.len().to_string(),
+            Value::Decimal(val) | Value::BigInt(val) => sortable_decimal(val),
+            Value::LangString(texts) => texts.first().to_string(),
+            // Sorted by their length or moment in time, so date-times in different time zones compare correctly
+            Value::Duration(val) => sortable_nanos(parse_duration(val), val),
+            Value::Time(val) => sortable_nanos(parse_time(val), val),
+            Value::DateTime(val) => sortable_nanos(parse_date_time(val), val),
             other => other.to_string(),
         }
     }
@@ -252,6 +337,79 @@ impl Value {
     }
 }
 
+/// Removes the plus sign, leading zeros and trailing zeros of a number in decimal notation,
+/// so that equal numbers are equal strings.
+fn normalize_decimal(value: &str) -> String {
+    let (negative, unsigned) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let int = int.trim_start_matches('0');
+    let frac = frac.trim_end_matches('0');
+    if int.is_empty() && frac.is_empty() {
+        return "0".into();
+    }
+    let mut normalized = String::new();
+    if negative {
+        normalized.push('-');
+    }
+    normalized.push_str(if int.is_empty() { "0" } else { int });
+    if !frac.is_empty() {
+        normalized.push('.');
+        normalized.push_str(frac);
+    }
+    normalized
+}
+
+/// Offset of the exponent in [sortable_decimal], so that negative exponents still sort as four digits.
+const EXPONENT_OFFSET: i64 = 5000;
+
+/// Encodes a normalized decimal number so that lexicographic order matches numeric order.
+/// Starts with `1` for negative numbers, `2` for zero and `3` for positive numbers,
+/// followed by the exponent as four digits and the significant digits.
+/// Negative numbers invert the exponent and the digits, and end with `~` so that longer numbers sort first.
+fn sortable_decimal(normalized: &str) -> SortableValue {
+    let (negative, unsigned) = match normalized.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, normalized),
+    };
+    let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    // The number is 0.{digits} * 10^exponent
+    let (exponent, digits) = if int != "0" {
+        let digits = format!("{}{}", int, frac);
+        (int.len() as i64, digits.trim_end_matches('0').to_string())
+    } else {
+        let significant = frac.trim_start_matches('0');
+        if significant.is_empty() {
+            return "2".into();
+        }
+        (
+            -((frac.len() - significant.len()) as i64),
+            significant.to_string(),
+        )
+    };
+    let exponent = (exponent + EXPONENT_OFFSET).clamp(0, 9999);
+    if negative {
+        let inverted: String = digits
+            .chars()
+            .map(|c| char::from(b'9' - (c as u8 - b'0')))
+            .collect();
+        format!("1{:04}{}~", 9999 - exponent, inverted)
+    } else {
+        format!("3{:04}{}", exponent, digits)
+    }
+}
+
+/// Encodes the nanoseconds of a [DataType::Duration], [DataType::Time] or [DataType::DateTime] with [sortable_decimal].
+/// Falls back to the value itself if it can't be parsed.
+fn sortable_nanos(nanos: AtomicResult<i128>, value: &str) -> SortableValue {
+    match nanos {
+        Ok(nanos) => sortable_decimal(&nanos.to_string()),
+        Err(_) => value.to_string(),
+    }
+}
+
 /// A value that is meant for checking reference indexes.
 /// short. Vectors of subjects are turned into individual ReferenceStrings.
 pub type ReferenceString = String;
@@ -361,6 +519,11 @@ impl fmt::Display for Value {
             Value::Date(s) => write!(f, "{}", s),
             Value::Integer(i) => write!(f, "{}", i),
             Value::Float(float) => write!(f, "{}", float),
+            Value::Decimal(s) => write!(f, "{}", s),
+            Value::BigInt(s) => write!(f, "{}", s),
+            Value::GeoPoint(point) => write!(f, "{}", point),
+            Value::LangString(texts) => write!(f, "{}", texts),
+            Value::Duration(s) | Value::Time(s) | Value::DateTime(s) => write!(f, "{}", s),
             Value::Markdown(i) => write!(f, "{}", i),
             Value::ResourceArray(v) => {
                 let mut s: String = String::new();
@@ -451,6 +614,106 @@ mod test {
         assert!(converted.to_string() == "8");
     }
 
+    #[test]
+    fn decimals_and_big_ints() {
+        let decimal = Value::new("+0012.5000", &DataType::Decimal).unwrap();
+        assert_eq!(decimal.to_string(), "12.5");
+        assert_eq!(decimal.datatype(), DataType::Decimal);
+        assert_eq!(
+            Value::new("-.50", &DataType::Decimal).unwrap().to_string(),
+            "-0.5"
+        );
+        assert_eq!(
+            Value::new("-0.0", &DataType::Decimal).unwrap().to_string(),
+            "0"
+        );
+        let big = "123456789012345678901234567890";
+        assert_eq!(Value::new(big, &DataType::BigInt).unwrap().to_string(), big);
+        Value::new("1e10", &DataType::Decimal).unwrap_err();
+        Value::new("1.5", &DataType::BigInt).unwrap_err();
+        // Only ASCII digits
+        Value::new("١٢", &DataType::Decimal).unwrap_err();
+        Value::new("١٢", &DataType::BigInt).unwrap_err();
+
+        let sorted = [
+            "-123456789012345678901234567890",
+            "-10",
+            "-1.5",
+            "-1",
+            "-0.05",
+            "0",
+            "0.0001",
+            "0.05",
+            "1",
+            "1.5",
+            "9.99",
+            "10",
+            "123456789012345678901234567890",
+        ];
+        let sortables: Vec<SortableValue> = sorted
+            .iter()
+            .map(|v| {
+                Value::new(v, &DataType::Decimal)
+                    .unwrap()
+                    .to_sortable_string()
+            })
+            .collect();
+        let mut resorted = sortables.clone();
+        resorted.sort();
+        assert_eq!(sortables, resorted);
+        assert_eq!(
+            Value::new("10", &DataType::BigInt)
+                .unwrap()
+                .to_sortable_string(),
+            Value::new("10.00", &DataType::Decimal)
+                .unwrap()
+                .to_sortable_string()
+        );
+    }
+
+    #[test]
+    fn durations_times_and_date_times() {
+        let date_time = Value::new("2024-05-01T09:30:00+02:00", &DataType::DateTime).unwrap();
+        assert_eq!(date_time.to_string(), "2024-05-01T09:30:00+02:00");
+        assert_eq!(date_time.datatype(), DataType::DateTime);
+        Value::new("2024-05-01T09:30:00", &DataType::DateTime).unwrap_err();
+        Value::new("2024-05-01", &DataType::DateTime).unwrap_err();
+        Value::new("09:30:00", &DataType::Time).unwrap();
+        Value::new("9:30", &DataType::Time).unwrap_err();
+        Value::new("PT1H30M", &DataType::Duration).unwrap();
+        Value::new("1 hour", &DataType::Duration).unwrap_err();
+
+        let assert_sorted = |datatype: DataType, sorted: &[&str]| {
+            let sortables: Vec<SortableValue> = sorted
+                .iter()
+                .map(|v| Value::new(v, &datatype).unwrap().to_sortable_string())
+                .collect();
+            let mut resorted = sortables.clone();
+            resorted.sort();
+            assert_eq!(sortables, resorted, "{:?} are not sorted", sorted);
+        };
+        assert_sorted(
+            DataType::DateTime,
+            &[
+                "1969-07-20T20:17:40Z",
+                "2024-05-01T09:30:00+02:00",
+                "2024-05-01T08:00:00Z",
+                "2024-05-01T03:30:00.25-05:00",
+                "2024-05-01T08:30:00.5Z",
+            ],
+        );
+        assert_sorted(
+            DataType::Time,
+            &["00:00:00", "09:05:00", "09:30:00", "09:30:00.1", "23:59:59"],
+        );
+        assert_sorted(
+            DataType::Duration,
+            &[
+                "-P1D", "-PT1S", "PT0S", "PT0.5S", "PT59M", "PT1H", "P1D", "P1M", "P1Y",
+            ],
+        );
+    }
+
     #[test]
     fn fails_wrong_values() {
         Value::new("no int", &DataType::Integer).unwrap_err();
@@ -0,0 +1,817 @@
+//! Imports a vault of Markdown files, such as an Obsidian vault or a Hugo `content` folder, from a directory or a zip file.
+//!
+//! - Every Markdown file becomes an instance of a Class ([urls::ARTICLE] by default) below the `importer` of the [ParseOpts].
+//!   The body is the `description`, the `title` in the front matter (or the file name) is the `name`.
+//! - Every folder becomes a resource too, so the hierarchy is preserved through `parent`.
+//!   An `index.md`, `_index.md` or `README.md` in a folder is used as the folder itself. Other folders list their contents.
+//! - YAML front matter keys are mapped to Properties by shortname, like [crate::parse::parse_json_with_class].
+//!   `title` maps to `name` and `date` to `published-at`. Keys that can't be mapped are skipped and reported.
+//! - `[[wikilinks]]` become links to the subjects of the imported resources, both in the body and in front matter values.
+//!
+//! Subjects are derived from the paths of the files, so importing the same vault again updates the existing resources.
+//! Vaults are limited to [MAX_VAULT_FILES] files of [MAX_VAULT_FILE_SIZE] bytes, and [MAX_VAULT_SIZE] bytes in total.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::{
+    datatype::DataType,
+    datetime,
+    errors::AtomicResult,
+    parse::{
+        generate_id_from_local_id, parse_plain_json_objects, resolve_plain_json_key, ParseOpts,
+    },
+    schema::Property,
+    urls, Resource, Storelike, Value,
+};
+
+/// Files in a folder that describe the folder itself, in order of preference.
+const INDEX_FILES: [&str; 3] = ["index", "_index", "readme"];
+/// Front matter keys that are commonly used in vaults, and the shortnames they map to.
+const KEY_ALIASES: [(&str, &str); 2] = [("title", "name"), ("date", "published-at")];
+/// Maximum number of entries (including folders and other files) in a vault.
+pub const MAX_VAULT_FILES: usize = 10_000;
+/// Maximum size of a single Markdown file, in bytes.
+pub const MAX_VAULT_FILE_SIZE: u64 = 10 * 1024 * 1024;
+/// Maximum size of all Markdown files together, in bytes. Zip files are checked after decompression.
+pub const MAX_VAULT_SIZE: u64 = 100 * 1024 * 1024;
+
+/// A Markdown file in the vault.
+#[derive(Debug, Clone)]
+pub struct VaultFile {
+    /// Path relative to the root of the vault, separated by `/`, e.g. `notes/My note.md`
+    pub path: String,
+    pub content: String,
+}
+
+/// The result of a vault import. Problems with single keys or links do not stop the import.
+#[derive(Debug, Default, Serialize)]
+pub struct VaultImportReport {
+    /// Subjects of the imported Resources, folders first
+    pub imported: Vec<String>,
+    pub warnings: Vec<VaultWarning>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VaultWarning {
+    /// Path of the file in the vault
+    pub path: String,
+    pub message: String,
+}
+
+/// A file or folder that becomes a Resource.
+struct Entry {
+    /// Path without the `.md` extension, e.g. `notes/My note`
+    path: String,
+    subject: String,
+    parent: String,
+    name: String,
+    /// `None` for folders without an index file
+    file: Option<VaultFile>,
+    children: Vec<usize>,
+}
+
+/// Reads all Markdown files in the directory and its subdirectories.
+/// Hidden files and folders (e.g. `.obsidian`) and symlinks are skipped.
+pub fn read_vault_dir(dir: &std::path::Path) -> AtomicResult<Vec<VaultFile>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    let mut count = 0;
+    let mut total_size = 0;
+    while let Some(current) = dirs.pop() {
+        let entries = std::fs::read_dir(&current)
+            .map_err(|e| format!("Unable to read directory {:?}: {}", current, e))?;
+        for entry in entries {
+            let entry = entry?;
+            count += 1;
+            check_file_count(count)?;
+            // Unlike `Path::is_dir`, this does not follow symlinks, which could point outside the vault or form a cycle
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                continue;
+            }
+            let path = entry.path();
+            let relative = path
+                .strip_prefix(dir)
+                .map_err(|e| format!("Invalid path {:?}: {}", path, e))?
+                .iter()
+                .map(|s| s.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if file_type.is_dir() {
+                if !is_hidden(&relative) {
+                    dirs.push(path);
+                }
+            } else if is_markdown(&relative) {
+                let file = std::fs::File::open(&path)
+                    .map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+                files.push(VaultFile {
+                    content: read_limited(file, &relative, &mut total_size)?,
+                    path: relative,
+                });
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Reads all Markdown files in a zip archive. Hidden files and folders are skipped.
+pub fn read_vault_zip(reader: impl std::io::Read + std::io::Seek) -> AtomicResult<Vec<VaultFile>> {
+    let mut archive =
+        zip::ZipArchive::new(reader).map_err(|e| format!("Unable to read zip file: {}", e))?;
+    check_file_count(archive.len())?;
+    let mut files = Vec::new();
+    let mut total_size = 0;
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index(i)
+            .map_err(|e| format!("Unable to read zip file: {}", e))?;
+        let Some(path) = file.enclosed_name().map(|p| {
+            p.iter()
+                .map(|s| s.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        }) else {
+            continue;
+        };
+        if file.is_dir() || !is_markdown(&path) {
+            continue;
+        }
+        let content = read_limited(file, &path, &mut total_size)?;
+        files.push(VaultFile { path, content });
+    }
+    Ok(files)
+}
+
+fn check_file_count(count: usize) -> AtomicResult<()> {
+    if count > MAX_VAULT_FILES {
+        return Err(format!(
+            "The vault contains more than {} files and folders",
+            MAX_VAULT_FILES
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Reads a file as UTF-8, and throws if it exceeds [MAX_VAULT_FILE_SIZE], or if the `total_size` exceeds [MAX_VAULT_SIZE].
+/// Stops reading at the limit, as the size in a zip file header can't be trusted.
+fn read_limited(
+    reader: impl std::io::Read,
+    path: &str,
+    total_size: &mut u64,
+) -> AtomicResult<String> {
+    use std::io::Read;
+    let mut bytes = Vec::new();
+    reader
+        .take(MAX_VAULT_FILE_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Unable to read {}: {}", path, e))?;
+    let size = bytes.len() as u64;
+    if size > MAX_VAULT_FILE_SIZE {
+        return Err(format!(
+            "{} is larger than the maximum of {} bytes",
+            path, MAX_VAULT_FILE_SIZE
+        )
+        .into());
+    }
+    *total_size += size;
+    if *total_size > MAX_VAULT_SIZE {
+        return Err(format!(
+            "The Markdown files in the vault are larger than the maximum of {} bytes",
+            MAX_VAULT_SIZE
+        )
+        .into());
+    }
+    String::from_utf8(bytes).map_err(|e| format!("Unable to read {}: {}", path, e).into())
+}
+
+fn is_hidden(path: &str) -> bool {
+    path.split('/').any(|segment| segment.starts_with('.'))
+}
+
+fn is_markdown(path: &str) -> bool {
+    !is_hidden(path) && path.to_lowercase().ends_with(".md")
+}
+
+/// Imports the files as instances of the `class` (or [urls::ARTICLE]), below the `importer` of the `parse_opts`.
+#[tracing::instrument(skip(files, store))]
+pub fn import_vault(
+    files: Vec<VaultFile>,
+    class: Option<&str>,
+    store: &impl Storelike,
+    parse_opts: &ParseOpts,
+) -> AtomicResult<VaultImportReport> {
+    let importer = parse_opts
+        .importer
+        .as_ref()
+        .ok_or("A parent is required to import a vault")?;
+    let class = class.unwrap_or(urls::ARTICLE);
+    let mut report = VaultImportReport::default();
+    let entries = build_entries(files, importer);
+    let links = link_index(&entries);
+
+    let mut template = Resource::new(class.into());
+    template.set_class(class);
+    let mut properties: HashMap<String, Option<Property>> = HashMap::new();
+    let mut objects = Vec::new();
+    for entry in entries.iter() {
+        let mut warn = |message: String| {
+            report.warnings.push(VaultWarning {
+                path: entry
+                    .file
+                    .as_ref()
+                    .map(|f| f.path.clone())
+                    .unwrap_or_else(|| entry.path.clone()),
+                message,
+            })
+        };
+        let (front_matter, body) = match &entry.file {
+            Some(file) => split_front_matter(&file.content),
+            None => (None, folder_listing(entry, &entries)),
+        };
+        let mut object = Map::new();
+        object.insert("@id".into(), entry.subject.clone().into());
+        object.insert(urls::NAME.into(), entry.name.clone().into());
+        object.insert(urls::PARENT.into(), entry.parent.clone().into());
+
+        if let Some(yaml) = front_matter {
+            match serde_yaml::from_str::<JsonValue>(yaml) {
+                Ok(JsonValue::Object(map)) => {
+                    for (key, value) in map {
+                        let key = KEY_ALIASES
+                            .iter()
+                            .find(|(alias, _)| *alias == key)
+                            .map(|(_, shortname)| shortname.to_string())
+                            .unwrap_or(key);
+                        let property = properties
+                            .entry(key.clone())
+                            .or_insert_with(|| resolve_plain_json_key(&key, &template, store).ok())
+                            .clone();
+                        let Some(property) = property else {
+                            warn(format!("Skipped '{}', it is not a Property", key));
+                            continue;
+                        };
+                        if property.subject == urls::DESCRIPTION {
+                            warn("Skipped 'description', the body is used instead".into());
+                            continue;
+                        }
+                        match front_matter_value(value, &property, &links) {
+                            Ok(value) => {
+                                object.insert(property.subject, value);
+                            }
+                            Err(e) => warn(format!("Skipped '{}': {}", key, e)),
+                        }
+                    }
+                }
+                Ok(JsonValue::Null) => {}
+                Ok(_) => warn("Front matter is not a map of keys and values".into()),
+                Err(e) => warn(format!("Invalid front matter: {}", e)),
+            }
+        }
+
+        let (body, unresolved) = replace_wikilinks(&body, &links);
+        for target in unresolved {
+            warn(format!(
+                "Link [[{}]] does not point to a file in the vault",
+                target
+            ));
+        }
+        object.insert(urls::DESCRIPTION.into(), body.trim().into());
+        objects.push(JsonValue::Object(object));
+    }
+
+    let resources = parse_plain_json_objects(objects, class, store, parse_opts)?;
+    report.imported = resources.iter().map(|r| r.get_subject().clone()).collect();
+    Ok(report)
+}
+
+/// Creates an Entry for every folder and file, with folders before their contents.
+/// If paths have the same slug, the first one (sorted by path) gets the slug.
+/// The others get a suffix derived from their path, so their subjects don't depend on the other files.
+fn build_entries(mut files: Vec<VaultFile>, importer: &str) -> Vec<Entry> {
+    files.sort_by(|a, b| strip_md(&a.path).cmp(strip_md(&b.path)));
+    let mut entries: Vec<Entry> = Vec::new();
+    // Paths (lowercase) to the index in `entries`
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut used_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    let mut add = |entries: &mut Vec<Entry>, path: &str, file: Option<VaultFile>| -> usize {
+        let (folder, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let parent_index = if folder.is_empty() {
+            None
+        } else {
+            index.get(&folder.to_lowercase()).copied()
+        };
+        let parent = match parent_index {
+            Some(i) => entries[i].subject.clone(),
+            None => importer.to_string(),
+        };
+        let mut local_id = path.split('/').map(slugify).collect::<Vec<_>>().join("/");
+        if !used_ids.insert(local_id.clone()) {
+            local_id = format!("{}-{}", local_id, path_hash(path));
+            used_ids.insert(local_id.clone());
+        }
+        entries.push(Entry {
+            path: path.into(),
+            subject: generate_id_from_local_id(importer, &local_id),
+            parent,
+            name: name.into(),
+            file,
+            children: Vec::new(),
+        });
+        let i = entries.len() - 1;
+        if let Some(parent_index) = parent_index {
+            entries[parent_index].children.push(i);
+        }
+        index.insert(path.to_lowercase(), i);
+        i
+    };
+
+    // Folders first, so they exist before their children are saved
+    let mut folders: Vec<String> = Vec::new();
+    for file in files.iter() {
+        let mut folder = String::new();
+        for segment in file
+            .path
+            .split('/')
+            .rev()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            if !folder.is_empty() {
+                folder.push('/');
+            }
+            folder.push_str(segment);
+            if !folders.contains(&folder) {
+                folders.push(folder.clone());
+            }
+        }
+    }
+    for folder in folders.iter() {
+        add(&mut entries, folder, None);
+    }
+
+    for file in files {
+        let path = strip_md(&file.path).to_string();
+        let (folder, name) = path.rsplit_once('/').unwrap_or(("", &path));
+        if !folder.is_empty() && INDEX_FILES.contains(&name.to_lowercase().as_str()) {
+            if let Some(i) = entries
+                .iter()
+                .position(|e| e.path.eq_ignore_ascii_case(folder))
+            {
+                if entries[i].file.is_none() {
+                    entries[i].file = Some(file);
+                    continue;
+                }
+            }
+        }
+        add(&mut entries, &path, Some(file));
+    }
+
+    // Titles from the front matter
+    for entry in entries.iter_mut() {
+        if let Some(title) = entry
+            .file
+            .as_ref()
+            .and_then(|f| split_front_matter(&f.content).0)
+            .and_then(|yaml| serde_yaml::from_str::<JsonValue>(yaml).ok())
+            .and_then(|fm| fm.get("title").and_then(|t| t.as_str()).map(String::from))
+        {
+            entry.name = title;
+        }
+    }
+    entries
+}
+
+/// Maps link targets (lowercase) to subjects. Both the file name and the path can be used.
+/// If multiple files have the same name, the first one (sorted by path) is used, like Obsidian does.
+fn link_index(entries: &[Entry]) -> HashMap<String, String> {
+    let mut links = HashMap::new();
+    for entry in entries.iter() {
+        let path = entry.path.to_lowercase();
+        let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+        links.insert(path, entry.subject.clone());
+        links.entry(name).or_insert_with(|| entry.subject.clone());
+    }
+    links
+}
+
+fn strip_md(path: &str) -> &str {
+    if path.to_lowercase().ends_with(".md") {
+        &path[..path.len() - 3]
+    } else {
+        path
+    }
+}
+
+/// A short hash of the path, to tell apart paths with the same slug.
+fn path_hash(path: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, path.as_bytes());
+    digest.as_ref()[..4]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Lowercase letters and numbers, with dashes in between.
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "untitled".into()
+    } else {
+        slug.into()
+    }
+}
+
+/// Splits the YAML front matter (between two `---` lines at the start of the file) from the body.
+fn split_front_matter(content: &str) -> (Option<&str>, String) {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content.into());
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), rest[offset + line.len()..].into());
+        }
+        offset += line.len();
+    }
+    (None, content.into())
+}
+
+/// A Markdown list with links to the contents of a folder.
+fn folder_listing(entry: &Entry, entries: &[Entry]) -> String {
+    entry
+        .children
+        .iter()
+        .map(|&i| format!("- [{}]({})\n", entries[i].name, entries[i].subject))
+        .collect()
+}
+
+/// Returns the target of a `[[target#heading|alias]]` link.
+fn wikilink_target(link: &str) -> &str {
+    let link = link.split('|').next().unwrap_or(link);
+    let link = link.split('#').next().unwrap_or(link);
+    strip_md(link.trim())
+}
+
+/// Replaces `[[wikilinks]]` with Markdown links to the subjects. Returns the targets that could not be found.
+/// Code blocks and code spans are left as they are.
+fn replace_wikilinks(body: &str, links: &HashMap<String, String>) -> (String, Vec<String>) {
+    // Code spans are matched too, so that wikilinks inside them are skipped
+    let re = regex::Regex::new(r"`[^`]*`|!?\[\[([^\[\]]+)\]\]").expect("Invalid wikilink regex");
+    let mut unresolved = Vec::new();
+    let mut replaced = String::with_capacity(body.len());
+    let mut fence: Option<&str> = None;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            (None, None) => {
+                replaced.push_str(&replace_wikilinks_in_line(
+                    &re,
+                    line,
+                    links,
+                    &mut unresolved,
+                ));
+                continue;
+            }
+            _ => {}
+        }
+        replaced.push_str(line);
+    }
+    (replaced, unresolved)
+}
+
+fn replace_wikilinks_in_line(
+    re: &regex::Regex,
+    line: &str,
+    links: &HashMap<String, String>,
+    unresolved: &mut Vec<String>,
+) -> String {
+    re.replace_all(line, |caps: &regex::Captures| {
+        let Some(link) = caps.get(1).map(|m| m.as_str()) else {
+            return caps[0].to_string();
+        };
+        let target = wikilink_target(link);
+        let text = link
+            .split_once('|')
+            .map(|(_, alias)| alias.trim())
+            .unwrap_or(target);
+        match links.get(&target.to_lowercase()) {
+            Some(subject) => format!("[{}]({})", text, subject),
+            None => {
+                unresolved.push(target.to_string());
+                caps[0].to_string()
+            }
+        }
+    })
+    .into_owned()
+}
+
+/// Converts a front matter value to plain JSON for the Property.
+fn front_matter_value(
+    value: JsonValue,
+    property: &Property,
+    links: &HashMap<String, String>,
+) -> AtomicResult<JsonValue> {
+    let resolve = |value: JsonValue| -> AtomicResult<JsonValue> {
+        match value {
+            JsonValue::String(s) => {
+                let s = s.trim();
+                if let Some(link) = s.strip_prefix("[[").and_then(|s| s.strip_suffix("]]")) {
+                    let target = wikilink_target(link);
+                    return links
+                        .get(&target.to_lowercase())
+                        .map(|subject| subject.clone().into())
+                        .ok_or_else(|| {
+                            format!("[[{}]] is not a file in the vault", target).into()
+                        });
+                }
+                if crate::utils::check_valid_url(s).is_err() {
+                    return Err(format!("'{}' is not a URL or a [[wikilink]]", s).into());
+                }
+                Ok(s.into())
+            }
+            other => Err(format!("{} is not a URL or a [[wikilink]]", other).into()),
+        }
+    };
+    match &property.data_type {
+        DataType::AtomicUrl => resolve(value),
+        DataType::ResourceArray => {
+            let items = match value {
+                JsonValue::Array(items) => items,
+                single => vec![single],
+            };
+            Ok(JsonValue::Array(
+                items
+                    .into_iter()
+                    .map(resolve)
+                    .collect::<AtomicResult<_>>()?,
+            ))
+        }
+        DataType::Timestamp => match value {
+            JsonValue::String(s) => Ok(parse_date_time(&s)
+                .ok_or(format!("'{}' is not a date", s))?
+                .into()),
+            number @ JsonValue::Number(_) => Ok(number),
+            other => Err(format!("{} is not a date", other).into()),
+        },
+        datatype => {
+            let text = match &value {
+                JsonValue::String(s) => s.clone(),
+                JsonValue::Number(n) => n.to_string(),
+                JsonValue::Bool(b) => b.to_string(),
+                other => return Err(format!("{} is not a {}", other, datatype).into()),
+            };
+            Value::new(&text, datatype)?;
+            Ok(text.into())
+        }
+    }
+}
+
+/// Parses `2023-01-31`, `2023-01-31 12:00` and date-times with a time zone like `2023-01-31T12:00:00+02:00` as a UNIX timestamp in milliseconds.
+/// Dates, and dates with a time but without a time zone, are treated as UTC.
+fn parse_date_time(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if let Ok(nanos) = datetime::parse_date_time(s) {
+        return i64::try_from(nanos.div_euclid(1_000_000)).ok();
+    }
+    let (date, time) = match s.split_once(' ') {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let number = |digits: &str, len: usize| -> Option<i64> {
+        if digits.len() != len || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let mut parts = date.split('-');
+    let year = number(parts.next()?, 4)?;
+    let month = number(parts.next()?, 2)?;
+    let day = number(parts.next()?, 2)?;
+    if parts.next().is_some()
+        || !(1..=12).contains(&month)
+        || day < 1
+        || day > datetime::days_in_month(year, month)
+    {
+        return None;
+    }
+    let mut seconds = datetime::days_from_civil(year, month, day) * 86_400;
+    if let Some(time) = time {
+        let (hours, minutes) = time.split_once(':')?;
+        let (hours, minutes) = (number(hours, 2)?, number(minutes, 2)?);
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        seconds += hours * 3600 + minutes * 60;
+    }
+    Some(seconds * 1000)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{agents::ForAgent, parse::SaveOpts, Store};
+
+    fn file(path: &str, content: &str) -> VaultFile {
+        VaultFile {
+            path: path.into(),
+            content: content.into(),
+        }
+    }
+
+    #[test]
+    fn import_markdown_vault() {
+        let store = Store::init().unwrap();
+        store.populate().unwrap();
+        let agent = store.create_agent(None).unwrap();
+        store.set_default_agent(agent.clone());
+        let importer = format!("{}/vault", store.get_server_url());
+        let parse_opts = ParseOpts {
+            importer: Some(importer.clone()),
+            for_agent: ForAgent::Sudo,
+            signer: Some(agent),
+            save: SaveOpts::Commit,
+            overwrite_outside: false,
+        };
+        let files = vec![
+            file(
+                "Notes/Daily note.md",
+                "---\ntitle: Monday\ndate: 2023-01-02\naliases: [mon]\n---\nSee [[Ideas|my ideas]] and [[Missing]].",
+            ),
+            file("Notes/_index.md", "All my notes"),
+            file("Ideas.md", "# Ideas\n\nBack to [[notes/daily note#Tasks]]"),
+            file(".obsidian/workspace.md", "Hidden"),
+        ];
+        let files: Vec<VaultFile> = files.into_iter().filter(|f| is_markdown(&f.path)).collect();
+        let report = import_vault(files, None, &store, &parse_opts).unwrap();
+        assert_eq!(report.imported.len(), 3, "{:?}", report.warnings);
+
+        let folder = store.get_resource(&format!("{}/notes", importer)).unwrap();
+        assert_eq!(folder.get(urls::NAME).unwrap().to_string(), "Notes");
+        assert_eq!(
+            folder.get(urls::DESCRIPTION).unwrap().to_string(),
+            "All my notes"
+        );
+        assert_eq!(folder.get(urls::PARENT).unwrap().to_string(), importer);
+
+        let daily_subject = format!("{}/notes/daily-note", importer);
+        let ideas_subject = format!("{}/ideas", importer);
+        let daily = store.get_resource(&daily_subject).unwrap();
+        assert_eq!(daily.get(urls::NAME).unwrap().to_string(), "Monday");
+        assert_eq!(daily.get_main_class().unwrap(), urls::ARTICLE);
+        assert_eq!(
+            daily.get(urls::PARENT).unwrap().to_string(),
+            folder.get_subject().as_str()
+        );
+        assert_eq!(
+            daily.get(urls::PUBLISHED_AT).unwrap().to_int().unwrap(),
+            1_672_617_600_000
+        );
+        assert_eq!(
+            daily.get(urls::DESCRIPTION).unwrap().to_string(),
+            format!("See [my ideas]({}) and [[Missing]].", ideas_subject)
+        );
+        let ideas = store.get_resource(&ideas_subject).unwrap();
+        assert!(ideas
+            .get(urls::DESCRIPTION)
+            .unwrap()
+            .to_string()
+            .contains(&format!("[notes/daily note]({})", daily_subject)));
+
+        let warnings: Vec<&str> = report.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings.contains(&"Skipped 'aliases', it is not a Property"));
+        assert!(warnings.contains(&"Link [[Missing]] does not point to a file in the vault"));
+    }
+
+    #[test]
+    fn folders_without_index_list_their_contents() {
+        let entries = build_entries(
+            vec![file("a/b/Note one.md", ""), file("a/b/Note one!.md", "")],
+            "https://example.com/i",
+        );
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["a", "a/b", "a/b/Note one", "a/b/Note one!"]);
+        let suffixed = format!(
+            "https://example.com/i/a/b/note-one-{}",
+            path_hash("a/b/Note one!")
+        );
+        assert_eq!(entries[3].subject, suffixed);
+        assert_eq!(
+            folder_listing(&entries[1], &entries),
+            format!(
+                "- [Note one](https://example.com/i/a/b/note-one)\n- [Note one!]({})\n",
+                suffixed
+            )
+        );
+
+        // Other files don't change the subject
+        let entries = build_entries(
+            vec![
+                file("a/b/Note one!.md", ""),
+                file("a/b/Note one.md", ""),
+                file("a/b/Note one?.md", ""),
+            ],
+            "https://example.com/i",
+        );
+        assert_eq!(entries[3].subject, suffixed);
+    }
+
+    #[test]
+    fn wikilinks_in_code_are_kept() {
+        let links = HashMap::from([("note".to_string(), "https://example.com/note".to_string())]);
+        let body =
+            "[[Note]] and `[[Note]]`\n```\n[[Note]]\n```\n~~~md\n```\n[[Note]]\n~~~\nEnd [[Note]]";
+        let (replaced, unresolved) = replace_wikilinks(body, &links);
+        assert!(unresolved.is_empty());
+        assert_eq!(
+            replaced,
+            "[Note](https://example.com/note) and `[[Note]]`\n```\n[[Note]]\n```\n~~~md\n```\n[[Note]]\n~~~\nEnd [Note](https://example.com/note)"
+        );
+    }
+
+    #[test]
+    fn read_zip() {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        zip.start_file("vault/Note.md", options).unwrap();
+        zip.write_all(b"Hello").unwrap();
+        zip.start_file("vault/.obsidian/app.md", options).unwrap();
+        zip.start_file("vault/image.png", options).unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+        let files = read_vault_zip(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "vault/Note.md");
+        assert_eq!(files[0].content, "Hello");
+
+        // Files are not decompressed beyond the limit
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        zip.start_file("big.md", options).unwrap();
+        let chunk = vec![b'a'; 1024 * 1024];
+        for _ in 0..=MAX_VAULT_FILE_SIZE / chunk.len() as u64 {
+            zip.write_all(&chunk).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+        let err = read_vault_zip(std::io::Cursor::new(bytes)).unwrap_err();
+        assert!(err.message.contains("larger than the maximum"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("vault-symlinks-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+        std::fs::write(dir.join("notes/Note.md"), "Hello").unwrap();
+        // A cycle
+        std::os::unix::fs::symlink(&dir, dir.join("notes/loop")).unwrap();
+        let files = read_vault_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "notes/Note.md");
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date_time("1970-01-01"), Some(0));
+        assert_eq!(
+            parse_date_time("2000-03-01T01:02:03Z"),
+            Some(951_872_523_000)
+        );
+        assert_eq!(parse_date_time("1970-01-02 01:30"), Some(91_800_000));
+        // Time zones are taken into account
+        assert_eq!(
+            parse_date_time("2000-03-01T03:02:03.5+02:00"),
+            Some(951_872_523_500)
+        );
+        assert_eq!(parse_date_time("2023-02-31"), None);
+        assert_eq!(parse_date_time("2023-02-29 12:00"), None);
+        assert_eq!(parse_date_time("2024-02-29 24:00"), None);
+        assert_eq!(parse_date_time("not a date"), None);
+    }
+}
@@ -1,6 +1,7 @@
 //! App state, which is accessible from handlers
 use crate::{
-    commit_monitor::CommitMonitor, config::Config, errors::AtomicServerResult, search::SearchState,
+    commit_monitor::CommitMonitor, config::Config, errors::AtomicServerResult,
+    handlers::sitemap::SitemapCache, search::SearchState,
 };
 use atomic_lib::{
     agents::{generate_public_key, Agent},
@@ -23,6 +24,8 @@ pub struct AppState {
     /// The Actix Address of the CommitMonitor, which should receive updates when a commit is applied
     pub commit_monitor: actix::Addr<CommitMonitor>,
     pub search_state: SearchState,
+    /// Public Resources listed in `/sitemap.xml`, cleared by every Commit
+    pub sitemap: SitemapCache,
 }
 
 /// Creates the AppState (the server's context available in Handlers).
@@ -62,9 +65,13 @@ pub fn init(config: Config) -> AtomicServerResult<AppState> {
         crate::commit_monitor::create_commit_monitor(store.clone(), search_state.clone());
 
     let commit_monitor_clone = commit_monitor.clone();
+    let sitemap = SitemapCache::default();
+    let sitemap_clone = sitemap.clone();
 
     // This closure is called every time a Commit is created
     let send_commit = move |commit_response: &CommitResponse| {
+        // Any Commit can change which Resources are public, e.g. by changing the rights of a parent
+        sitemap_clone.invalidate();
         commit_monitor_clone.do_send(crate::actor_messages::CommitMessage {
             commit_response: commit_response.clone(),
         });
@@ -98,6 +105,7 @@ pub fn init(config: Config) -> AtomicServerResult<AppState> {
         config,
         commit_monitor,
         search_state,
+        sitemap,
     })
 }
 
@@ -0,0 +1,294 @@
+//! Creates periodic backups of the store and the uploaded files, and restores them.
+//! Every backup is a folder in [Config::backups_path], named after the UTC time it was created.
+//! It contains a `store` folder, which is a Sled database, and an `uploads` folder.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, NaiveDateTime, Utc};
+
+use crate::{appstate::AppState, config::Config, errors::AtomicServerResult};
+
+/// The format of the backup folder names. Does not contain `:` characters, as these are not allowed on Windows.
+const BACKUP_NAME_FORMAT: &str = "%Y-%m-%dT%H-%M-%SZ";
+const STORE_DIR: &str = "store";
+const UPLOADS_DIR: &str = "uploads";
+/// Added to the name of a backup folder or an uploaded file while it is being written.
+/// Uploaded files with this suffix are not part of backups.
+pub const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Creates a new backup folder containing a snapshot of the store and a copy of the uploaded files.
+/// Removes older backups that fall outside of the retention policy.
+/// Can be called while the server is running.
+pub fn create_backup(store: &atomic_lib::Db, config: &Config) -> AtomicServerResult<PathBuf> {
+    let name = Utc::now().format(BACKUP_NAME_FORMAT).to_string();
+    let backup_path = config.backups_path.join(&name);
+    // The backup is written to a folder that is not named like a backup, and renamed when it's complete.
+    // That way, a failed backup is never restored or counted by the retention policy.
+    let partial_path = config
+        .backups_path
+        .join(format!("{}{}", name, PARTIAL_SUFFIX));
+    let _try_remove_existing = std::fs::remove_dir_all(&partial_path);
+    std::fs::create_dir_all(&partial_path).map_err(|e| {
+        format!(
+            "Failed to create backup directory {:?}. {}",
+            partial_path, e
+        )
+    })?;
+    let count = match write_backup(store, config, &partial_path) {
+        Ok(count) => count,
+        Err(e) => {
+            let _try_remove_partial = std::fs::remove_dir_all(&partial_path);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&partial_path, &backup_path).map_err(|e| {
+        format!(
+            "Failed to move backup {:?} to {:?}. {}",
+            partial_path, backup_path, e
+        )
+    })?;
+    tracing::info!("Backed up {} resources to {:?}", count, backup_path);
+
+    let removed = apply_retention(
+        &config.backups_path,
+        config.opts.backup_keep_daily,
+        config.opts.backup_keep_weekly,
+    )?;
+    for path in removed {
+        tracing::info!("Removed outdated backup {:?}", path);
+    }
+    Ok(backup_path)
+}
+
+/// Writes the store and the uploaded files to the folder, and returns the amount of Resources.
+/// The uploads are copied while writes wait for the snapshot, so the backup has a file for every File resource.
+/// Uploads are renamed from their [PARTIAL_SUFFIX] name when their File resource is saved, see [crate::handlers::upload].
+fn write_backup(store: &atomic_lib::Db, config: &Config, path: &Path) -> AtomicServerResult<usize> {
+    let count = store.snapshot_with(&path.join(STORE_DIR), || {
+        if config.uploads_path.exists() {
+            copy_dir(&config.uploads_path, &path.join(UPLOADS_DIR)).map_err(|e| e.message)?;
+        }
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+/// Creates a backup every `interval_minutes` in a separate thread.
+pub fn spawn_backup_scheduler(appstate: &AppState, interval_minutes: u64) {
+    let appstate = appstate.clone();
+    let interval = std::time::Duration::from_secs(interval_minutes.max(1) * 60);
+    tracing::info!("Creating backups every {} minutes", interval_minutes);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        if let Err(e) = create_backup(&appstate.store, &appstate.config) {
+            tracing::error!("Failed to create backup: {}", e);
+        }
+    });
+}
+
+/// Replaces the store and uploads folders with the ones from the backup at `backup_path`.
+/// If no path is passed, the most recent backup is used.
+/// The existing folders are not removed, but renamed, so they can be recovered.
+/// Fails if another instance of the server is using the store.
+pub fn restore_backup(config: &Config, backup_path: Option<&Path>) -> AtomicServerResult<PathBuf> {
+    let backup_path = match backup_path {
+        Some(path) => path.to_path_buf(),
+        None => list_backups(&config.backups_path)?
+            .pop()
+            .map(|(_, path)| path)
+            .ok_or(format!("No backups found in {:?}", config.backups_path))?,
+    };
+    let backup_store = backup_path.join(STORE_DIR);
+    if !backup_store.exists() {
+        return Err(format!("No store found in backup {:?}", backup_path).into());
+    }
+
+    // Opening the store fails if a running server holds the lock.
+    if config.store_path.exists() {
+        drop(atomic_lib::Db::init(
+            &config.store_path,
+            config.server_url.clone(),
+        )?);
+    }
+
+    let suffix = format!("before-restore-{}", Utc::now().format(BACKUP_NAME_FORMAT));
+    move_aside(&config.store_path, &suffix)?;
+    copy_dir(&backup_store, &config.store_path)?;
+
+    let backup_uploads = backup_path.join(UPLOADS_DIR);
+    if backup_uploads.exists() {
+        move_aside(&config.uploads_path, &suffix)?;
+        copy_dir(&backup_uploads, &config.uploads_path)?;
+    }
+    Ok(backup_path)
+}
+
+/// Removes the backups in `backups_path` that are not needed by the retention policy, and returns their paths.
+pub fn apply_retention(
+    backups_path: &Path,
+    keep_daily: usize,
+    keep_weekly: usize,
+) -> AtomicServerResult<Vec<PathBuf>> {
+    let backups = list_backups(backups_path)?;
+    let dates: Vec<NaiveDateTime> = backups.iter().map(|(date, _)| *date).collect();
+    let keep = backups_to_keep(&dates, keep_daily, keep_weekly);
+    let mut removed = Vec::new();
+    for (i, (_, path)) in backups.into_iter().enumerate() {
+        if !keep[i] {
+            std::fs::remove_dir_all(&path)
+                .map_err(|e| format!("Failed to remove backup {:?}. {}", path, e))?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+/// Returns for every backup date whether it should be kept.
+/// The most recent backup of each of the last `keep_daily` days and of each of the last `keep_weekly` weeks is kept.
+/// The most recent backup is always kept.
+fn backups_to_keep(dates: &[NaiveDateTime], keep_daily: usize, keep_weekly: usize) -> Vec<bool> {
+    let mut keep = vec![false; dates.len()];
+    let mut order: Vec<usize> = (0..dates.len()).collect();
+    // Newest first
+    order.sort_by_key(|i| std::cmp::Reverse(dates[*i]));
+
+    let mut days = Vec::new();
+    let mut weeks = Vec::new();
+    for i in order.iter() {
+        let day = dates[*i].date();
+        if !days.contains(&day) && days.len() < keep_daily {
+            days.push(day);
+            keep[*i] = true;
+        }
+        let week = dates[*i].iso_week();
+        if !weeks.contains(&week) && weeks.len() < keep_weekly {
+            weeks.push(week);
+            keep[*i] = true;
+        }
+    }
+    if let Some(newest) = order.first() {
+        keep[*newest] = true;
+    }
+    keep
+}
+
+/// Returns all backups in the folder, sorted from old to new.
+/// Folders that are not named like a backup are ignored.
+fn list_backups(backups_path: &Path) -> AtomicServerResult<Vec<(NaiveDateTime, PathBuf)>> {
+    let mut backups = Vec::new();
+    if !backups_path.exists() {
+        return Ok(backups);
+    }
+    for entry in std::fs::read_dir(backups_path)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if let Ok(date) = NaiveDateTime::parse_from_str(name, BACKUP_NAME_FORMAT) {
+            backups.push((date, path));
+        }
+    }
+    backups.sort_by_key(|(date, _)| *date);
+    Ok(backups)
+}
+
+/// Renames an existing folder to `{path}-{suffix}`.
+fn move_aside(path: &Path, suffix: &str) -> AtomicServerResult<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut new_name = path.as_os_str().to_owned();
+    new_name.push(format!("-{}", suffix));
+    std::fs::rename(path, &new_name)
+        .map_err(|e| format!("Failed to move {:?} to {:?}. {}", path, new_name, e))?;
+    println!("Moved existing {:?} to {:?}", path, new_name);
+    Ok(())
+}
+
+/// Recursively copies a folder. Skips files that are still being written, see [PARTIAL_SUFFIX].
+fn copy_dir(from: &Path, to: &Path) -> AtomicServerResult<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry
+            .file_name()
+            .to_string_lossy()
+            .ends_with(PARTIAL_SUFFIX)
+        {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {:?}. {}", entry.path(), e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, BACKUP_NAME_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn retention() {
+        let dates = vec![
+            // Older weeks, only the latest one of each week should be kept
+            date("2024-01-01T10-00-00Z"),
+            date("2024-01-03T10-00-00Z"),
+            date("2024-01-09T10-00-00Z"),
+            // Latest of the second week, but outside of the daily range
+            date("2024-01-14T10-00-00Z"),
+            date("2024-01-15T10-00-00Z"),
+            // Hourly backups of today, only the latest one should be kept
+            date("2024-01-16T08-00-00Z"),
+            date("2024-01-16T09-00-00Z"),
+        ];
+        let keep = backups_to_keep(&dates, 2, 3);
+        assert_eq!(keep, vec![false, true, false, true, true, false, true]);
+
+        let keep_none = backups_to_keep(&dates, 0, 0);
+        assert_eq!(keep_none.iter().filter(|k| **k).count(), 1);
+        assert!(keep_none[6], "The newest backup should always be kept");
+    }
+
+    #[test]
+    fn partial_backups_are_ignored() {
+        let backups_path = Path::new(".temp/backups/partial");
+        let _try_remove_existing = std::fs::remove_dir_all(backups_path);
+        let complete = backups_path.join("2024-01-16T08-00-00Z");
+        std::fs::create_dir_all(&complete).unwrap();
+        std::fs::create_dir_all(
+            backups_path.join(format!("2024-01-16T09-00-00Z{}", PARTIAL_SUFFIX)),
+        )
+        .unwrap();
+        let backups = list_backups(backups_path).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].1, complete);
+    }
+
+    #[test]
+    fn partial_uploads_are_not_copied() {
+        let path = Path::new(".temp/backups/uploads");
+        let _try_remove_existing = std::fs::remove_dir_all(path);
+        let uploads = path.join("uploads");
+        std::fs::create_dir_all(&uploads).unwrap();
+        std::fs::write(uploads.join("1-done.txt"), "done").unwrap();
+        std::fs::write(uploads.join(format!("2-busy.txt{}", PARTIAL_SUFFIX)), "bu").unwrap();
+        copy_dir(&uploads, &path.join("copy")).unwrap();
+        let copied: Vec<_> = std::fs::read_dir(path.join("copy"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(copied, vec!["1-done.txt"]);
+    }
+}
@@ -4,6 +4,7 @@ use std::{fs::File, io::Write};
 
 mod actor_messages;
 mod appstate;
+mod backups;
 mod commit_monitor;
 pub mod config;
 mod content_types;
@@ -42,27 +43,69 @@ async fn main_wrapped() -> errors::AtomicServerResult<()> {
                 Some(p) => std::path::Path::new(&p).to_path_buf(),
                 None => {
                     let date = chrono::Local::now().to_rfc3339();
-                    let pathstr = format!("backups/{}.json", date);
+                    let pathstr = format!("backups/{}.{}", date, e.format.extension());
                     let mut pt = config.config_dir.clone();
                     pt.push(&pathstr);
                     pt
                 }
             };
             let appstate = appstate::init(config.clone())?;
-            let outstr = appstate.store.export(!e.only_internal)?;
             std::fs::create_dir_all(path.parent().unwrap())
                 .map_err(|e| format!("Failed to create directory {:?}. {}", path, e))?;
             let mut file = File::create(&path)
                 .map_err(|e| format!("Failed to write file to {:?}. {}", path, e))?;
-            write!(file, "{}", outstr)?;
+            match &e.format {
+                config::ExportFormat::Json => {
+                    let outstr = appstate.store.export(!e.only_internal)?;
+                    write!(file, "{}", outstr)?;
+                }
+                config::ExportFormat::Ndjson => {
+                    let mut writer = std::io::BufWriter::new(file);
+                    let count = appstate
+                        .store
+                        .export_ndjson(!e.only_internal, &mut writer)?;
+                    println!("Exported {} resources", count);
+                }
+                format @ (config::ExportFormat::Turtle
+                | config::ExportFormat::NTriples
+                | config::ExportFormat::RdfXml
+                | config::ExportFormat::Trig) => {
+                    let rdf_format = format.rdf_format().expect("RDF export format");
+                    let mut writer = std::io::BufWriter::new(file);
+                    let count = atomic_lib::serialize::write_rdf(
+                        appstate.store.all_resources(!e.only_internal),
+                        &rdf_format,
+                        &appstate.store,
+                        &mut writer,
+                    )?;
+                    println!("Exported {} resources", count);
+                }
+                config::ExportFormat::Csv => {
+                    let class = e.class.as_ref().ok_or("Pass a --class to export as CSV")?;
+                    let count = atomic_lib::csv::export_class_csv(
+                        &appstate.store,
+                        class,
+                        &ForAgent::Sudo,
+                        std::io::BufWriter::new(file),
+                    )?;
+                    println!("Exported {} resources", count);
+                }
+            }
             println!("Succesfully exported data to {}", path.to_str().unwrap());
             Ok(())
         }
+        Some(config::Command::ExportSite(e)) => {
+            let appstate = appstate::init(config.clone())?;
+            let count = atomic_lib::html::export_site(&appstate.store, &e.path)?;
+            println!("Succesfully exported {} pages to {:?}", count, e.path);
+            Ok(())
+        }
         Some(config::Command::Import(import_opts)) => {
-            let readstring = {
-                let path = std::path::Path::new(&import_opts.file);
-                std::fs::read_to_string(path)?
-            };
+            let path = std::path::Path::new(&import_opts.file);
+            let format = import_opts
+                .format
+                .clone()
+                .unwrap_or_else(|| config::ImportFormat::from_path(path));
 
             let appstate = appstate::init(config.clone())?;
             let importer_subject = if let Some(i) = &import_opts.parent {
@@ -82,7 +125,74 @@ async fn main_wrapped() -> errors::AtomicServerResult<()> {
                 signer: Some(appstate.store.get_default_agent()?),
             };
             println!("Importing...");
-            appstate.store.import(&readstring, &parse_opts)?;
+            if format == config::ImportFormat::Ndjson {
+                let file = File::open(path)
+                    .map_err(|e| format!("Failed to open file {:?}. {}", path, e))?;
+                appstate.store.import_ndjson(
+                    std::io::BufReader::new(file),
+                    &parse_opts,
+                    import_opts.batch_size,
+                    &mut |count| println!("Imported {} resources...", count),
+                )?;
+            } else if format == config::ImportFormat::Csv {
+                let class = import_opts
+                    .class
+                    .as_ref()
+                    .ok_or("Pass a --class to import CSV")?;
+                let file = File::open(path)
+                    .map_err(|e| format!("Failed to open file {:?}. {}", path, e))?;
+                let report =
+                    atomic_lib::csv::import_class_csv(&appstate.store, class, file, &parse_opts)?;
+                for error in report.errors.iter() {
+                    println!("Line {}: {}", error.line, error.error);
+                }
+                println!(
+                    "Imported {} resources, {} rows failed",
+                    report.imported.len(),
+                    report.errors.len()
+                );
+            } else if format == config::ImportFormat::Json {
+                let class = import_opts
+                    .class
+                    .as_ref()
+                    .ok_or("Pass a --class to import plain JSON")?;
+                let readstring = std::fs::read_to_string(path)?;
+                let count = appstate
+                    .store
+                    .import_json(&readstring, class, &parse_opts)?;
+                println!("Imported {} resources", count);
+            } else if format == config::ImportFormat::Markdown {
+                let files = if path.is_dir() {
+                    atomic_lib::vault::read_vault_dir(path)?
+                } else {
+                    let file = File::open(path)
+                        .map_err(|e| format!("Failed to open file {:?}. {}", path, e))?;
+                    atomic_lib::vault::read_vault_zip(std::io::BufReader::new(file))?
+                };
+                let report = atomic_lib::vault::import_vault(
+                    files,
+                    import_opts.class.as_deref(),
+                    &appstate.store,
+                    &parse_opts,
+                )?;
+                for warning in report.warnings.iter() {
+                    println!("{}: {}", warning.path, warning.message);
+                }
+                println!(
+                    "Imported {} resources, {} warnings",
+                    report.imported.len(),
+                    report.warnings.len()
+                );
+            } else if let Some(rdf_format) = format.rdf_format() {
+                let readstring = std::fs::read_to_string(path)?;
+                let count = appstate
+                    .store
+                    .import_rdf(&readstring, rdf_format, &parse_opts)?;
+                println!("Imported {} resources", count);
+            } else {
+                let readstring = std::fs::read_to_string(path)?;
+                appstate.store.import(&readstring, &parse_opts)?;
+            }
 
             println!("Sucesfully imported {:?} to store.", import_opts.file);
             Ok(())
@@ -107,6 +217,51 @@ async fn main_wrapped() -> errors::AtomicServerResult<()> {
             }
             Ok(())
         }
+        Some(config::Command::Backup) => {
+            let appstate = appstate::init(config.clone())?;
+            let path = backups::create_backup(&appstate.store, &config)?;
+            println!("Succesfully created backup at {:?}", path);
+            Ok(())
+        }
+        Some(config::Command::Restore(restore_opts)) => {
+            let path = backups::restore_backup(&config, restore_opts.path.as_deref())?;
+            println!("Restored backup {:?}, rebuilding indexes...", path);
+            let appstate = appstate::init(config.clone())?;
+            appstate.store.clear_index()?;
+            appstate.store.build_index(true)?;
+            appstate
+                .search_state
+                .writer
+                .write()
+                .expect("Could not get a lock on search writer")
+                .delete_all_documents()?;
+            search::add_all_resources(&appstate.search_state, &appstate.store)?;
+            println!("Succesfully restored {:?}", path);
+            Ok(())
+        }
+        Some(config::Command::MigrateDatatype(opts)) => {
+            let appstate = appstate::init(config.clone())?;
+            let property = appstate.store.get_property(&opts.property)?;
+            let datatype = match &opts.datatype {
+                Some(url) => atomic_lib::datatype::match_datatype(url),
+                None => property.data_type.clone(),
+            };
+            if datatype != property.data_type && !opts.dry_run {
+                return Err(format!(
+                    "The datatype of {} is {}. Change it with a Commit to convert its values, or use --dry-run to preview converting them to {}.",
+                    opts.property, property.data_type, datatype
+                )
+                .into());
+            }
+            let report = atomic_lib::migrate::migrate_datatype(
+                &appstate.store,
+                &opts.property,
+                &datatype,
+                opts.dry_run,
+            )?;
+            print!("{}", report);
+            Ok(())
+        }
         Some(config::Command::CreateDotEnv) => {
             let current_path = std::env::current_dir()?;
             let pathstr = format!(
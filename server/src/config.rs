@@ -74,6 +74,10 @@ pub struct Opts {
     #[clap(long, env = "ATOMIC_DATA_DIR")]
     pub data_dir: Option<PathBuf>,
 
+    /// Render the contents of public resources into the HTML of the app, for clients that don't run JavaScript, such as link previews, screen readers and archive crawlers.
+    #[clap(long, env = "ATOMIC_SSR")]
+    pub ssr: bool,
+
     /// CAUTION: Skip authentication checks, making all data publicly readable. Improves performance.
     #[clap(long, env = "ATOMIC_PUBLIC_MODE")]
     pub public_mode: bool,
@@ -94,6 +98,30 @@ pub struct Opts {
     /// Introduces random delays in the server, to simulate a slow connection. Useful for testing.
     #[clap(long, env = "ATOMIC_SLOW_MODE")]
     pub slow_mode: bool,
+
+    /// Create a backup of the store and uploaded files every N minutes while the server is running. Disabled if not set.
+    #[clap(long, env = "ATOMIC_BACKUP_INTERVAL")]
+    pub backup_interval: Option<u64>,
+
+    /// Path for the folder where backups are stored. Defaults to a `backups` folder in the data directory.
+    #[clap(long, env = "ATOMIC_BACKUP_DIR")]
+    pub backup_dir: Option<PathBuf>,
+
+    /// The amount of days for which the latest backup is kept.
+    #[clap(long, default_value = "7", env = "ATOMIC_BACKUP_KEEP_DAILY")]
+    pub backup_keep_daily: usize,
+
+    /// The amount of weeks for which the latest backup is kept.
+    #[clap(long, default_value = "4", env = "ATOMIC_BACKUP_KEEP_WEEKLY")]
+    pub backup_keep_weekly: usize,
+
+    /// Path to a file that is served at `/robots.txt`. By default, all crawlers are allowed and pointed to `/sitemap.xml`.
+    #[clap(long, env = "ATOMIC_ROBOTS_TXT")]
+    pub robots_txt: Option<PathBuf>,
+
+    /// The maximum amount of resources listed per page of `/sitemap.xml`. Larger stores get a sitemap index that links to the pages.
+    #[clap(long, default_value = "50000", env = "ATOMIC_SITEMAP_PAGE_SIZE")]
+    pub sitemap_page_size: usize,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -119,7 +147,10 @@ pub enum Command {
     /// Create and save a JSON-AD backup of the store.
     #[clap(name = "export")]
     Export(ExportOpts),
-    /// Import a JSON-AD file or stream to the store. By default creates Commits for all changes, maintaining version history. Use --force to allow importing other types of files.
+    /// Render all publicly readable resources as a static HTML website, with a JSON-AD file next to every page.
+    #[clap(name = "export-site")]
+    ExportSite(ExportSiteOpts),
+    /// Import a JSON-AD, NDJSON or RDF (Turtle, N-Triples, JSON-LD) file to the store. By default creates Commits for all changes, maintaining version history. Use --force to allow importing other types of files.
     #[clap(name = "import", trailing_var_arg = true)]
     Import(ImportOpts),
     /// Creates a `.env` file in your current directory that shows various options that you can set.
@@ -131,6 +162,21 @@ pub enum Command {
     /// Danger! Removes all data from the store.
     #[clap(name = "reset")]
     Reset,
+    /// Create a backup of the store and uploaded files. Applies the retention policy to existing backups.
+    #[clap(name = "backup")]
+    Backup,
+    /// Replace the store and uploaded files with those from a backup. The server must not be running.
+    #[clap(name = "restore")]
+    Restore(RestoreOpts),
+    /// Convert the values of a Property to its datatype, and list the values that can't be converted. Runs automatically when a Commit changes the datatype of a Property.
+    #[clap(name = "migrate-datatype")]
+    MigrateDatatype(MigrateDatatypeOpts),
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct ExportSiteOpts {
+    /// The directory where the website is written. Existing files with the same names are overwritten.
+    pub path: PathBuf,
 }
 
 #[derive(Parser, Clone, Debug)]
@@ -141,11 +187,64 @@ pub struct ExportOpts {
     /// Do not export resources that are externally defined, which are cached by this Server.
     #[clap(long)]
     pub only_internal: bool,
+    /// The serialization format of the export.
+    /// `ndjson` writes one JSON-AD resource per line, and streams the data to disk. Use this for large stores.
+    /// `turtle`, `n-triples` and `trig` are streamed too. `rdf-xml` and `json` are built in memory first.
+    /// `csv` exports the instances of the `--class`, with a column for every required and recommended property.
+    #[clap(value_enum, long, default_value = "json")]
+    pub format: ExportFormat,
+    /// URL of the Class of which the instances are exported. Required for the `csv` format.
+    #[clap(long)]
+    pub class: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// A single JSON-AD array, containing all resources
+    Json,
+    /// Newline Delimited JSON-AD, one resource per line
+    Ndjson,
+    /// Comma Separated Values, one instance of a Class per row
+    Csv,
+    /// RDF Turtle, with prefixes for the ontologies in the store
+    Turtle,
+    /// RDF N-Triples
+    NTriples,
+    /// RDF/XML
+    RdfXml,
+    /// RDF TriG, with a named graph for every resource
+    Trig,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Turtle => "ttl",
+            ExportFormat::NTriples => "nt",
+            ExportFormat::RdfXml => "rdf",
+            ExportFormat::Trig => "trig",
+        }
+    }
+
+    /// Returns the RDF serialization format, if this is an RDF format.
+    pub fn rdf_format(&self) -> Option<atomic_lib::serialize::Format> {
+        use atomic_lib::serialize::Format;
+        match self {
+            ExportFormat::Turtle => Some(Format::Turtle),
+            ExportFormat::NTriples => Some(Format::NTriples),
+            ExportFormat::RdfXml => Some(Format::RdfXml),
+            ExportFormat::Trig => Some(Format::TriG),
+            ExportFormat::Json | ExportFormat::Ndjson | ExportFormat::Csv => None,
+        }
+    }
 }
 
 #[derive(Parser, Clone, Debug)]
 pub struct ImportOpts {
-    /// Path of the file to be imported.
+    /// Path of the file to be imported. For the `markdown` format, this can also be a directory.
     #[clap(long)]
     pub file: PathBuf,
     /// The URL of the  Importer (parent) Resource to be used.
@@ -156,6 +255,94 @@ pub struct ImportOpts {
     /// Skip checks, allows for importing things like Commits.
     #[clap(long)]
     pub force: bool,
+    /// The format of the file.
+    /// If not set, it is derived from the file extension (`.ndjson` / `.jsonl`, `.ttl`, `.nt`, `.jsonld`, `.csv`, `.zip`), or defaults to JSON-AD.
+    /// Directories are imported as `markdown`.
+    /// `ndjson` is read one resource per line and streamed into the store.
+    /// `csv` creates an instance of the `--class` for every row.
+    /// `json` creates an instance of the `--class` for every object, and is never derived from the extension.
+    /// `markdown` imports a vault of Markdown files with front matter, as Articles or instances of the `--class`.
+    #[clap(value_enum, long)]
+    pub format: Option<ImportFormat>,
+    /// URL of the Class of which instances are created. Required for the `csv` and `json` formats, optional for `markdown`.
+    #[clap(long)]
+    pub class: Option<String>,
+    /// When streaming NDJSON, the amount of resources that are parsed and saved at once.
+    #[clap(long, default_value = "1000")]
+    pub batch_size: usize,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq)]
+pub enum ImportFormat {
+    /// A JSON-AD array or object
+    JsonAd,
+    /// Newline Delimited JSON-AD, one resource per line
+    Ndjson,
+    /// RDF Turtle
+    Turtle,
+    /// RDF N-Triples
+    NTriples,
+    /// RDF JSON-LD
+    JsonLd,
+    /// Comma Separated Values, with a header row of property shortnames
+    Csv,
+    /// Plain JSON objects with property shortnames as keys
+    Json,
+    /// A directory or zip file of Markdown files with YAML front matter, e.g. an Obsidian vault
+    Markdown,
+}
+
+impl ImportFormat {
+    /// Derives the format from the extension of the file.
+    pub fn from_path(path: &std::path::Path) -> ImportFormat {
+        if path.is_dir() {
+            return ImportFormat::Markdown;
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ndjson") | Some("jsonl") => ImportFormat::Ndjson,
+            Some("ttl") => ImportFormat::Turtle,
+            Some("nt") => ImportFormat::NTriples,
+            Some("jsonld") => ImportFormat::JsonLd,
+            Some("csv") => ImportFormat::Csv,
+            Some("zip") => ImportFormat::Markdown,
+            _ => ImportFormat::JsonAd,
+        }
+    }
+
+    /// Returns the RDF format, if this is an RDF serialization.
+    pub fn rdf_format(&self) -> Option<atomic_lib::rdf::RdfFormat> {
+        match self {
+            ImportFormat::Turtle => Some(atomic_lib::rdf::RdfFormat::Turtle),
+            ImportFormat::NTriples => Some(atomic_lib::rdf::RdfFormat::NTriples),
+            ImportFormat::JsonLd => Some(atomic_lib::rdf::RdfFormat::JsonLd),
+            ImportFormat::JsonAd
+            | ImportFormat::Ndjson
+            | ImportFormat::Csv
+            | ImportFormat::Json
+            | ImportFormat::Markdown => None,
+        }
+    }
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct RestoreOpts {
+    /// Path of the backup folder to be restored. Defaults to the most recent backup in the backup directory.
+    #[clap(long)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct MigrateDatatypeOpts {
+    /// URL of the Property of which the values are converted.
+    #[clap(long)]
+    pub property: String,
+    /// URL of the datatype to convert to. Defaults to the datatype of the Property.
+    /// Another datatype can only be used with `--dry-run`, to preview what changing the datatype of the Property would do.
+    #[clap(long)]
+    pub datatype: Option<String>,
+    /// Only list what would be converted, without changing anything.
+    #[clap(long)]
+    pub dry_run: bool,
 }
 
 /// Start atomic-server, oi mate
@@ -188,6 +375,8 @@ pub struct Config {
     pub store_path: PathBuf,
     /// Path to where the uploaded files are stored.
     pub uploads_path: PathBuf,
+    /// Path to where the backups of the store and uploaded files are stored.
+    pub backups_path: PathBuf,
     /// Path to where the search index for tantivy full text search is located
     pub search_index_path: PathBuf,
     /// If true, the initialization scripts will be ran (create first Drive, Agent, indexing, etc)
@@ -220,6 +409,11 @@ pub fn build_config(opts: Opts) -> AtomicServerResult<Config> {
     let mut uploads_path = data_dir.clone();
     uploads_path.push("uploads");
 
+    let backups_path = opts
+        .backup_dir
+        .clone()
+        .unwrap_or_else(|| data_dir.join("backups"));
+
     let mut static_path = data_dir;
     static_path.push("static");
 
@@ -268,6 +462,7 @@ pub fn build_config(opts: Opts) -> AtomicServerResult<Config> {
     };
 
     Ok(Config {
+        backups_path,
         initialize,
         opts,
         cert_path,
@@ -2,7 +2,9 @@
 
 use actix_web::http::header::HeaderMap;
 
-#[derive(Debug, PartialEq, Eq)]
+use crate::errors::{AppErrorType, AtomicServerError, AtomicServerResult};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ContentType {
     /// Plain JSON, using shortnames as keys instead of URLs
     /// https://docs.atomicdata.dev/interoperability/json.html#atomic-data-as-plain-json
@@ -10,6 +12,9 @@ pub enum ContentType {
     /// JSON-AD, default Atomic Data serialization
     /// https://docs.atomicdata.dev/core/json-ad.html
     JsonAd,
+    /// Binary JSON-AD, encoded as CBOR with repeated strings interned
+    /// https://docs.atomicdata.dev/core/json-ad.html#binary-json-ad
+    JsonAdCbor,
     /// JSON-LD, RDF compatible JSON with @context mapping
     /// https://docs.atomicdata.dev/interoperability/json.html#from-json-to-json-ad
     JsonLd,
@@ -20,69 +25,244 @@ pub enum ContentType {
     /// RDF N-Triples format
     /// https://www.w3.org/TR/n-triples/
     NTriples,
+    /// RDF/XML format
+    /// https://www.w3.org/TR/rdf-syntax-grammar/
+    RdfXml,
+    /// RDF TriG format, Turtle with named graphs
+    /// https://www.w3.org/TR/trig/
+    TriG,
 }
 
 const MIME_HTML: &str = "text/html";
+const MIME_XHTML: &str = "application/xhtml+xml";
 const MIME_XML: &str = "application/xml";
 const MIME_JSON: &str = "application/json";
 const MIME_JSONLD: &str = "application/ld+json";
 const MIME_JSONAD: &str = "application/ad+json";
+const MIME_JSONAD_CBOR: &str = atomic_lib::parse::JSON_AD_CBOR_MIME;
 const MIME_TURTLE: &str = "text/turtle";
 const MIME_NT: &str = "application/n-triples";
+const MIME_RDF_XML: &str = "application/rdf+xml";
+const MIME_TRIG: &str = "application/trig";
 
 impl ContentType {
     pub fn to_mime(&self) -> &str {
         match self {
             ContentType::Json => MIME_JSON,
             ContentType::JsonAd => MIME_JSONAD,
+            ContentType::JsonAdCbor => MIME_JSONAD_CBOR,
             ContentType::JsonLd => MIME_JSONLD,
             ContentType::Html => MIME_HTML,
             ContentType::Turtle => MIME_TURTLE,
             ContentType::NTriples => MIME_NT,
+            ContentType::RdfXml => MIME_RDF_XML,
+            ContentType::TriG => MIME_TRIG,
         }
     }
+
+    /// Whether this is one of the RDF serialization formats.
+    pub fn is_rdf(&self) -> bool {
+        matches!(
+            self,
+            ContentType::Turtle | ContentType::NTriples | ContentType::RdfXml | ContentType::TriG
+        )
+    }
+
+    /// Whether language-tagged strings are served in the language of the client, see [get_accept_language].
+    /// Only for plain JSON and HTML, which are meant to be read.
+    /// JSON-AD (and CBOR) keep all languages, as clients edit Resources with it, and would drop the other translations.
+    /// RDF and JSON-LD keep all languages too, as these formats can tag literals with their language.
+    pub fn is_localized(&self) -> bool {
+        matches!(self, ContentType::Json | ContentType::Html)
+    }
+
+    /// Serializes Atoms to the RDF format of this content type.
+    /// Fails for content types that are not RDF.
+    pub fn atoms_to_rdf(
+        &self,
+        atoms: Vec<atomic_lib::Atom>,
+        store: &impl atomic_lib::Storelike,
+    ) -> atomic_lib::errors::AtomicResult<String> {
+        use atomic_lib::serialize::Format;
+        let format = match self {
+            ContentType::Turtle => Format::Turtle,
+            ContentType::NTriples => Format::NTriples,
+            ContentType::RdfXml => Format::RdfXml,
+            ContentType::TriG => Format::TriG,
+            other => return Err(format!("{} is not an RDF format", other.to_mime()).into()),
+        };
+        atomic_lib::serialize::atoms_to_rdf(atoms, &format, store)
+    }
 }
 
+/// The content types that can be served, with the MIME types that select them.
+/// When the client likes several of them equally, the first one is used.
+const OFFERED: [(&str, ContentType); 9] = [
+    (MIME_HTML, ContentType::Html),
+    (MIME_JSONAD, ContentType::JsonAd),
+    (MIME_JSON, ContentType::Json),
+    (MIME_JSONLD, ContentType::JsonLd),
+    (MIME_TURTLE, ContentType::Turtle),
+    (MIME_NT, ContentType::NTriples),
+    (MIME_RDF_XML, ContentType::RdfXml),
+    (MIME_TRIG, ContentType::TriG),
+    (MIME_JSONAD_CBOR, ContentType::JsonAdCbor),
+];
+
+/// MIME types that browsers send for HTML. Unlike [OFFERED], these are not matched by wildcards.
+const HTML_ALIASES: [&str; 2] = [MIME_XHTML, MIME_XML];
+
 /// Returns the preferred content type.
-/// Defaults to HTML if none is found.
-pub fn get_accept(map: &HeaderMap) -> ContentType {
+/// Defaults to HTML if there is no Accept header.
+/// Returns a `406 Not Acceptable` error if none of the accepted types can be served.
+pub fn get_accept(map: &HeaderMap) -> AtomicServerResult<ContentType> {
     let accept_header = match map.get("Accept") {
         Some(header) => header.to_str().unwrap_or(""),
-        None => return ContentType::Html,
+        None => return Ok(ContentType::Html),
     };
-    parse_accept_header(accept_header)
+    parse_accept_header(accept_header).ok_or_else(|| AtomicServerError {
+        message: format!(
+            "None of the types in the Accept header '{}' can be served. Available types: {}",
+            accept_header,
+            OFFERED.map(|(mime, _)| mime).join(", ")
+        ),
+        error_type: AppErrorType::NotAcceptable,
+        error_resource: None,
+    })
+}
+
+/// A media range from an Accept header, such as `text/*;q=0.8`.
+struct MediaRange<'a> {
+    mime_type: &'a str,
+    subtype: &'a str,
+    quality: f32,
+}
+
+impl MediaRange<'_> {
+    /// How specific the range matches the MIME type, higher is more specific.
+    /// `None` if it does not match.
+    fn specificity(&self, mime: &str) -> Option<u8> {
+        let (mime_type, subtype) = mime.split_once('/')?;
+        match (self.mime_type, self.subtype) {
+            ("*", "*") => Some(0),
+            (t, "*") if t.eq_ignore_ascii_case(mime_type) => Some(1),
+            (t, s) if t.eq_ignore_ascii_case(mime_type) && s.eq_ignore_ascii_case(subtype) => {
+                Some(2)
+            }
+            _ => None,
+        }
+    }
 }
 
-/// Parses an HTTP Accept header
-/// Does not fully adhere to the RFC spec: https://tools.ietf.org/html/rfc7231
-/// Does not take into consideration the q value, simply reads the first thing before the comma
-/// Defaults to HTML
-pub fn parse_accept_header(header: &str) -> ContentType {
-    for mimepart in header.split(',') {
-        if mimepart.contains(MIME_JSONAD) {
-            return ContentType::JsonAd;
+/// Parses a single comma separated entry of an Accept header.
+/// Returns `None` for entries that are not valid media ranges.
+fn parse_media_range(entry: &str) -> Option<MediaRange<'_>> {
+    let mut parts = entry.split(';');
+    let (mime_type, subtype) = parts.next()?.trim().split_once('/')?;
+    let (mime_type, subtype) = (mime_type.trim(), subtype.trim());
+    if mime_type.is_empty() || subtype.is_empty() || (mime_type == "*" && subtype != "*") {
+        return None;
+    }
+    let mut quality = 1.0;
+    for param in parts {
+        if let Some((key, value)) = param.split_once('=') {
+            if key.trim().eq_ignore_ascii_case("q") {
+                quality = value.trim().parse::<f32>().ok()?.clamp(0.0, 1.0);
+            }
         }
-        if mimepart.contains(MIME_HTML) {
-            return ContentType::Html;
+    }
+    Some(MediaRange {
+        mime_type,
+        subtype,
+        quality,
+    })
+}
+
+/// Parses an HTTP Accept header and picks the best content type, following [RFC 7231](https://tools.ietf.org/html/rfc7231#section-5.3.2).
+/// Every type gets the quality (`q`) of the most specific media range that matches it, wildcards included.
+/// Ties are won by the type that is matched more specifically, then by the earliest match in the header.
+/// An empty header accepts anything, which means HTML.
+/// Returns `None` if none of the types that we can serve are acceptable.
+pub fn parse_accept_header(header: &str) -> Option<ContentType> {
+    if header.trim().is_empty() {
+        return Some(ContentType::Html);
+    }
+    let ranges: Vec<MediaRange> = header.split(',').filter_map(parse_media_range).collect();
+    // (quality, specificity, position in the header)
+    let mut best: Option<(f32, u8, usize, &ContentType)> = None;
+    let aliases = HTML_ALIASES
+        .iter()
+        .map(|mime| (*mime, &ContentType::Html, true));
+    let offered = OFFERED.iter().map(|(mime, c)| (*mime, c, false));
+    for (mime, content_type, is_alias) in offered.chain(aliases) {
+        let Some((position, range, specificity)) = ranges
+            .iter()
+            .enumerate()
+            .filter_map(|(i, range)| range.specificity(mime).map(|s| (i, range, s)))
+            .filter(|(_, _, s)| !is_alias || *s == 2)
+            .max_by_key(|(i, _, s)| (*s, std::cmp::Reverse(*i)))
+        else {
+            continue;
+        };
+        if range.quality <= 0.0 {
+            continue;
         }
-        if mimepart.contains(MIME_XML) {
-            return ContentType::Html;
+        let better = match best {
+            None => true,
+            Some((quality, best_specificity, best_position, _)) => {
+                range.quality > quality
+                    || (range.quality == quality
+                        && (specificity > best_specificity
+                            || (specificity == best_specificity && position < best_position)))
+            }
+        };
+        if better {
+            best = Some((range.quality, specificity, position, content_type));
         }
-        if mimepart.contains(MIME_JSON) {
-            return ContentType::Json;
+    }
+    match best {
+        Some((_, _, _, content_type)) => Some(content_type.clone()),
+        None => {
+            tracing::info!("No acceptable content type in Accept header: {}", header);
+            None
         }
-        if mimepart.contains(MIME_JSONLD) {
-            return ContentType::JsonLd;
+    }
+}
+
+/// Returns the languages of the Accept-Language header, most preferred first.
+/// Empty if there is no such header.
+pub fn get_accept_language(map: &HeaderMap) -> Vec<String> {
+    match map.get("Accept-Language").map(|h| h.to_str()) {
+        Some(Ok(header)) => parse_accept_language(header),
+        _ => Vec::new(),
+    }
+}
+
+/// Parses an Accept-Language header, following [RFC 7231](https://tools.ietf.org/html/rfc7231#section-5.3.5).
+/// Returns the language ranges sorted by quality (`q`), leaving out those with a quality of zero.
+/// Ranges with the same quality keep their order.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut ranges: Vec<(f32, String)> = Vec::new();
+    for entry in header.split(',') {
+        let mut parts = entry.split(';');
+        let range = parts.next().unwrap_or_default().trim();
+        if range.is_empty() {
+            continue;
         }
-        if mimepart.contains(MIME_TURTLE) {
-            return ContentType::Turtle;
+        let mut quality = 1.0;
+        for param in parts {
+            if let Some((key, value)) = param.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("q") {
+                    quality = value.trim().parse::<f32>().unwrap_or(0.0).clamp(0.0, 1.0);
+                }
+            }
         }
-        if mimepart.contains(MIME_NT) {
-            return ContentType::NTriples;
+        if quality > 0.0 {
+            ranges.push((quality, range.to_string()));
         }
     }
-    tracing::info!("Unknown Accept header, defaut to HTML: {}", header);
-    ContentType::Html
+    ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranges.into_iter().map(|(_, range)| range).collect()
 }
 
 #[cfg(test)]
@@ -91,14 +271,85 @@ mod test {
 
     #[test]
     fn parse_types() {
-        assert!(parse_accept_header("text/html,application/xml") == ContentType::Html);
-        assert!(parse_accept_header("application/ad+json") == ContentType::JsonAd);
-        assert!(parse_accept_header("application/ld+json") == ContentType::JsonLd);
+        assert!(parse_accept_header("text/html,application/xml") == Some(ContentType::Html));
+        assert!(parse_accept_header("application/ad+json") == Some(ContentType::JsonAd));
+        assert!(parse_accept_header("application/ld+json") == Some(ContentType::JsonLd));
+        assert!(parse_accept_header("application/rdf+xml") == Some(ContentType::RdfXml));
+        assert!(parse_accept_header("application/trig") == Some(ContentType::TriG));
+        assert!(parse_accept_header("application/ad+cbor") == Some(ContentType::JsonAdCbor));
     }
 
     #[test]
     fn parse_types_with_blank_chars() {
-        assert!(parse_accept_header("application/ad+json ; ") == ContentType::JsonAd);
-        assert!(parse_accept_header(" application/ad+json ; ") == ContentType::JsonAd);
+        assert!(parse_accept_header("application/ad+json ; ") == Some(ContentType::JsonAd));
+        assert!(parse_accept_header(" application/ad+json ; ") == Some(ContentType::JsonAd));
+    }
+
+    #[test]
+    fn parse_quality_values() {
+        assert_eq!(
+            parse_accept_header("application/json;q=0.9, application/ad+json"),
+            Some(ContentType::JsonAd)
+        );
+        assert_eq!(
+            parse_accept_header("text/turtle;q=0.5, application/n-triples;q=0.8"),
+            Some(ContentType::NTriples)
+        );
+        // Browsers
+        assert_eq!(
+            parse_accept_header(
+                "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,*/*;q=0.8"
+            ),
+            Some(ContentType::Html)
+        );
+        // Equal quality: the earliest in the header wins
+        assert_eq!(
+            parse_accept_header("text/turtle, application/ad+json"),
+            Some(ContentType::Turtle)
+        );
+    }
+
+    #[test]
+    fn parse_wildcards() {
+        assert_eq!(parse_accept_header("*/*"), Some(ContentType::Html));
+        assert_eq!(parse_accept_header(""), Some(ContentType::Html));
+        assert_eq!(
+            parse_accept_header("*/*;q=0.1, application/ld+json"),
+            Some(ContentType::JsonLd)
+        );
+        // The more specific range determines the quality
+        assert_eq!(
+            parse_accept_header("text/*, text/html;q=0"),
+            Some(ContentType::Turtle)
+        );
+        assert_eq!(
+            parse_accept_header("application/*, */*;q=0.1"),
+            Some(ContentType::JsonAd)
+        );
+    }
+
+    #[test]
+    fn parse_languages() {
+        assert_eq!(
+            parse_accept_language("nl-BE, en;q=0.8, de;q=0.9, fr;q=0, *;q=0.1"),
+            vec!["nl-BE", "de", "en", "*"]
+        );
+        assert_eq!(parse_accept_language(" en , nl "), vec!["en", "nl"]);
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn not_acceptable() {
+        assert_eq!(parse_accept_header("image/png"), None);
+        assert_eq!(parse_accept_header("application/ad+json;q=0"), None);
+        assert_eq!(parse_accept_header("nonsense"), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            actix_web::http::header::ACCEPT,
+            "image/png".try_into().unwrap(),
+        );
+        let err = get_accept(&headers).unwrap_err();
+        assert!(matches!(err.error_type, AppErrorType::NotAcceptable));
     }
 }
@@ -11,6 +11,10 @@ pub enum AppErrorType {
     NotFound,
     Unauthorized,
     MethodNotAllowed,
+    NotAcceptable,
+    Conflict,
+    /// The data does not follow the Validation Rules of its Classes
+    Validation,
     Other,
 }
 
@@ -43,6 +47,9 @@ impl ResponseError for AtomicServerError {
         match self.error_type {
             AppErrorType::NotFound => StatusCode::NOT_FOUND,
             AppErrorType::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            AppErrorType::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            AppErrorType::Conflict => StatusCode::CONFLICT,
+            AppErrorType::Validation => StatusCode::UNPROCESSABLE_ENTITY,
             AppErrorType::Other => StatusCode::INTERNAL_SERVER_ERROR,
             AppErrorType::Unauthorized => StatusCode::UNAUTHORIZED,
         }
@@ -85,6 +92,8 @@ impl From<atomic_lib::errors::AtomicError> for AtomicServerError {
             atomic_lib::AtomicErrorType::NotFoundError => AppErrorType::NotFound,
             atomic_lib::AtomicErrorType::UnauthorizedError => AppErrorType::Unauthorized,
             atomic_lib::AtomicErrorType::MethodNotAllowed => AppErrorType::MethodNotAllowed,
+            atomic_lib::AtomicErrorType::ConflictError => AppErrorType::Conflict,
+            atomic_lib::AtomicErrorType::ValidationError(_) => AppErrorType::Validation,
             atomic_lib::AtomicErrorType::ParseError => AppErrorType::Other,
             atomic_lib::AtomicErrorType::OtherError => AppErrorType::Other,
         };
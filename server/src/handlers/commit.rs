@@ -1,13 +1,22 @@
-use crate::{appstate::AppState, errors::AtomicServerResult};
+use crate::{
+    appstate::AppState,
+    content_types::{get_accept, ContentType},
+    errors::AtomicServerResult,
+};
 use actix_web::{web, HttpResponse};
-use atomic_lib::{commit::CommitOpts, parse::parse_json_ad_commit_resource, Commit, Storelike};
+use atomic_lib::{
+    commit::CommitOpts,
+    parse::{parse_json_ad_cbor_commit_resource, parse_json_ad_commit_resource, JSON_AD_CBOR_MIME},
+    Commit, Storelike,
+};
 
 /// Send and process a Commit.
-/// Currently only accepts JSON-AD
-#[tracing::instrument(skip(appstate))]
+/// Accepts JSON-AD, or binary JSON-AD if the `Content-Type` is `application/ad+cbor`.
+#[tracing::instrument(skip(appstate, req, body))]
 pub async fn post_commit(
     appstate: web::Data<AppState>,
-    body: String,
+    req: actix_web::HttpRequest,
+    body: web::Bytes,
 ) -> AtomicServerResult<HttpResponse> {
     if appstate.config.opts.slow_mode {
         use rand::Rng;
@@ -17,7 +26,17 @@ pub async fn post_commit(
     }
     let store = &appstate.store;
     let mut builder = HttpResponse::Ok();
-    let incoming_commit_resource = parse_json_ad_commit_resource(&body, store)?;
+    let is_cbor = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(JSON_AD_CBOR_MIME));
+    let incoming_commit_resource = if is_cbor {
+        parse_json_ad_cbor_commit_resource(&body, store)?
+    } else {
+        let body = std::str::from_utf8(&body).map_err(|e| format!("Invalid UTF-8: {}", e))?;
+        parse_json_ad_commit_resource(body, store)?
+    };
     let incoming_commit = Commit::from_resource(incoming_commit_resource)?;
     if !incoming_commit.subject.contains(
         &store
@@ -38,6 +57,10 @@ pub async fn post_commit(
     };
     let commit_response = incoming_commit.apply_opts(store, &opts)?;
 
+    if let Ok(ContentType::JsonAdCbor) = get_accept(req.headers()) {
+        builder.append_header(("Content-Type", JSON_AD_CBOR_MIME));
+        return Ok(builder.body(commit_response.commit_resource.to_json_ad_cbor()?));
+    }
     let message = commit_response.commit_resource.to_json_ad()?;
 
     Ok(builder.body(message))
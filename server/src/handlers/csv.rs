@@ -0,0 +1,80 @@
+//! Exports the instances of a Class as CSV, and imports CSV files as new instances.
+//! See [atomic_lib::csv].
+
+use crate::{
+    appstate::AppState,
+    errors::AtomicServerResult,
+    helpers::{get_client_agent, get_readable_local},
+};
+use actix_web::{web, HttpResponse};
+use atomic_lib::{agents::ForAgent, csv::CSV_MIME, schema::Class, Storelike};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct CsvQuery {
+    /// URL of the Class of which the instances are exported or imported
+    pub class: String,
+    /// Where the imported resources are placed. Only used when importing.
+    pub parent: Option<String>,
+}
+
+/// Responds with a CSV file containing all instances of the `class` that the agent can read.
+#[tracing::instrument(skip(appstate, req))]
+pub async fn export_csv(
+    appstate: web::Data<AppState>,
+    params: web::Query<CsvQuery>,
+    req: actix_web::HttpRequest,
+) -> AtomicServerResult<HttpResponse> {
+    let store = &appstate.store;
+    let subject = format!("{}{}", appstate.config.server_url, req.uri());
+    let for_agent = get_client_agent(req.headers(), &appstate, subject)?;
+    let class = Class::from_resource(get_readable_local(&appstate, &params.class, &for_agent)?)?;
+
+    let mut body = Vec::new();
+    atomic_lib::csv::export_class_csv(store, &class.subject, &for_agent, &mut body)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", CSV_MIME))
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.csv\"", class.shortname),
+        ))
+        .body(body))
+}
+
+/// Creates an instance of the `class` below the `parent` for every row in the CSV body.
+/// Responds with the imported subjects and the errors per row.
+#[tracing::instrument(skip(appstate, req, body))]
+pub async fn import_csv(
+    appstate: web::Data<AppState>,
+    params: web::Query<CsvQuery>,
+    req: actix_web::HttpRequest,
+    body: web::Bytes,
+) -> AtomicServerResult<HttpResponse> {
+    let store = &appstate.store;
+    let subject = format!("{}{}", appstate.config.server_url, req.uri());
+    let for_agent = get_client_agent(req.headers(), &appstate, subject)?;
+    if for_agent == ForAgent::Public {
+        return Err("No agent specified for CSV import".into());
+    }
+    let class = Class::from_resource(get_readable_local(&appstate, &params.class, &for_agent)?)?;
+    let parent = params
+        .parent
+        .clone()
+        .ok_or("No parent specified for CSV import")?;
+
+    let parse_opts = atomic_lib::parse::ParseOpts {
+        importer: Some(parent),
+        for_agent,
+        overwrite_outside: false,
+        // Same as the Importer: we sign with the default agent, because we don't have the private key of the user.
+        signer: Some(store.get_default_agent()?),
+        save: atomic_lib::parse::SaveOpts::Commit,
+    };
+    let report = atomic_lib::csv::import_class_csv(store, &class.subject, &body[..], &parse_opts)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/json"))
+        .body(
+            serde_json::to_string(&report)
+                .map_err(|e| format!("Failed to serialize CSV import report: {}", e))?,
+        ))
+}
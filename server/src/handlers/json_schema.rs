@@ -0,0 +1,79 @@
+//! Serves JSON Schemas of Classes, and validates JSON against them.
+//! See [atomic_lib::json_schema].
+
+use crate::{
+    appstate::AppState,
+    errors::AtomicServerResult,
+    helpers::{get_client_agent, get_readable_local},
+};
+use actix_web::{web, HttpResponse};
+use atomic_lib::json_schema::{
+    class_to_json_schema, validate_json_with_class, JsonShape, JSON_SCHEMA_MIME,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct JsonSchemaQuery {
+    /// URL of the Class that the schema describes
+    pub class: String,
+    /// `json-ad` (default) uses Property URLs as keys, `json` uses shortnames
+    pub shape: Option<String>,
+}
+
+impl JsonSchemaQuery {
+    fn shape(&self) -> AtomicServerResult<JsonShape> {
+        match &self.shape {
+            Some(shape) => Ok(shape.parse()?),
+            None => Ok(JsonShape::JsonAd),
+        }
+    }
+}
+
+/// Responds with the JSON Schema of the `class`, if the agent can read it.
+#[tracing::instrument(skip(appstate, req))]
+pub async fn get_json_schema(
+    appstate: web::Data<AppState>,
+    params: web::Query<JsonSchemaQuery>,
+    req: actix_web::HttpRequest,
+) -> AtomicServerResult<HttpResponse> {
+    check_class(&appstate, &params.class, &req)?;
+    let schema = class_to_json_schema(&appstate.store, &params.class, params.shape()?)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", JSON_SCHEMA_MIME))
+        .body(schema.to_string()))
+}
+
+/// Validates the JSON body against the JSON Schema of the `class`.
+/// Responds with `valid` and the list of `errors`, without storing anything.
+#[tracing::instrument(skip(appstate, req, body))]
+pub async fn validate_json_schema(
+    appstate: web::Data<AppState>,
+    params: web::Query<JsonSchemaQuery>,
+    req: actix_web::HttpRequest,
+    body: web::Bytes,
+) -> AtomicServerResult<HttpResponse> {
+    check_class(&appstate, &params.class, &req)?;
+    let instance: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|e| format!("Unable to parse JSON body: {}", e))?;
+    let errors =
+        validate_json_with_class(&appstate.store, &params.class, params.shape()?, &instance)?;
+    let report = serde_json::json!({
+        "valid": errors.is_empty(),
+        "errors": errors,
+    });
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/json"))
+        .body(report.to_string()))
+}
+
+/// Throws if the Class is not stored on this server, or if the agent can't read it.
+fn check_class(
+    appstate: &AppState,
+    class: &str,
+    req: &actix_web::HttpRequest,
+) -> AtomicServerResult<()> {
+    let subject = format!("{}{}", appstate.config.server_url, req.uri());
+    let for_agent = get_client_agent(req.headers(), appstate, subject)?;
+    get_readable_local(appstate, class, &for_agent)?;
+    Ok(())
+}
@@ -6,10 +6,14 @@ However, some features reside in atomic-server.
 */
 
 pub mod commit;
+pub mod csv;
 pub mod download;
 pub mod get_resource;
+pub mod json_schema;
 pub mod post_resource;
 pub mod search;
+pub mod shacl;
 pub mod single_page_app;
+pub mod sitemap;
 pub mod upload;
 pub mod web_sockets;
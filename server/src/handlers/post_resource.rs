@@ -1,9 +1,8 @@
 use crate::{
     appstate::AppState,
-    content_types::get_accept,
-    content_types::ContentType,
+    content_types::{get_accept_language, ContentType},
     errors::AtomicServerResult,
-    helpers::{get_client_agent, try_extension},
+    helpers::{get_client_agent, get_content_type},
 };
 use actix_web::{web, HttpResponse};
 use atomic_lib::Storelike;
@@ -20,34 +19,22 @@ pub async fn handle_post_resource(
     let mut timer = Timer::new();
 
     let headers = req.headers();
-    let mut content_type = get_accept(headers);
+    let path = path.as_ref().map(|p| p.as_str()).unwrap_or_default();
+    // Extensions take precedence over the Accept header. Harder than it looks to get right...
+    let (content_type, subj_end_string) = get_content_type(path, headers)?;
     let server_url = &appstate.config.server_url;
     // Get the subject from the path, or return the home URL
-    let subject = if let Some(subj_end) = path {
-        let mut subj_end_string = subj_end.as_str();
-        // If the request is for the root, return the home URL
-        if subj_end_string.is_empty() {
-            server_url.to_string()
-        } else {
-            if content_type == ContentType::Html {
-                if let Some((ext, path)) = try_extension(subj_end_string) {
-                    content_type = ext;
-                    subj_end_string = path;
-                }
-            }
-            // Check extensions and set datatype. Harder than it looks to get right...
-            // This might not be the best way of creating the subject. But I can't access the full URL from any actix stuff!
-            let querystring = if req.query_string().is_empty() {
-                "".to_string()
-            } else {
-                format!("?{}", req.query_string())
-            };
-            let subject = format!("{}/{}{}", server_url, subj_end_string, querystring);
-            subject
-        }
-    } else {
+    let subject = if subj_end_string.is_empty() {
         // There is no end string, so It's the root of the URL, the base URL!
         String::from(server_url)
+    } else {
+        // This might not be the best way of creating the subject. But I can't access the full URL from any actix stuff!
+        let querystring = if req.query_string().is_empty() {
+            "".to_string()
+        } else {
+            format!("?{}", req.query_string())
+        };
+        format!("{}/{}{}", server_url, subj_end_string, querystring)
     };
 
     let store = &appstate.store;
@@ -60,6 +47,8 @@ pub async fn handle_post_resource(
 
     tracing::debug!("post_resource: {} as {}", subject, content_type.to_mime());
     builder.append_header(("Content-Type", content_type.to_mime()));
+    // The response depends on the Accept headers, which caches should take into account
+    builder.append_header(("Vary", "Accept, Accept-Language"));
     // This prevents the browser from displaying the JSON response upon re-opening a closed tab
     // https://github.com/atomicdata-dev/atomic-server/issues/137
     builder.append_header((
@@ -67,17 +56,25 @@ pub async fn handle_post_resource(
         "no-store, no-cache, must-revalidate, private",
     ));
 
-    let resource = store.post_resource(&subject, body.into(), &for_agent)?;
+    let body_type = headers
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok());
+    let mut resource = store.post_resource(&subject, body.into(), body_type, &for_agent)?;
+    if content_type.is_localized() {
+        resource.localize(&get_accept_language(headers));
+    }
     timer.add("post_resource");
 
-    let response_body = match content_type {
-        ContentType::Json => resource.to_json(store)?,
-        ContentType::JsonLd => resource.to_json_ld(store)?,
-        ContentType::JsonAd => resource.to_json_ad()?,
-        ContentType::Html => resource.to_json_ad()?,
-        ContentType::Turtle | ContentType::NTriples => {
-            let atoms = resource.to_atoms();
-            atomic_lib::serialize::atoms_to_ntriples(atoms, store)?
+    let response_body: Vec<u8> = match content_type {
+        ContentType::Json => resource.to_json(store)?.into(),
+        ContentType::JsonLd => resource.to_json_ld(store)?.into(),
+        ContentType::JsonAd => resource.to_json_ad()?.into(),
+        ContentType::JsonAdCbor => resource.to_json_ad_cbor()?,
+        ContentType::Html => resource.to_json_ad()?.into(),
+        ContentType::Turtle | ContentType::NTriples | ContentType::RdfXml | ContentType::TriG => {
+            content_type
+                .atoms_to_rdf(resource.to_atoms(), store)?
+                .into()
         }
     };
     timer.add("serialize");
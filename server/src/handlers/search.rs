@@ -4,6 +4,7 @@
 
 use crate::{
     appstate::AppState,
+    content_types::{get_accept, get_accept_language, ContentType},
     errors::{AtomicServerError, AtomicServerResult},
     search::{resource_to_facet, Fields},
 };
@@ -93,14 +94,39 @@ pub async fn search_query(
     let mut results_resource = atomic_lib::plugins::search::search_endpoint().to_resource(store)?;
     results_resource.set_subject(subject.clone());
 
+    let content_type = get_accept(req.headers())?;
+    let languages = get_accept_language(req.headers());
     let resources = get_resources(req, &appstate, &subject, subjects, limit)?;
     timer.add("get_resources");
+    // The results are included as separate resources, so RDF tools can query them.
+    let mut atoms = Vec::new();
+    if content_type.is_rdf() {
+        for resource in resources.iter() {
+            atoms.extend(resource.to_atoms());
+        }
+    }
     results_resource.set(urls::ENDPOINT_RESULTS.into(), resources.into(), store)?;
+    if content_type.is_localized() {
+        results_resource.localize(&languages);
+    }
     let mut builder = HttpResponse::Ok();
     builder.append_header(("Server-Timing", timer.header_value()));
-
-    // TODO: support other serialization options
-    Ok(builder.body(results_resource.to_json_ad()?))
+    builder.append_header(("Vary", "Accept, Accept-Language"));
+
+    let body: Vec<u8> = match content_type {
+        ContentType::Turtle | ContentType::NTriples | ContentType::RdfXml | ContentType::TriG => {
+            let mut result_atoms = results_resource.to_atoms();
+            result_atoms.append(&mut atoms);
+            builder.append_header(("Content-Type", content_type.to_mime()));
+            content_type.atoms_to_rdf(result_atoms, store)?.into()
+        }
+        ContentType::JsonAdCbor => {
+            builder.append_header(("Content-Type", content_type.to_mime()));
+            results_resource.to_json_ad_cbor()?
+        }
+        _ => results_resource.to_json_ad()?.into(),
+    };
+    Ok(builder.body(body))
 }
 
 #[derive(Debug, std::hash::Hash, Eq, PartialEq)]
@@ -0,0 +1,46 @@
+//! Serves SHACL shapes of Classes as Turtle.
+//! See [atomic_lib::serialize::shacl].
+
+use crate::{
+    appstate::AppState,
+    errors::AtomicServerResult,
+    helpers::{get_client_agent, get_readable_local},
+};
+use actix_web::{web, HttpResponse};
+use atomic_lib::{rdf::TURTLE_MIME, serialize::shacl::classes_to_shacl, urls};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct ShaclQuery {
+    /// URL of the Class that is exported
+    pub class: Option<String>,
+    /// URL of an Ontology, of which all Classes are exported
+    pub ontology: Option<String>,
+}
+
+/// Responds with the SHACL shapes of the `class`, or of all Classes of the `ontology`.
+/// Only Classes that are stored on this server and that the agent can read are exported.
+#[tracing::instrument(skip(appstate, req))]
+pub async fn get_shacl(
+    appstate: web::Data<AppState>,
+    params: web::Query<ShaclQuery>,
+    req: actix_web::HttpRequest,
+) -> AtomicServerResult<HttpResponse> {
+    let store = &appstate.store;
+    let subject = format!("{}{}", appstate.config.server_url, req.uri());
+    let for_agent = get_client_agent(req.headers(), &appstate, subject)?;
+    let classes = match (&params.class, &params.ontology) {
+        (Some(class), _) => vec![class.clone()],
+        (None, Some(ontology)) => get_readable_local(&appstate, ontology, &for_agent)?
+            .get(urls::CLASSES)?
+            .to_subjects(None)?,
+        (None, None) => return Err("Add a `class` or `ontology` query param".into()),
+    };
+    for class in &classes {
+        get_readable_local(&appstate, class, &for_agent)?;
+    }
+    let body = classes_to_shacl(&classes, store)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", TURTLE_MIME))
+        .body(body))
+}
@@ -1,22 +1,40 @@
 use std::fmt::Display;
 use std::fmt::Formatter;
 
-use crate::{appstate::AppState, errors::AtomicServerResult};
+use crate::{appstate::AppState, content_types::get_accept_language, errors::AtomicServerResult};
 use actix_web::HttpResponse;
 
-/// Returns the atomic-data-browser single page application
-#[tracing::instrument(skip(appstate))]
+/// Returns the atomic-data-browser single page application.
+/// With `--ssr`, the contents of the public resource are rendered in the body, for clients without JavaScript.
+#[tracing::instrument(skip(appstate, req))]
 pub async fn single_page(
     appstate: actix_web::web::Data<AppState>,
     path: actix_web::web::Path<String>,
+    req: actix_web::HttpRequest,
 ) -> AtomicServerResult<HttpResponse> {
     let template = include_str!("../../assets_tmp/index.html");
-    let subject = format!("{}/{}", appstate.store.get_server_url(), path);
-    let meta_tags: MetaTags = if let Ok(resource) =
-        appstate
-            .store
-            .get_resource_extended(&subject, true, &ForAgent::Public)
-    {
+    let mut subject = format!("{}/{}", appstate.store.get_server_url(), path);
+    let ssr = appstate.config.opts.ssr;
+    // Collection pages are selected using query params.
+    // Without SSR these are ignored, so that Endpoints and dynamic Resources are not calculated for the meta tags.
+    if ssr && !req.query_string().is_empty() {
+        subject = format!("{}?{}", subject, req.query_string());
+    }
+    let resource = appstate
+        .store
+        .get_resource_extended(&subject, !ssr, &ForAgent::Public)
+        .map(|mut r| {
+            r.localize(&get_accept_language(req.headers()));
+            r
+        });
+    let rendered_body = match (&resource, ssr) {
+        (Ok(resource), true) => format!(
+            "<article>\n{}</article>",
+            render_resource_html(&appstate.store, resource, &ForAgent::Public)
+        ),
+        _ => String::new(),
+    };
+    let meta_tags: MetaTags = if let Ok(resource) = resource {
         resource.into()
     } else {
         MetaTags::default()
@@ -25,10 +43,13 @@ pub async fn single_page(
     let script = format!("<script>{}</script>", appstate.config.opts.script);
     let body = template
         .replace("<!-- { inject_html_head } -->", &meta_tags.to_string())
-        .replace("<!-- { inject_script } -->", &script);
+        .replace("<!-- { inject_script } -->", &script)
+        .replace("<!-- { inject_html_body } -->", &rendered_body);
 
     let resp = HttpResponse::Ok()
         .content_type("text/html")
+        // Other Accept headers get other representations of the resource
+        .insert_header(("Vary", "Accept, Accept-Language"))
         // This prevents the browser from displaying the JSON response upon re-opening a closed tab
         // https://github.com/atomicdata-dev/atomic-server/issues/137
         .insert_header((
@@ -41,6 +62,7 @@ pub async fn single_page(
 }
 
 use atomic_lib::agents::ForAgent;
+use atomic_lib::html::{render_resource_html, value_text};
 use atomic_lib::urls;
 use atomic_lib::Resource;
 use atomic_lib::Storelike;
@@ -56,12 +78,12 @@ struct MetaTags {
 impl From<Resource> for MetaTags {
     fn from(r: Resource) -> Self {
         let description = if let Ok(d) = r.get(urls::DESCRIPTION) {
-            d.to_string()
+            value_text(d)
         } else {
             "Open this resource in your browser to view its contents.".to_string()
         };
         let title = if let Ok(d) = r.get(urls::NAME) {
-            d.to_string()
+            value_text(d)
         } else {
             "Atomic Server".to_string()
         };
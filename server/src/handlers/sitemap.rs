@@ -0,0 +1,177 @@
+//! Helps search engines find public Resources: `/sitemap.xml` lists them, `/robots.txt` points crawlers to the sitemap.
+//! Only Resources that the Public Agent can read are listed. See [atomic_lib::hierarchy::public_resources].
+//! Finding these requires a scan of the whole store, so the result is kept in a [SitemapCache] until the next Commit.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use crate::{appstate::AppState, errors::AtomicServerResult};
+use actix_web::{web, HttpResponse};
+use atomic_lib::{
+    errors::AtomicError, hierarchy::public_resources, html::escape_html, urls, Resource, Storelike,
+};
+use chrono::TimeZone;
+use serde::Deserialize;
+
+const SITEMAP_NS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+const XML_MIME: &str = "application/xml";
+
+/// A Resource listed in the sitemap.
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub subject: String,
+    /// W3C Datetime of the last Commit
+    pub lastmod: Option<String>,
+}
+
+/// The entries, and the generation of the cache they were built in.
+type CachedEntries = Option<(u64, Arc<Vec<SitemapEntry>>)>;
+
+/// The public Resources of the store, built on the first request and cleared by every Commit.
+/// Cloning shares the cache.
+#[derive(Clone, Default)]
+pub struct SitemapCache {
+    /// Incremented by every Commit
+    generation: Arc<AtomicU64>,
+    /// Held while building, so concurrent requests wait for a single scan.
+    entries: Arc<Mutex<CachedEntries>>,
+}
+
+impl SitemapCache {
+    /// Marks the cache as outdated. Does not wait for a scan that is in progress, so it can be called while applying Commits.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the cached entries, or scans the store if a Commit was applied since they were built.
+    pub fn get(&self, store: &impl Storelike) -> AtomicServerResult<Arc<Vec<SitemapEntry>>> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|e| format!("Sitemap cache is poisoned: {}", e))?;
+        let generation = self.generation.load(Ordering::SeqCst);
+        if let Some((built_in, cached)) = entries.as_ref() {
+            if *built_in == generation {
+                return Ok(cached.clone());
+            }
+        }
+        let built: Arc<Vec<SitemapEntry>> = Arc::new(
+            public_resources(store)
+                .map(|resource| SitemapEntry {
+                    lastmod: last_modified(store, &resource),
+                    subject: resource.get_subject().clone(),
+                })
+                .collect(),
+        );
+        *entries = Some((generation, built.clone()));
+        Ok(built)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SitemapQuery {
+    /// Number of the page of the sitemap, starting at 1. Only needed if the sitemap index lists multiple pages.
+    pub page: Option<usize>,
+}
+
+/// Responds with a sitemap of all public Resources.
+/// If there are more Resources than fit on one page (`--sitemap-page-size`), responds with a sitemap index that links to the pages.
+#[tracing::instrument(skip(appstate))]
+pub async fn get_sitemap(
+    appstate: web::Data<AppState>,
+    params: web::Query<SitemapQuery>,
+) -> AtomicServerResult<HttpResponse> {
+    let page_size = appstate.config.opts.sitemap_page_size.max(1);
+    let entries = appstate.sitemap.get(&appstate.store)?;
+    let body = match params.page {
+        Some(page) => {
+            let start = page.saturating_sub(1).saturating_mul(page_size);
+            if page == 0 || (page > 1 && start >= entries.len()) {
+                return Err(
+                    AtomicError::not_found(format!("Sitemap page {} not found", page)).into(),
+                );
+            }
+            url_set(&entries[start..entries.len().min(start + page_size)])
+        }
+        None => {
+            if entries.len() <= page_size {
+                url_set(&entries)
+            } else {
+                sitemap_index(
+                    &appstate.config.server_url,
+                    entries.len().div_ceil(page_size),
+                )
+            }
+        }
+    };
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", XML_MIME))
+        .body(body))
+}
+
+/// Responds with the file at `--robots-txt`, or by default allows all crawlers and links to the sitemap.
+#[tracing::instrument(skip(appstate))]
+pub async fn get_robots(appstate: web::Data<AppState>) -> AtomicServerResult<HttpResponse> {
+    let body = match &appstate.config.opts.robots_txt {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read robots.txt file {:?}: {}", path, e))?,
+        None => format!(
+            "User-agent: *\nAllow: /\n\nSitemap: {}/sitemap.xml\n",
+            appstate.config.server_url
+        ),
+    };
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "text/plain; charset=utf-8"))
+        .body(body))
+}
+
+fn url_set(entries: &[SitemapEntry]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"{}\">\n",
+        SITEMAP_NS
+    );
+    for entry in entries {
+        xml.push_str(&format!(
+            "  <url>\n    <loc>{}</loc>\n",
+            escape_html(&entry.subject)
+        ));
+        if let Some(lastmod) = &entry.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn sitemap_index(server_url: &str, pages: usize) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<sitemapindex xmlns=\"{}\">\n",
+        SITEMAP_NS
+    );
+    for page in 1..=pages {
+        xml.push_str(&format!(
+            "  <sitemap>\n    <loc>{}/sitemap.xml?page={}</loc>\n  </sitemap>\n",
+            escape_html(server_url),
+            page
+        ));
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+/// The `createdAt` of the last Commit of the Resource, as a W3C Datetime.
+fn last_modified(store: &impl Storelike, resource: &Resource) -> Option<String> {
+    let last_commit = resource.get(urls::LAST_COMMIT).ok()?.to_string();
+    let created_at = store
+        .get_resource(&last_commit)
+        .ok()?
+        .get(urls::CREATED_AT)
+        .ok()?
+        .to_int()
+        .ok()?;
+    let date = chrono::Utc.timestamp_millis_opt(created_at).single()?;
+    Some(date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
@@ -8,7 +8,10 @@ use atomic_lib::{
 use futures::{StreamExt, TryStreamExt};
 use serde::Deserialize;
 
-use crate::{appstate::AppState, errors::AtomicServerResult, helpers::get_client_agent};
+use crate::{
+    appstate::AppState, backups::PARTIAL_SUFFIX, errors::AtomicServerResult,
+    helpers::get_client_agent,
+};
 
 #[derive(Deserialize, Debug)]
 pub struct UploadQuery {
@@ -58,9 +61,13 @@ pub async fn upload_handler(
                 .replace(' ', "-")
         );
 
-        let mut file_path = appstate.config.uploads_path.clone();
-        file_path.push(&file_id);
-        let mut file = std::fs::File::create(file_path)?;
+        let file_path = appstate.config.uploads_path.join(&file_id);
+        // The file gets its name when its File resource is saved, so backups never contain only one of them
+        let partial_path = appstate
+            .config
+            .uploads_path
+            .join(format!("{}{}", file_id, PARTIAL_SUFFIX));
+        let mut file = std::fs::File::create(&partial_path)?;
 
         // Field in turn is stream of *Bytes* object
         while let Some(chunk) = field.next().await {
@@ -92,7 +99,19 @@ pub async fn upload_handler(
             )?
             .set_string(urls::FILENAME.into(), filename, store)?
             .set_string(urls::DOWNLOAD_URL.into(), &download_url, store)?;
-        commit_responses.push(resource.save(store)?);
+        drop(file);
+        let saved = store.hold_snapshots(|| {
+            std::fs::rename(&partial_path, &file_path)?;
+            resource.save(store)
+        });
+        match saved {
+            Ok(response) => commit_responses.push(response),
+            Err(e) => {
+                let _try_remove_file = std::fs::remove_file(&partial_path);
+                let _try_remove_file = std::fs::remove_file(&file_path);
+                return Err(e.into());
+            }
+        }
         created_resources.push(resource);
     }
 
@@ -13,8 +13,9 @@ use atomic_lib::{
     agents::ForAgent,
     authentication::{get_agent_from_auth_values_and_check, AuthValues},
     errors::AtomicResult,
-    Db, Storelike,
+    Db, Resource, Storelike,
 };
+use serde::Deserialize;
 use std::time::{Duration, Instant};
 
 use crate::{
@@ -22,12 +23,19 @@ use crate::{
     errors::AtomicServerResult, helpers::get_auth_headers,
 };
 
+#[derive(Deserialize, Debug)]
+pub struct WebSocketParams {
+    /// Use `cbor` to receive `COMMIT` and `RESOURCE` messages as binary JSON-AD.
+    pub format: Option<String>,
+}
+
 /// Get an HTTP request, upgrade it to a Websocket connection
 #[tracing::instrument(skip(appstate, stream))]
 pub async fn web_socket_handler(
     req: HttpRequest,
     stream: web::Payload,
     appstate: web::Data<AppState>,
+    params: web::Query<WebSocketParams>,
 ) -> AtomicServerResult<HttpResponse> {
     // Authentication check. If the user has no headers, continue with the Public Agent.
     let auth_header_values = get_auth_headers(req.headers(), "ws".into())?;
@@ -43,6 +51,7 @@ pub async fn web_socket_handler(
             for_agent,
             // We need to make sure this is easily clone-able
             appstate.store.clone(),
+            params.format.as_deref() == Some("cbor"),
         ),
         &req,
         stream,
@@ -65,6 +74,8 @@ pub struct WebSocketConnection {
     /// If it's not specified, it's the Public Agent.
     agent: ForAgent,
     store: Db,
+    /// Sends Resources as binary JSON-AD instead of JSON-AD text.
+    binary: bool,
 }
 
 impl Actor for WebSocketConnection {
@@ -136,18 +147,10 @@ fn handle_ws_message(
                             .store
                             .get_resource_extended(subject, false, &conn.agent)
                         {
-                            Ok(r) => {
-                                let serialized =
-                                    r.to_json_ad().expect("Can't serialize Resource to JSON-AD");
-                                ctx.text(format!("RESOURCE {serialized}"));
-                                Ok(())
-                            }
+                            Ok(r) => send_resource(ctx, conn.binary, "RESOURCE", &r),
                             Err(e) => {
                                 let r = e.into_resource(subject.into());
-                                let serialized_err =
-                                    r.to_json_ad().expect("Can't serialize Resource to JSON-AD");
-                                ctx.text(format!("RESOURCE {serialized_err}"));
-                                Ok(())
+                                send_resource(ctx, conn.binary, "RESOURCE", &r)
                             }
                         }
                     } else {
@@ -198,8 +201,31 @@ fn handle_ws_message(
     }
 }
 
+/// Sends a message that contains a Resource, such as `COMMIT {resource}`.
+/// Binary messages start with the same prefix, followed by binary JSON-AD.
+fn send_resource(
+    ctx: &mut ws::WebsocketContext<WebSocketConnection>,
+    binary: bool,
+    prefix: &str,
+    resource: &Resource,
+) -> AtomicResult<()> {
+    if binary {
+        let mut message = format!("{prefix} ").into_bytes();
+        message.extend(resource.to_json_ad_cbor()?);
+        ctx.binary(message);
+    } else {
+        ctx.text(format!("{prefix} {}", resource.to_json_ad()?));
+    }
+    Ok(())
+}
+
 impl WebSocketConnection {
-    fn new(commit_monitor_addr: Addr<CommitMonitor>, agent: ForAgent, store: Db) -> Self {
+    fn new(
+        commit_monitor_addr: Addr<CommitMonitor>,
+        agent: ForAgent,
+        store: Db,
+        binary: bool,
+    ) -> Self {
         let size = std::mem::size_of::<Db>();
         if size > 10000 {
             tracing::warn!(
@@ -215,6 +241,7 @@ impl WebSocketConnection {
             commit_monitor_addr,
             agent,
             store,
+            binary,
         }
     }
 
@@ -246,7 +273,8 @@ impl Handler<CommitMessage> for WebSocketConnection {
     #[tracing::instrument(name = "handle_commit", skip_all)]
     fn handle(&mut self, msg: CommitMessage, ctx: &mut ws::WebsocketContext<Self>) {
         let resource = msg.commit_response.commit_resource;
-        let formatted_commit = format!("COMMIT {}", resource.to_json_ad().unwrap());
-        ctx.text(formatted_commit);
+        if let Err(e) = send_resource(ctx, self.binary, "COMMIT", &resource) {
+            tracing::error!("Failed to send Commit over WebSocket: {}", e);
+        }
     }
 }
@@ -5,12 +5,16 @@ use actix_web::http::header::{HeaderMap, HeaderValue};
 use actix_web::http::Uri;
 use atomic_lib::agents::ForAgent;
 use atomic_lib::authentication::AuthValues;
-use atomic_lib::AtomicError;
+use atomic_lib::{AtomicError, Resource, Storelike};
 use percent_encoding::percent_decode_str;
 use std::str::FromStr;
 
 use crate::errors::{AppErrorType, AtomicServerError};
-use crate::{appstate::AppState, content_types::ContentType, errors::AtomicServerResult};
+use crate::{
+    appstate::AppState,
+    content_types::{get_accept, ContentType},
+    errors::AtomicServerResult,
+};
 
 /// Returns the authentication headers from the request
 #[tracing::instrument(skip_all)]
@@ -183,22 +187,53 @@ pub fn get_client_agent(
     Ok(for_agent)
 }
 
-/// Finds the extension
+/// Returns a Resource (e.g. a Class) that is stored on this server, if the Agent is allowed to read it.
+/// The subject comes from the request, so it is never fetched from elsewhere.
+pub fn get_readable_local(
+    appstate: &AppState,
+    subject: &str,
+    for_agent: &ForAgent,
+) -> AtomicServerResult<Resource> {
+    let resource = appstate.store.get_resource_local(subject)?;
+    atomic_lib::hierarchy::check_read(&appstate.store, &resource, for_agent)?;
+    Ok(resource)
+}
+
+/// Finds a known extension (e.g. `.jsonad` or `.ttl`) at the end of the path.
+/// Returns the content type and the path without the extension.
+/// Dots in earlier path segments are ignored, so `docs/v1.2/page.ttl` works.
 pub fn try_extension(path: &str) -> Option<(ContentType, &str)> {
-    let items: Vec<&str> = path.split('.').collect();
-    if items.len() == 2 {
-        let path = items[0];
-        let content_type = match items[1] {
-            "json" => ContentType::Json,
-            "jsonld" => ContentType::JsonLd,
-            "jsonad" => ContentType::JsonAd,
-            "html" => ContentType::Html,
-            "ttl" => ContentType::Turtle,
-            _ => return None,
-        };
-        return Some((content_type, path));
+    let last_segment = path.rsplit('/').next().unwrap_or(path);
+    let (name, extension) = last_segment.rsplit_once('.')?;
+    if name.is_empty() {
+        return None;
     }
-    None
+    let content_type = match extension.to_ascii_lowercase().as_str() {
+        "json" => ContentType::Json,
+        "jsonld" => ContentType::JsonLd,
+        "jsonad" => ContentType::JsonAd,
+        "cbor" => ContentType::JsonAdCbor,
+        "html" => ContentType::Html,
+        "ttl" => ContentType::Turtle,
+        "nt" => ContentType::NTriples,
+        "rdf" => ContentType::RdfXml,
+        "trig" => ContentType::TriG,
+        _ => return None,
+    };
+    Some((content_type, &path[..path.len() - extension.len() - 1]))
+}
+
+/// Determines the content type of the response for a request path.
+/// An extension in the path takes precedence over the Accept header, see [try_extension].
+/// Returns the path without the extension.
+pub fn get_content_type<'a>(
+    path: &'a str,
+    headers: &HeaderMap,
+) -> AtomicServerResult<(ContentType, &'a str)> {
+    if let Some((content_type, path)) = try_extension(path) {
+        return Ok((content_type, path));
+    }
+    Ok((get_accept(headers)?, path))
 }
 
 fn session_cookies_from_header(header: &HeaderValue) -> AtomicServerResult<Vec<String>> {
@@ -295,4 +330,19 @@ mod test {
 
         assert_eq!(out.requested_subject, subject);
     }
+
+    #[test]
+    fn extensions() {
+        assert!(matches!(
+            try_extension("things/my-thing.jsonad"),
+            Some((ContentType::JsonAd, "things/my-thing"))
+        ));
+        assert!(matches!(
+            try_extension("docs/v1.2/page.TTL"),
+            Some((ContentType::Turtle, "docs/v1.2/page"))
+        ));
+        assert!(try_extension("docs/v1.2/page").is_none());
+        assert!(try_extension("file.png").is_none());
+        assert!(try_extension(".ttl").is_none());
+    }
 }
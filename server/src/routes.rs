@@ -1,7 +1,7 @@
 //! Contains routing logic, sends the client to the correct handler.
 //! We should try to minimize what happens in here, since most logic should be defined in Atomic Data - not in the server itself.
 
-use crate::{content_types, handlers};
+use crate::{content_types, handlers, helpers};
 use actix_web::{guard, http::Method, web};
 use actix_web_static_files::ResourceFiles;
 
@@ -20,6 +20,19 @@ include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 pub fn config_routes(app: &mut actix_web::web::ServiceConfig) {
     app.service(web::resource("/ws").to(handlers::web_sockets::web_socket_handler))
         .service(web::resource("/download/{path:[^{}]+}").to(handlers::download::handle_download))
+        .service(
+            web::resource("/csv")
+                .route(web::get().to(handlers::csv::export_csv))
+                .route(web::post().to(handlers::csv::import_csv)),
+        )
+        .service(
+            web::resource("/json-schema")
+                .route(web::get().to(handlers::json_schema::get_json_schema))
+                .route(web::post().to(handlers::json_schema::validate_json_schema)),
+        )
+        .service(web::resource("/shacl").route(web::get().to(handlers::shacl::get_shacl)))
+        .service(web::resource("/sitemap.xml").route(web::get().to(handlers::sitemap::get_sitemap)))
+        .service(web::resource("/robots.txt").route(web::get().to(handlers::sitemap::get_robots)))
         // This `generate` imports the static files from the `app_assets` folder
         .service(
             ResourceFiles::new("/", generate())
@@ -31,8 +44,11 @@ pub fn config_routes(app: &mut actix_web::web::ServiceConfig) {
             web::resource(ANY)
                 .guard(guard::Method(Method::GET))
                 .guard(guard::fn_guard(|guard_ctx| {
-                    content_types::get_accept(guard_ctx.head().headers())
-                        == content_types::ContentType::Html
+                    let head = guard_ctx.head();
+                    matches!(
+                        helpers::get_content_type(head.uri.path(), &head.headers),
+                        Ok((content_types::ContentType::Html, _))
+                    )
                 }))
                 .to(handlers::single_page_app::single_page),
         )
@@ -239,6 +239,12 @@ fn get_resource_title(resource: &Resource) -> String {
     match title {
         atomic_lib::Value::String(s) => s,
         atomic_lib::Value::Slug(s) => s,
+        // Makes the title searchable in every language
+        atomic_lib::Value::LangString(texts) => texts
+            .iter()
+            .map(|(_, text)| text)
+            .collect::<Vec<&str>>()
+            .join(" "),
         _ => resource.get_subject().to_string(),
     }
 }
@@ -43,6 +43,9 @@ pub async fn serve(config: crate::config::Config) -> AtomicServerResult<()> {
     if config.opts.rebuild_indexes {
         rebuild_indexes(&appstate)?;
     }
+    if let Some(interval) = config.opts.backup_interval {
+        crate::backups::spawn_backup_scheduler(&appstate, interval);
+    }
 
     let server = HttpServer::new(move || {
         let cors = Cors::permissive();
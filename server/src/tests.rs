@@ -12,7 +12,7 @@ use actix_web::{
     web::Data,
     App,
 };
-use atomic_lib::{urls, Storelike};
+use atomic_lib::{urls, Resource, Storelike, Value};
 
 /// Returns the request with signed headers. Also adds a json-ad accept header - overwrite this if you need something else.
 fn build_request_authenticated(path: &str, appstate: &AppState) -> TestRequest {
@@ -65,8 +65,7 @@ async fn server_tests() {
     //         .expect("could not fetch drive");
 
     // Get HTML page
-    let req =
-        build_request_authenticated("/", &appstate).insert_header(("Accept", "application/html"));
+    let req = build_request_authenticated("/", &appstate).insert_header(("Accept", "text/html"));
     let resp = test::call_service(&app, req.to_request()).await;
     let is_success = resp.status().is_success();
     let body = get_body(resp);
@@ -140,10 +139,249 @@ async fn server_tests() {
     assert!(resp.status().is_success());
     let body = get_body(resp);
     assert!(
-        body.as_str().starts_with("<htt"),
+        body.as_str().starts_with("@prefix "),
         "response should be turtle"
     );
 
+    // Quality values and Vary header
+    let req = build_request_authenticated("/properties", &appstate)
+        .insert_header(("Accept", "application/json;q=0.9, application/ad+json"));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    assert_eq!(
+        resp.headers().get("Vary").unwrap(),
+        "Accept, Accept-Language"
+    );
+    assert_eq!(
+        resp.headers().get("Content-Type").unwrap(),
+        "application/ad+json"
+    );
+
+    // Language-tagged strings in the language of the client
+    let title = atomic_lib::schema::Property {
+        class_type: None,
+        data_type: atomic_lib::datatype::DataType::LangString,
+        shortname: "title".into(),
+        description: "A translated title".into(),
+        subject: format!("{}/properties/title", appstate.config.server_url),
+        allows_only: None,
+        constraints: Default::default(),
+        default: None,
+    };
+    store.add_resource(&title.to_resource()).unwrap();
+    let mut greeting = Resource::new(format!("{}/greeting", appstate.config.server_url));
+    greeting
+        .set_string(
+            title.subject.clone(),
+            r#"{"en": "Hello", "nl": "Hallo"}"#,
+            store,
+        )
+        .unwrap();
+    greeting
+        .set_string(urls::PARENT.into(), &appstate.config.server_url, store)
+        .unwrap();
+    store.add_resource(&greeting).unwrap();
+    let req = build_request_authenticated("/greeting", &appstate)
+        .insert_header(("Accept", "application/json"))
+        .insert_header(("Accept-Language", "nl-NL, en;q=0.5"));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = serde_json::from_str(&get_body(resp)).unwrap();
+    assert_eq!(body["title"], serde_json::json!({"nl": "Hallo"}));
+    // JSON-AD is used for editing, so it keeps all languages
+    let req = build_request_authenticated("/greeting", &appstate)
+        .insert_header(("Accept", "application/ad+json"))
+        .insert_header(("Accept-Language", "nl-NL, en;q=0.5"));
+    let resp = test::call_service(&app, req.to_request()).await;
+    let body: serde_json::Value = serde_json::from_str(&get_body(resp)).unwrap();
+    assert_eq!(
+        body[&title.subject],
+        serde_json::json!({"en": "Hello", "nl": "Hallo"})
+    );
+    // RDF keeps all languages
+    let req = build_request_authenticated("/greeting", &appstate)
+        .insert_header(("Accept", "text/turtle"))
+        .insert_header(("Accept-Language", "nl"));
+    let resp = test::call_service(&app, req.to_request()).await;
+    let body = get_body(resp);
+    assert!(body.contains(r#""Hello"@en, "Hallo"@nl"#), "{}", body);
+
+    // Extensions take precedence over the Accept header
+    let req = build_request_authenticated("/properties", &appstate).uri("/properties.ttl");
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    assert!(get_body(resp).as_str().starts_with("@prefix "));
+
+    // Get binary JSON-AD
+    let req = build_request_authenticated("/properties", &appstate)
+        .insert_header(("Accept", "application/ad+cbor"));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    let body = resp.into_body().try_into_bytes().unwrap();
+    let parsed = atomic_lib::parse::parse_json_ad_cbor(
+        &body,
+        store,
+        &atomic_lib::parse::ParseOpts {
+            save: atomic_lib::parse::SaveOpts::DontSave,
+            ..Default::default()
+        },
+    )
+    .expect("response should be binary JSON-AD");
+    assert_eq!(
+        parsed[0].get_subject(),
+        &format!("{}/properties", appstate.config.server_url)
+    );
+
+    // Should 406 (Not Acceptable)
+    let req = build_request_authenticated("/properties", &appstate)
+        .insert_header(("Accept", "image/png"));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert_eq!(resp.status().as_u16(), 406);
+
+    // Get the JSON Schema of a Class
+    let req = test::TestRequest::with_uri(&format!(
+        "/json-schema?class={}&shape=json",
+        urlencoding::encode(urls::PROPERTY)
+    ));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    let schema: serde_json::Value = serde_json::from_str(&get_body(resp)).unwrap();
+    assert_eq!(schema["title"], "property");
+
+    // Validate JSON against it
+    let req = test::TestRequest::post()
+        .uri(&format!(
+            "/json-schema?class={}&shape=json",
+            urlencoding::encode(urls::PROPERTY)
+        ))
+        .set_payload(r#"{"shortname": "Not A Slug"}"#);
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    let report: serde_json::Value = serde_json::from_str(&get_body(resp)).unwrap();
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["errors"].as_array().unwrap().len(), 3);
+
+    // Classes are not fetched from other servers
+    let req = test::TestRequest::with_uri(&format!(
+        "/json-schema?class={}",
+        urlencoding::encode("https://unknown.invalid/class")
+    ));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert_eq!(resp.status().as_u16(), 404);
+
+    // Private Classes need an agent with read rights
+    let private_class = format!("{}/private-class", appstate.config.server_url);
+    let mut class = Resource::new(private_class.clone());
+    class
+        .set(
+            urls::PARENT.into(),
+            Value::AtomicUrl(appstate.config.server_url.clone()),
+            store,
+        )
+        .unwrap();
+    class
+        .set(urls::IS_A.into(), vec![urls::CLASS].into(), store)
+        .unwrap();
+    class
+        .set(urls::SHORTNAME.into(), Value::Slug("private".into()), store)
+        .unwrap();
+    class
+        .set(
+            urls::DESCRIPTION.into(),
+            Value::Markdown("secret".into()),
+            store,
+        )
+        .unwrap();
+    class.save(store).unwrap();
+    let path = format!("/json-schema?class={}", urlencoding::encode(&private_class));
+    let resp = test::call_service(&app, test::TestRequest::with_uri(&path).to_request()).await;
+    assert!(resp.status().is_client_error());
+    let req = build_request_authenticated(&path, &appstate);
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+
+    // Get the SHACL shapes of a Class
+    let req = test::TestRequest::with_uri(&format!(
+        "/shacl?class={}",
+        urlencoding::encode(urls::PROPERTY)
+    ));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    assert!(get_body(resp).contains("sh:targetClass atomic-classes:Property"));
+    let path = format!("/shacl?class={}", urlencoding::encode(&private_class));
+    let resp = test::call_service(&app, test::TestRequest::with_uri(&path).to_request()).await;
+    assert!(resp.status().is_client_error());
+    let req = test::TestRequest::with_uri(&format!(
+        "/shacl?ontology={}",
+        urlencoding::encode("https://unknown.invalid/ontology")
+    ));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert_eq!(resp.status().as_u16(), 404);
+
+    // CSV exports only use readable Classes from this server
+    let req = test::TestRequest::with_uri(&format!(
+        "/csv?class={}",
+        urlencoding::encode(urls::PROPERTY)
+    ));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    assert!(get_body(resp).starts_with("@id,"));
+    let req = test::TestRequest::with_uri(&format!(
+        "/csv?class={}",
+        urlencoding::encode("https://unknown.invalid/class")
+    ));
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert_eq!(resp.status().as_u16(), 404);
+    let path = format!("/csv?class={}", urlencoding::encode(&private_class));
+    let resp = test::call_service(&app, test::TestRequest::with_uri(&path).to_request()).await;
+    assert!(resp.status().is_client_error());
+
+    // The sitemap lists public resources, with the date of their last commit
+    let public_subject = format!("{}/public-page", appstate.config.server_url);
+    let mut public_page = Resource::new(public_subject.clone());
+    public_page
+        .set(
+            urls::PARENT.into(),
+            Value::AtomicUrl(appstate.config.server_url.clone()),
+            store,
+        )
+        .unwrap();
+    public_page
+        .set(urls::READ.into(), vec![urls::PUBLIC_AGENT].into(), store)
+        .unwrap();
+    public_page.save(store).unwrap();
+    let req = test::TestRequest::with_uri("/sitemap.xml");
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(resp.status().is_success());
+    let body = get_body(resp);
+    assert!(body.contains(&format!("<loc>{}</loc>\n    <lastmod>", public_subject)));
+    // The drive was made private above
+    assert!(!body.contains(&format!("<loc>{}</loc>", appstate.config.server_url)));
+    assert!(!body.contains("/commits/"));
+    // The cached sitemap is updated by Commits
+    let second_subject = format!("{}/public-page-2", appstate.config.server_url);
+    let mut second_page = Resource::new(second_subject.clone());
+    second_page
+        .set(
+            urls::PARENT.into(),
+            Value::AtomicUrl(public_subject.clone()),
+            store,
+        )
+        .unwrap();
+    second_page.save(store).unwrap();
+    let req = test::TestRequest::with_uri("/sitemap.xml");
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(get_body(resp).contains(&format!("<loc>{}</loc>", second_subject)));
+    let req = test::TestRequest::with_uri("/sitemap.xml?page=99");
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert_eq!(resp.status().as_u16(), 404);
+    let req = test::TestRequest::with_uri("/robots.txt");
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert!(get_body(resp).contains(&format!(
+        "Sitemap: {}/sitemap.xml",
+        appstate.config.server_url
+    )));
+
     // Get Search
     // Does not test the contents of the results - the index isn't built at this point
     let req = build_request_authenticated("/search?q=setup", &appstate);